@@ -0,0 +1,92 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::io::{BufReader, Read};
+use test::{black_box, Bencher};
+use read_exact::{ExactBufReader, MemReadExactExt, ReadExactExt};
+
+const RECORDS: usize = 1024;
+const RECORD_LEN: usize = 64;
+
+fn data() -> Vec<u8> {
+    (0..RECORDS * RECORD_LEN).map(|i| i as u8).collect()
+}
+
+#[bench]
+fn read_exact(b: &mut Bencher) {
+    let data = data();
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut read = &data[..];
+        let mut buf = [0; RECORD_LEN];
+        for _ in 0..RECORDS {
+            read.read_exact(&mut buf).unwrap();
+            black_box(&buf);
+        }
+    })
+}
+
+#[bench]
+fn read_exact_or_eof(b: &mut Bencher) {
+    let data = data();
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut read = &data[..];
+        let mut buf = [0; RECORD_LEN];
+        while read.read_exact_or_eof(&mut buf).unwrap() {
+            black_box(&buf);
+        }
+    })
+}
+
+#[bench]
+fn read_exact_or_eof_buffered(b: &mut Bencher) {
+    let data = data();
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut read = BufReader::new(&data[..]);
+        let mut buf = [0; RECORD_LEN];
+        while read.read_exact_or_eof(&mut buf).unwrap() {
+            black_box(&buf);
+        }
+    })
+}
+
+#[bench]
+fn page_records_buffered(b: &mut Bencher) {
+    let data = vec![0; 0x100000];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut read = BufReader::new(&data[..]);
+        let mut buf = [0; 0x1000];
+        while read.read_exact_or_eof(&mut buf).unwrap() {
+            black_box(&buf);
+        }
+    })
+}
+
+#[bench]
+fn page_records_exact_buf(b: &mut Bencher) {
+    let data = vec![0; 0x100000];
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut read = ExactBufReader::with_record_size(&data[..], 0x1000, 4);
+        while let Some(record) = read.next_record().unwrap() {
+            black_box(record);
+        }
+    })
+}
+
+#[bench]
+fn copy_exact_or_eof(b: &mut Bencher) {
+    let data = data();
+    b.bytes = data.len() as u64;
+    b.iter(|| {
+        let mut read = &data[..];
+        let mut buf = [0; RECORD_LEN];
+        while read.copy_exact_or_eof(&mut buf).unwrap() {
+            black_box(&buf);
+        }
+    })
+}
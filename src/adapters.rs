@@ -0,0 +1,586 @@
+use crate::{io, ReadExactOpts};
+#[cfg(feature = "std")]
+use crate::ReadExactExt;
+use crate::opts::OptsControl;
+use crate::source::{fill_source, IoSource, SliceBuf};
+
+/// A reader whose every read either fills the whole buffer or reaches EOF.
+///
+/// Each `read` behaves like `ReadExactExt::read_exact_or_eof`, returning
+/// `buf.len()` once `buf` is full, `0` at a clean EOF, or an error after a
+/// partial read. This carries the guarantee through APIs that only accept a
+/// `Read`.
+#[derive(Debug)]
+pub struct ExactOrEof<R> {
+    reader: R,
+    opts: ReadExactOpts,
+}
+
+impl<R> ExactOrEof<R> {
+    /// Wraps a reader.
+    pub fn new(reader: R) -> Self {
+        Self::with_opts(reader, ReadExactOpts::new())
+    }
+
+    /// Wraps a reader, reading with `opts` as in
+    /// `ReadExactExt::read_exact_or_eof_with`.
+    ///
+    /// This applies options such as `ReadExactOpts::zero_read_retries` to
+    /// every read through the wrapper.
+    pub fn with_opts(reader: R, opts: ReadExactOpts) -> Self {
+        ExactOrEof {
+            reader,
+            opts,
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::Read> io::Read for ExactOrEof<R> {
+    /// An `Interrupted` error partway through `buf`, once
+    /// `ReadExactOpts::max_interrupts` is exceeded, becomes an `Other` error
+    /// wrapping it, since a caller retrying the read would lose the bytes
+    /// already read.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len();
+        let mut buf = SliceBuf::new(buf);
+        let ret = fill_source(&mut IoSource(&mut self.reader), &mut buf, &mut OptsControl::new(&self.opts, &[]));
+
+        match ret {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted && buf.filled() > 0 => Err(interrupted_record(e)),
+            ret => ret.and_then(|()| buf.status().into_result())
+                .map(|full| if full { len } else { 0 }),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn interrupted_record(e: io::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+#[cfg(not(feature = "std"))]
+fn interrupted_record(_: io::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "interrupted partway through a record")
+}
+
+/// A reader that reports any short read from another reader as an error.
+///
+/// This is a debugging aid for tracking down `Read` implementations that
+/// return less than was asked of them. Each `read` of the underlying reader
+/// must either fill the whole buffer or return EOF, and anything else fails
+/// with `UnexpectedEof` describing the call. Reads that fail with
+/// `Interrupted` are passed through.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct AssertExact<R> {
+    reader: R,
+    calls: u64,
+    panic: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R> AssertExact<R> {
+    /// Wraps a reader.
+    pub fn new(reader: R) -> Self {
+        AssertExact {
+            reader,
+            calls: 0,
+            panic: false,
+        }
+    }
+
+    /// Sets whether a short read panics instead of failing, when built with
+    /// debug assertions.
+    pub fn panic_in_debug(mut self, panic: bool) -> Self {
+        self.panic = panic;
+        self
+    }
+
+    /// Returns the number of reads issued so far.
+    pub fn calls(&self) -> u64 {
+        self.calls
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> io::Read for AssertExact<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let call = self.calls;
+        self.calls += 1;
+
+        let n = self.reader.read(buf)?;
+        if n == 0 || n == buf.len() {
+            return Ok(n)
+        }
+
+        let message = format!("short read on call {}: expected {} bytes, got {}", call, buf.len(), n);
+        if self.panic && cfg!(debug_assertions) {
+            panic!("{}", message)
+        }
+        Err(io::Error::new(io::ErrorKind::UnexpectedEof, message))
+    }
+}
+
+/// A reader that bytes can be pushed back onto.
+///
+/// This allows a header or record to be read speculatively and handed back
+/// untouched, as when it turns out to belong to another parser, without
+/// requiring the reader to implement `Seek`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Unread<R> {
+    reader: R,
+    pending: std::collections::VecDeque<u8>,
+}
+
+#[cfg(feature = "std")]
+impl<R> Unread<R> {
+    /// Wraps a reader.
+    pub fn new(reader: R) -> Self {
+        Unread {
+            reader,
+            pending: Default::default(),
+        }
+    }
+
+    /// Pushes `buf` back onto the front of the stream, so that it is read
+    /// again before anything else, including bytes unread earlier.
+    pub fn unread(&mut self, buf: &[u8]) {
+        for &byte in buf.iter().rev() {
+            self.pending.push_front(byte);
+        }
+    }
+
+    /// Returns the bytes that have been pushed back but not read again.
+    pub fn pending(&self) -> &std::collections::VecDeque<u8> {
+        &self.pending
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    ///
+    /// Any bytes that were pushed back are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> io::Read for Unread<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.pending.is_empty() {
+            true => self.reader.read(buf),
+            false => self.pending.read(buf),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::BufRead> io::BufRead for Unread<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self.pending.is_empty() {
+            true => self.reader.fill_buf(),
+            false => Ok(self.pending.as_slices().0),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self.pending.is_empty() {
+            true => self.reader.consume(amt),
+            false => drop(self.pending.drain(..amt.min(self.pending.len()))),
+        }
+    }
+}
+
+/// A reader whose next record can be examined before it is read.
+///
+/// This suits dispatchers that must look at a record before deciding which
+/// consumer gets the stream: `peek_exact_or_eof` buffers the bytes it reads,
+/// so that the following `read_exact_or_eof` returns the same record.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct PeekExact<R> {
+    reader: Unread<R>,
+}
+
+#[cfg(feature = "std")]
+impl<R> PeekExact<R> {
+    /// Wraps a reader.
+    pub fn new(reader: R) -> Self {
+        PeekExact {
+            reader: Unread::new(reader),
+        }
+    }
+
+    /// Returns the bytes that have been peeked but not read.
+    pub fn buffered(&self) -> &std::collections::VecDeque<u8> {
+        self.reader.pending()
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.reader.get_ref()
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.reader.get_mut()
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    ///
+    /// Any bytes that were peeked but not read are lost.
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> PeekExact<R> {
+    /// Copies exactly the number of bytes to fill `buf` without consuming
+    /// them, or returns `false` at EOF.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, except that every
+    /// byte read stays buffered, even when the reader fails or ends partway
+    /// through `buf`.
+    pub fn peek_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let mut buf = SliceBuf::new(buf);
+        let ret = fill_source(&mut IoSource(&mut self.reader), &mut buf, &mut OptsControl::new(&ReadExactOpts::new(), &[]));
+
+        self.reader.unread(buf.filled_bytes());
+        ret.and_then(|()| buf.status().into_result())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> io::Read for PeekExact<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::BufRead> io::BufRead for PeekExact<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.reader.consume(amt)
+    }
+}
+
+/// A reader that adds its label and the current offset to its errors.
+///
+/// Errors of the underlying reader are wrapped by the `Read` impl in a
+/// `LabeledError` with the same kind, so every method of `ReadExactExt`
+/// reports them with the label and offset. `Interrupted` and `WouldBlock`
+/// are passed through as they are, since they are retried or waited out
+/// rather than reported.
+///
+/// A truncated record is only detected once the reads have returned, so only
+/// `Labeled::read_exact_or_eof_labeled` labels its `UnexpectedEof`, which
+/// then reads like `segment-000123.dat @ 5242880: failed to fill whole
+/// buffer`. `ReadExactExt::read_exact_or_eof` and the other methods report a
+/// truncated record of a `Labeled` reader without its label.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Labeled<R> {
+    reader: R,
+    label: String,
+    offset: u64,
+}
+
+#[cfg(feature = "std")]
+impl<R> Labeled<R> {
+    /// Wraps a reader with the given label, such as a file name.
+    pub fn new<L: Into<String>>(reader: R, label: L) -> Self {
+        Labeled {
+            reader,
+            label: label.into(),
+            offset: 0,
+        }
+    }
+
+    /// Returns the label of this reader.
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn error(&self, offset: u64, source: io::Error) -> io::Error {
+        io::Error::new(source.kind(), LabeledError {
+            label: self.label.clone(),
+            offset,
+            source,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> Labeled<R> {
+    /// Reads exactly the number of bytes to fill `buf`, or zero, labeling a
+    /// truncated record.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, except that a
+    /// truncated record is also wrapped in a `LabeledError`, at the offset
+    /// the record started at.
+    pub fn read_exact_or_eof_labeled(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let start = self.offset;
+        match ReadExactExt::read_exact_or_eof_status(self, buf)? {
+            crate::ReadExactStatus::PartialEof { .. } => Err(self.error(start, crate::unexpected_eof())),
+            status => status.into_result(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> io::Read for Labeled<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.reader.read(buf) {
+            Ok(n) => {
+                self.offset += n as u64;
+                Ok(n)
+            },
+            Err(e) if matches!(e.kind(), io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock) => Err(e),
+            Err(e) => Err(self.error(self.offset, e)),
+        }
+    }
+}
+
+/// An error annotated with the label of its reader and the offset at which
+/// it occurred.
+///
+/// This is the inner error of the errors returned by `Labeled`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct LabeledError {
+    /// The label of the reader.
+    pub label: String,
+    /// The offset at which the error occurred.
+    pub offset: u64,
+    source: io::Error,
+}
+
+/// Returns the OS error code of `e`, looking through a `LabeledError`.
+#[cfg(all(feature = "std", any(windows, feature = "ffi", test)))]
+pub(crate) fn raw_os_error(e: &io::Error) -> Option<i32> {
+    match e.get_ref().and_then(|inner| inner.downcast_ref::<LabeledError>()) {
+        Some(labeled) => labeled.source.raw_os_error(),
+        None => e.raw_os_error(),
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for LabeledError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} @ {}: {}", self.label, self.offset, self.source)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LabeledError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::io::{BufRead, Read};
+    use crate::ReadExactExt;
+    use crate::testing::ScriptedReader;
+    use std::io;
+    use std::time::Duration;
+    use crate::ReadExactOpts;
+    use super::{AssertExact, ExactOrEof, Labeled, LabeledError, PeekExact, Unread};
+
+    #[test]
+    fn exact_or_eof() {
+        let mut read = ExactOrEof::new(ScriptedReader::new().data(&[1]).data(&[2, 3]));
+        let mut buf = [0, 0];
+
+        assert_eq!(read.read(&mut buf).unwrap(), 2);
+        assert_eq!(buf, [1, 2]);
+        assert!(read.read(&mut buf).is_err());
+        assert_eq!(read.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn exact_or_eof_opts() {
+        let opts = ReadExactOpts::new().zero_read_retries(1, Duration::ZERO);
+        let mut read = ExactOrEof::with_opts(ScriptedReader::new().data(&[1]).eof().data(&[2]), opts);
+
+        assert_eq!(read.read(&mut [0, 0]).unwrap(), 2);
+    }
+
+    #[test]
+    fn exact_or_eof_interrupted() {
+        let opts = ReadExactOpts::new().max_interrupts(Some(0));
+        let mut read = ExactOrEof::with_opts(ScriptedReader::new().error(io::ErrorKind::Interrupted).data(&[1]).error(io::ErrorKind::Interrupted), opts);
+        let mut buf = [0, 0];
+
+        assert_eq!(read.read(&mut buf).unwrap_err().kind(), io::ErrorKind::Interrupted);
+        let err = read.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert_eq!(err.get_ref().unwrap().downcast_ref::<io::Error>().unwrap().kind(), io::ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn assert_exact() {
+        let mut read = AssertExact::new(ScriptedReader::new().data(&[1, 2]).data(&[3]));
+        let mut buf = [0, 0];
+
+        assert_eq!(read.read(&mut buf).unwrap(), 2);
+        let err = read.read(&mut buf).unwrap_err();
+        assert_eq!(err.to_string(), "short read on call 1: expected 2 bytes, got 1");
+        assert_eq!(read.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic)]
+    fn assert_exact_panic() {
+        let mut read = AssertExact::new(&[1][..]).panic_in_debug(true);
+
+        let _ = read.read(&mut [0, 0]);
+    }
+
+    #[test]
+    fn unread() {
+        let mut read = Unread::new(&[3, 4, 5][..]);
+        let mut header = [0; 2];
+
+        assert!(read.read_exact_or_eof(&mut header).unwrap());
+        read.unread(&header);
+        read.unread(&[1, 2]);
+        assert_eq!(read.fill_buf().unwrap()[0], 1);
+        assert_eq!(read.pending(), &[1, 2, 3, 4]);
+        read.consume(1);
+        let mut buf = [0; 5];
+        assert!(read.read_exact_or_eof(&mut buf).is_err());
+        assert_eq!(buf[..4], [2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn peek_exact() {
+        let mut read = PeekExact::new(ScriptedReader::new().data(&[1]).error(io::ErrorKind::WouldBlock).data(&[2, 3]));
+        let mut buf = [0; 2];
+
+        assert_eq!(read.peek_exact_or_eof(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(read.buffered(), &[1]);
+        assert!(read.peek_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        buf = [0; 2];
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(read.peek_exact_or_eof(&mut buf).is_err());
+        assert!(read.read_exact_or_eof(&mut buf).is_err());
+        assert!(!read.peek_exact_or_eof(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn labeled() {
+        let mut read = Labeled::new(&[1, 2, 3][..], "segment.dat");
+        let mut buf = [0; 2];
+
+        assert!(read.read_exact_or_eof_labeled(&mut buf).unwrap());
+        let err = read.read_exact_or_eof_labeled(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(err.to_string(), "segment.dat @ 2: failed to fill whole buffer");
+        assert_eq!(read.offset(), 3);
+        assert!(!read.read_exact_or_eof_labeled(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn labeled_reader_error() {
+        let mut read = Labeled::new(ScriptedReader::new().data(&[1]).error(io::ErrorKind::ConnectionReset), "socket");
+
+        let err = read.read_exact_or_eof(&mut [0; 2]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(err.get_ref().unwrap().downcast_ref::<LabeledError>().unwrap().offset, 1);
+    }
+
+    #[test]
+    fn labeled_passthrough() {
+        let mut read = Labeled::new(ScriptedReader::new().error(io::ErrorKind::WouldBlock).error(io::ErrorKind::Interrupted).data(&[1]), "socket");
+
+        let err = read.read(&mut [0]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        assert!(err.get_ref().unwrap().downcast_ref::<LabeledError>().is_none());
+        assert_eq!(read.read(&mut [0]).unwrap_err().kind(), io::ErrorKind::Interrupted);
+        assert_eq!(read.read(&mut [0]).unwrap(), 1);
+    }
+
+    #[test]
+    fn labeled_os_error() {
+        struct Failing;
+
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::from_raw_os_error(32))
+            }
+        }
+
+        let err = Labeled::new(Failing, "pipe").read(&mut [0]).unwrap_err();
+        assert_eq!(err.raw_os_error(), None);
+        assert_eq!(super::raw_os_error(&err), Some(32));
+    }
+}
@@ -0,0 +1,162 @@
+use std::alloc::{self, Layout};
+use std::io::{self, Read};
+use std::ptr::NonNull;
+use std::slice;
+use crate::source::{fill_source, FillControl, IoSource, SliceBuf};
+use crate::ReadExactStatus;
+
+/// An aligned buffer for reading fixed-size records with direct I/O.
+///
+/// Direct I/O, such as files opened with `O_DIRECT`, requires that both the
+/// buffer and the length of each read are multiples of the device's block
+/// size. Each record therefore occupies a slot of `record_len` rounded up to
+/// the alignment, and any padding after the record in its slot is read along
+/// with it.
+#[derive(Debug)]
+pub struct AlignedRecordBuf {
+    ptr: NonNull<u8>,
+    layout: Layout,
+    record_len: usize,
+}
+
+// SAFETY: the buffer is uniquely owned, and only accessed through references
+// to this value
+unsafe impl Send for AlignedRecordBuf {}
+// SAFETY: as above
+unsafe impl Sync for AlignedRecordBuf {}
+
+impl AlignedRecordBuf {
+    /// Creates a zeroed buffer for `record_len` byte records aligned to
+    /// `align`, typically the logical block size of the device.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is not a power of two, or if the slot size
+    /// overflows.
+    pub fn new(record_len: usize, align: usize) -> Self {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        let slot_len = record_len.max(1).checked_next_multiple_of(align).expect("record slot is too large");
+        let layout = Layout::from_size_align(slot_len, align).expect("record slot is too large");
+
+        // SAFETY: `layout` has a non-zero size
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        AlignedRecordBuf {
+            ptr: NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout)),
+            layout,
+            record_len,
+        }
+    }
+
+    /// Returns the length of each record.
+    pub fn record_len(&self) -> usize {
+        self.record_len
+    }
+
+    /// Returns the length of each record's slot, which is read by each fill.
+    pub fn slot_len(&self) -> usize {
+        self.layout.size()
+    }
+
+    /// Returns the alignment of the buffer and slot length.
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+
+    /// Returns the most recently read record, excluding any padding.
+    pub fn record(&self) -> &[u8] {
+        &self.slot()[..self.record_len]
+    }
+
+    /// Returns the whole slot, including any padding.
+    pub fn slot(&self) -> &[u8] {
+        // SAFETY: the allocation is `slot_len` initialized bytes
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.slot_len()) }
+    }
+
+    /// Returns the whole slot mutably, including any padding.
+    pub fn slot_mut(&mut self) -> &mut [u8] {
+        // SAFETY: the allocation is `slot_len` initialized bytes, and is
+        // borrowed uniquely through `self`
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.slot_len()) }
+    }
+
+    /// Reads the next slot from `reader`, returning `true` if a record was
+    /// read or `false` at EOF on a slot boundary.
+    ///
+    /// Every read is issued at an aligned offset into the slot for a multiple
+    /// of the alignment. Direct I/O only returns a short read at the end of
+    /// the file, so a read that leaves the slot unaligned ends the fill; the
+    /// last slot of a file may be cut short after its record. EOF before the
+    /// end of a record fails with `UnexpectedEof`.
+    pub fn read_record_or_eof<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<bool> {
+        let (align, record_len) = (self.align(), self.record_len);
+        let mut slot = SliceBuf::new(self.slot_mut());
+        fill_source(&mut IoSource(reader), &mut slot, &mut Aligned(align))?;
+
+        ReadExactStatus::new(slot.filled().min(record_len), record_len).into_result()
+    }
+}
+
+/// The fill policy of `AlignedRecordBuf::read_record_or_eof`, which ends the
+/// fill after a short read leaves it unaligned.
+struct Aligned(usize);
+
+impl<B: ?Sized> FillControl<B, io::Error> for Aligned {
+    fn is_done(&self, filled: usize, len: usize) -> bool {
+        filled == len || !filled.is_multiple_of(self.0)
+    }
+}
+
+impl AsRef<[u8]> for AlignedRecordBuf {
+    fn as_ref(&self) -> &[u8] {
+        self.slot()
+    }
+}
+
+impl AsMut<[u8]> for AlignedRecordBuf {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.slot_mut()
+    }
+}
+
+impl Drop for AlignedRecordBuf {
+    fn drop(&mut self) {
+        // SAFETY: the buffer was allocated with `layout`
+        unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::ScriptedReader;
+    use super::AlignedRecordBuf;
+
+    #[test]
+    fn aligned() {
+        let buf = AlignedRecordBuf::new(6, 512);
+
+        assert_eq!(buf.slot_len(), 512);
+        assert_eq!(buf.slot().as_ptr() as usize % 512, 0);
+        assert_eq!(buf.record(), [0; 6]);
+    }
+
+    #[test]
+    fn records() {
+        let data: Vec<u8> = (1..=14).collect();
+        let mut read = ScriptedReader::new().data(&data[..4]).data(&data[4..]);
+        let mut buf = AlignedRecordBuf::new(6, 4);
+
+        assert!(buf.read_record_or_eof(&mut read).unwrap());
+        assert_eq!(buf.record(), [1, 2, 3, 4, 5, 6]);
+        assert!(buf.read_record_or_eof(&mut read).unwrap());
+        assert_eq!(buf.record(), [9, 10, 11, 12, 13, 14]);
+        assert!(!buf.read_record_or_eof(&mut read).unwrap());
+    }
+
+    #[test]
+    fn truncated() {
+        let mut buf = AlignedRecordBuf::new(6, 4);
+
+        assert!(buf.read_record_or_eof(&mut &[1, 2, 3, 4, 5][..]).is_err());
+    }
+}
@@ -0,0 +1,606 @@
+//! The async extension traits shared by the `futures` and `tokio` modules.
+//!
+//! Both traits are generated from one definition, so that they keep the same
+//! method names and semantics as each other. They cover the methods of
+//! `ReadExactExt` that fill a buffer, an array, a new `Vec`, a primitive
+//! integer, a length-prefixed frame or a stream of chunks, with the same
+//! outcomes as the blocking methods of the same names. Methods that take a
+//! blocking callback or writer, such as `read_exact_or_eof_tee`, or that
+//! depend on blocking primitives, such as `read_exact_or_eof_cancellable`,
+//! have no async counterpart. Each module provides `poll_read_some`,
+//! `poll_write_some` and `poll_flush` functions over its own `AsyncRead` and
+//! `AsyncWrite` traits, and invokes `async_read_exact_ext!` with the
+//! documentation of that trait.
+
+macro_rules! async_read_exact_ext {
+    ($doc:literal) => {
+        #[doc = $doc]
+        pub trait AsyncReadExactExt {
+            /// Reads exactly the number of bytes to fill `buf`, or zero.
+            ///
+            /// The returned future resolves to `true` upon successful read, or
+            /// `false` if no data was read, in the same manner as
+            /// `ReadExactExt::read_exact_or_eof`.
+            fn read_exact_or_eof<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadExactOrEof<'a, Self>;
+
+            /// Reads exactly the number of bytes to fill `buf`, or zero, as
+            /// configured by `opts`.
+            ///
+            /// This behaves like `ReadExactExt::read_exact_or_eof_with`, except
+            /// that `ReadExactOpts::zero_read_retries` is ignored and `Ok(0)` is
+            /// always EOF.
+            fn read_exact_or_eof_with<'a>(&'a mut self, opts: &ReadExactOpts, buf: &'a mut [u8]) -> ReadExactOrEof<'a, Self>;
+
+            /// Reads exactly the number of bytes to fill `buf`, reporting how
+            /// much was read if EOF is encountered first.
+            ///
+            /// This behaves like `ReadExactExt::read_exact_or_eof_status`.
+            fn read_exact_or_eof_status<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadExactOrEofStatus<'a, Self>;
+
+            /// Reads exactly `N` bytes into an array, or returns `None` at EOF.
+            ///
+            /// This behaves like `ReadExactExt::read_array_or_eof`.
+            fn read_array_or_eof<const N: usize>(&mut self) -> ReadArrayOrEof<'_, Self, N>;
+
+            /// Creates a stream of `N` byte chunks of this reader.
+            ///
+            /// The stream ends at EOF on a chunk boundary, and yields an error if
+            /// the reader ends partway through a chunk, in the same manner as
+            /// `ReadExactExt::exact_chunks`.
+            fn exact_chunks<const N: usize>(self) -> ExactChunks<Self, N> where Self: Sized;
+
+            /// Reads exactly the number of bytes to fill `buf`, or zero,
+            /// reporting a truncated record as a `ReadExactError`.
+            ///
+            /// This behaves like `ReadExactExt::read_exact_or_eof_typed`.
+            #[cfg(feature = "error")]
+            fn read_exact_or_eof_typed<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadExactOrEofTyped<'a, Self>;
+
+            /// Reads until `buf` is full or EOF is reached, resolving to the
+            /// number of bytes read.
+            ///
+            /// This behaves like `ReadExactExt::read_full`.
+            fn read_full<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadFull<'a, Self>;
+
+            /// Reads until `buf` is full or EOF is reached, zero-filling the
+            /// rest of `buf` at EOF.
+            ///
+            /// This behaves like `ReadExactExt::read_exact_or_zero_pad`.
+            fn read_exact_or_zero_pad<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadFull<'a, Self>;
+
+            /// Reads at least `min` bytes into `buf`, or resolves to `None` if
+            /// no data was read.
+            ///
+            /// This behaves like `ReadExactExt::read_at_least_or_eof`.
+            ///
+            /// # Panics
+            ///
+            /// The returned future panics if `min` is greater than `buf.len()`.
+            fn read_at_least_or_eof<'a>(&'a mut self, buf: &'a mut [u8], min: usize) -> ReadAtLeastOrEof<'a, Self>;
+
+            /// Reads exactly `n` bytes into a new `Vec`, or resolves to `None`
+            /// if no data was read.
+            ///
+            /// This behaves like `ReadExactExt::read_exact_to_vec`, allocating
+            /// the buffer fallibly when first polled.
+            fn read_exact_to_vec(&mut self, n: usize) -> ReadExactToVec<'_, Self>;
+
+            /// Reads a length-prefixed frame, or resolves to `None` at EOF on a
+            /// frame boundary.
+            ///
+            /// This behaves like `ReadExactExt::read_frame_or_eof`.
+            fn read_frame_or_eof(&mut self, format: &FrameFormat) -> ReadFrameOrEof<'_, Self>;
+
+            crate::async_ext::async_read_int_or_eof! {
+                read_u8_or_eof: u8::from_ne_bytes, 1, "";
+                read_i8_or_eof: i8::from_ne_bytes, 1, "n";
+                read_u16_le_or_eof: u16::from_le_bytes, 2, " little-endian";
+                read_u16_be_or_eof: u16::from_be_bytes, 2, " big-endian";
+                read_i16_le_or_eof: i16::from_le_bytes, 2, " little-endian";
+                read_i16_be_or_eof: i16::from_be_bytes, 2, " big-endian";
+                read_u32_le_or_eof: u32::from_le_bytes, 4, " little-endian";
+                read_u32_be_or_eof: u32::from_be_bytes, 4, " big-endian";
+                read_i32_le_or_eof: i32::from_le_bytes, 4, " little-endian";
+                read_i32_be_or_eof: i32::from_be_bytes, 4, " big-endian";
+                read_u64_le_or_eof: u64::from_le_bytes, 8, " little-endian";
+                read_u64_be_or_eof: u64::from_be_bytes, 8, " big-endian";
+                read_i64_le_or_eof: i64::from_le_bytes, 8, " little-endian";
+                read_i64_be_or_eof: i64::from_be_bytes, 8, " big-endian";
+            }
+        }
+
+        impl<T: AsyncRead + Unpin + ?Sized> AsyncReadExactExt for T {
+            fn read_exact_or_eof<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadExactOrEof<'a, Self> {
+                self.read_exact_or_eof_with(&ReadExactOpts::new(), buf)
+            }
+
+            fn read_exact_or_eof_with<'a>(&'a mut self, opts: &ReadExactOpts, buf: &'a mut [u8]) -> ReadExactOrEof<'a, Self> {
+                ReadExactOrEof {
+                    reader: self,
+                    buf,
+                    opts: *opts,
+                    state: ReadExactState::new(),
+                }
+            }
+
+            fn read_exact_or_eof_status<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadExactOrEofStatus<'a, Self> {
+                ReadExactOrEofStatus {
+                    reader: self,
+                    buf,
+                    state: ReadExactState::new(),
+                }
+            }
+
+            fn read_array_or_eof<const N: usize>(&mut self) -> ReadArrayOrEof<'_, Self, N> {
+                ReadArrayOrEof {
+                    reader: self,
+                    buf: [0; N],
+                    state: ReadExactState::new(),
+                }
+            }
+
+            fn exact_chunks<const N: usize>(self) -> ExactChunks<Self, N> where Self: Sized {
+                ExactChunks {
+                    reader: self,
+                    buf: [0; N],
+                    state: ReadExactState::new(),
+                    done: false,
+                }
+            }
+
+            #[cfg(feature = "error")]
+            fn read_exact_or_eof_typed<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadExactOrEofTyped<'a, Self> {
+                ReadExactOrEofTyped {
+                    read: self.read_exact_or_eof_status(buf),
+                }
+            }
+
+            fn read_full<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadFull<'a, Self> {
+                ReadFull {
+                    reader: self,
+                    buf,
+                    zero_pad: false,
+                    state: ReadExactState::new(),
+                }
+            }
+
+            fn read_exact_or_zero_pad<'a>(&'a mut self, buf: &'a mut [u8]) -> ReadFull<'a, Self> {
+                ReadFull {
+                    reader: self,
+                    buf,
+                    zero_pad: true,
+                    state: ReadExactState::new(),
+                }
+            }
+
+            fn read_at_least_or_eof<'a>(&'a mut self, buf: &'a mut [u8], min: usize) -> ReadAtLeastOrEof<'a, Self> {
+                ReadAtLeastOrEof {
+                    reader: self,
+                    buf,
+                    min,
+                    state: ReadExactState::new(),
+                }
+            }
+
+            fn read_exact_to_vec(&mut self, n: usize) -> ReadExactToVec<'_, Self> {
+                ReadExactToVec {
+                    reader: self,
+                    buf: Vec::new(),
+                    len: n,
+                    state: ReadExactState::new(),
+                }
+            }
+
+            fn read_frame_or_eof(&mut self, format: &FrameFormat) -> ReadFrameOrEof<'_, Self> {
+                ReadFrameOrEof {
+                    reader: self,
+                    format: *format,
+                    prefix: [0; 8],
+                    varint: crate::frame::Varint::default(),
+                    payload: None,
+                    state: ReadExactState::new(),
+                }
+            }
+        }
+
+        /// Future for the `AsyncReadExactExt::read_exact_or_eof` and
+        /// `AsyncReadExactExt::read_exact_or_eof_with` methods.
+        #[derive(Debug)]
+        #[must_use = "futures do nothing unless polled"]
+        pub struct ReadExactOrEof<'a, R: ?Sized> {
+            reader: &'a mut R,
+            buf: &'a mut [u8],
+            opts: ReadExactOpts,
+            state: ReadExactState,
+        }
+
+        impl<R: AsyncRead + Unpin + ?Sized> Future for ReadExactOrEof<'_, R> {
+            type Output = io::Result<bool>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                let this = self.get_mut();
+
+                poll_read_exact_or_eof_with(Pin::new(&mut *this.reader), cx, &mut this.state, &this.opts, this.buf)
+            }
+        }
+
+        /// Future for the `AsyncReadExactExt::read_exact_or_eof_status` method.
+        #[derive(Debug)]
+        #[must_use = "futures do nothing unless polled"]
+        pub struct ReadExactOrEofStatus<'a, R: ?Sized> {
+            reader: &'a mut R,
+            buf: &'a mut [u8],
+            state: ReadExactState,
+        }
+
+        impl<R: AsyncRead + Unpin + ?Sized> Future for ReadExactOrEofStatus<'_, R> {
+            type Output = io::Result<ReadExactStatus>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                let this = self.get_mut();
+
+                let len = this.buf.len();
+                poll_fill(Pin::new(&mut *this.reader), cx, &mut this.state, &ReadExactOpts::new(), this.buf, len)
+                    .map_ok(|filled| ReadExactStatus::new(filled, len))
+            }
+        }
+
+        /// Future for the `AsyncReadExactExt::read_exact_or_eof_typed` method.
+        #[cfg(feature = "error")]
+        #[derive(Debug)]
+        #[must_use = "futures do nothing unless polled"]
+        pub struct ReadExactOrEofTyped<'a, R: ?Sized> {
+            read: ReadExactOrEofStatus<'a, R>,
+        }
+
+        #[cfg(feature = "error")]
+        impl<R: AsyncRead + Unpin + ?Sized> Future for ReadExactOrEofTyped<'_, R> {
+            type Output = Result<bool, crate::ReadExactError>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                let this = self.get_mut();
+                let len = this.read.buf.len();
+
+                Pin::new(&mut this.read).poll(cx)
+                    .map(|ret| crate::ReadExactError::from_status(ret?, len))
+            }
+        }
+
+        /// Future for the `AsyncReadExactExt::read_full` and
+        /// `AsyncReadExactExt::read_exact_or_zero_pad` methods.
+        #[derive(Debug)]
+        #[must_use = "futures do nothing unless polled"]
+        pub struct ReadFull<'a, R: ?Sized> {
+            reader: &'a mut R,
+            buf: &'a mut [u8],
+            zero_pad: bool,
+            state: ReadExactState,
+        }
+
+        impl<R: AsyncRead + Unpin + ?Sized> Future for ReadFull<'_, R> {
+            type Output = io::Result<usize>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                let this = self.get_mut();
+                let len = this.buf.len();
+
+                poll_fill(Pin::new(&mut *this.reader), cx, &mut this.state, &ReadExactOpts::new(), this.buf, len)
+                    .map_ok(|filled| {
+                        if this.zero_pad {
+                            this.buf[filled..].fill(0);
+                        }
+                        filled
+                    })
+            }
+        }
+
+        /// Future for the `AsyncReadExactExt::read_at_least_or_eof` method.
+        #[derive(Debug)]
+        #[must_use = "futures do nothing unless polled"]
+        pub struct ReadAtLeastOrEof<'a, R: ?Sized> {
+            reader: &'a mut R,
+            buf: &'a mut [u8],
+            min: usize,
+            state: ReadExactState,
+        }
+
+        impl<R: AsyncRead + Unpin + ?Sized> Future for ReadAtLeastOrEof<'_, R> {
+            type Output = io::Result<Option<usize>>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                let this = self.get_mut();
+                assert!(this.min <= this.buf.len(), "min exceeds the buffer length");
+                let min = this.min;
+
+                poll_fill(Pin::new(&mut *this.reader), cx, &mut this.state, &ReadExactOpts::new(), this.buf, min)
+                    .map(|ret| ret.and_then(|filled| ReadExactStatus::new(filled.min(min), min).into_result()
+                        .map(|full| full.then_some(filled))))
+            }
+        }
+
+        /// Future for the `AsyncReadExactExt::read_exact_to_vec` method.
+        #[derive(Debug)]
+        #[must_use = "futures do nothing unless polled"]
+        pub struct ReadExactToVec<'a, R: ?Sized> {
+            reader: &'a mut R,
+            buf: Vec<u8>,
+            len: usize,
+            state: ReadExactState,
+        }
+
+        impl<R: AsyncRead + Unpin + ?Sized> Future for ReadExactToVec<'_, R> {
+            type Output = io::Result<Option<Vec<u8>>>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                let this = self.get_mut();
+                if this.buf.len() != this.len {
+                    this.buf = match zeroed_vec(this.len) {
+                        Ok(buf) => buf,
+                        Err(e) => return Poll::Ready(Err(e)),
+                    };
+                }
+
+                poll_read_exact_or_eof(Pin::new(&mut *this.reader), cx, &mut this.state, &mut this.buf)
+                    .map_ok(|full| full.then(|| ::std::mem::take(&mut this.buf)))
+            }
+        }
+
+        /// Future for the `AsyncReadExactExt::read_frame_or_eof` method.
+        #[derive(Debug)]
+        #[must_use = "futures do nothing unless polled"]
+        pub struct ReadFrameOrEof<'a, R: ?Sized> {
+            reader: &'a mut R,
+            format: FrameFormat,
+            prefix: [u8; 8],
+            varint: crate::frame::Varint,
+            payload: Option<Vec<u8>>,
+            state: ReadExactState,
+        }
+
+        impl<R: AsyncRead + Unpin + ?Sized> Future for ReadFrameOrEof<'_, R> {
+            type Output = io::Result<Option<Vec<u8>>>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                let this = self.get_mut();
+
+                loop {
+                    if let Some(payload) = &mut this.payload {
+                        return match poll_read_exact_or_eof(Pin::new(&mut *this.reader), cx, &mut this.state, payload) {
+                            Poll::Ready(Ok(true)) => Poll::Ready(Ok(this.payload.take())),
+                            Poll::Ready(Ok(false)) => Poll::Ready(Err(crate::unexpected_eof())),
+                            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+                            Poll::Pending => Poll::Pending,
+                        }
+                    }
+
+                    let width = crate::frame::prefix_width(&this.format).unwrap_or(1);
+                    let len = match poll_read_exact_or_eof(Pin::new(&mut *this.reader), cx, &mut this.state, &mut this.prefix[..width]) {
+                        Poll::Ready(Ok(true)) => match crate::frame::prefix_width(&this.format) {
+                            Some(..) => crate::frame::decode_len(&this.prefix[..width], &this.format),
+                            None => match this.varint.push(this.prefix[0]) {
+                                Ok(Some(len)) => crate::frame::check_len(len, &this.format),
+                                Ok(None) => continue,
+                                Err(e) => Err(e),
+                            },
+                        },
+                        Poll::Ready(Ok(false)) if this.varint.is_empty() => return Poll::Ready(Ok(None)),
+                        Poll::Ready(Ok(false)) => Err(crate::unexpected_eof()),
+                        Poll::Ready(Err(e)) => Err(e),
+                        Poll::Pending => return Poll::Pending,
+                    };
+                    this.payload = match len.and_then(zeroed_vec) {
+                        Ok(payload) => Some(payload),
+                        Err(e) => return Poll::Ready(Err(e)),
+                    };
+                }
+            }
+        }
+
+        /// Future for the primitive integer methods of `AsyncReadExactExt`,
+        /// such as `AsyncReadExactExt::read_u32_le_or_eof`.
+        #[derive(Debug)]
+        #[must_use = "futures do nothing unless polled"]
+        pub struct ReadIntOrEof<'a, R: ?Sized, T, const N: usize> {
+            read: ReadArrayOrEof<'a, R, N>,
+            from: fn([u8; N]) -> T,
+        }
+
+        impl<R: AsyncRead + Unpin + ?Sized, T, const N: usize> Future for ReadIntOrEof<'_, R, T, N> {
+            type Output = io::Result<Option<T>>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                let this = self.get_mut();
+
+                Pin::new(&mut this.read).poll(cx).map_ok(|buf| buf.map(this.from))
+            }
+        }
+
+        /// Future for the `AsyncReadExactExt::read_array_or_eof` method.
+        #[derive(Debug)]
+        #[must_use = "futures do nothing unless polled"]
+        pub struct ReadArrayOrEof<'a, R: ?Sized, const N: usize> {
+            reader: &'a mut R,
+            buf: [u8; N],
+            state: ReadExactState,
+        }
+
+        impl<R: AsyncRead + Unpin + ?Sized, const N: usize> Future for ReadArrayOrEof<'_, R, N> {
+            type Output = io::Result<Option<[u8; N]>>;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+                let this = self.get_mut();
+
+                poll_read_exact_or_eof(Pin::new(&mut *this.reader), cx, &mut this.state, &mut this.buf)
+                    .map_ok(|full| full.then_some(this.buf))
+            }
+        }
+
+        /// A stream over fixed-size chunks of a reader.
+        ///
+        /// This is created by `AsyncReadExactExt::exact_chunks`. It ends at EOF on a
+        /// chunk boundary, or after yielding the first error.
+        #[derive(Debug)]
+        #[must_use = "streams do nothing unless polled"]
+        pub struct ExactChunks<R, const N: usize> {
+            reader: R,
+            buf: [u8; N],
+            state: ReadExactState,
+            done: bool,
+        }
+
+        impl<R, const N: usize> ExactChunks<R, N> {
+            /// Gets a reference to the underlying reader.
+            pub fn get_ref(&self) -> &R {
+                &self.reader
+            }
+
+            /// Gets a mutable reference to the underlying reader.
+            pub fn get_mut(&mut self) -> &mut R {
+                &mut self.reader
+            }
+
+            /// Unwraps this stream, returning the underlying reader.
+            ///
+            /// Any partially read chunk is lost.
+            pub fn into_inner(self) -> R {
+                self.reader
+            }
+        }
+
+        impl<R: AsyncRead + Unpin, const N: usize> Stream for ExactChunks<R, N> {
+            type Item = io::Result<[u8; N]>;
+
+            fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+                let this = self.get_mut();
+                if this.done {
+                    return Poll::Ready(None)
+                }
+
+                let ret = match poll_read_exact_or_eof(Pin::new(&mut this.reader), cx, &mut this.state, &mut this.buf) {
+                    Poll::Ready(ret) => ret.map(|full| full.then_some(this.buf)).transpose(),
+                    Poll::Pending => return Poll::Pending,
+                };
+                this.done = !matches!(ret, Some(Ok(..)));
+                Poll::Ready(ret)
+            }
+        }
+
+        impl<R: AsyncRead + Unpin, const N: usize> FusedStream for ExactChunks<R, N> {
+            fn is_terminated(&self) -> bool {
+                self.done
+            }
+        }
+
+        /// Polls an exact read of `buf`, or zero, keeping its progress in `state`.
+        ///
+        /// This is the building block of `AsyncReadExactExt::read_exact_or_eof` for
+        /// manual `Future` implementations. Since the progress lives in `state`
+        /// rather than in a future, it is not lost if the caller stops polling, such
+        /// as when another branch of a `select!` completes first. The same `buf` must
+        /// be passed each time until the read finishes, at which point `state` is
+        /// reset for the next read.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `state` has filled more than `buf.len()` bytes.
+        pub fn poll_read_exact_or_eof<R: AsyncRead + ?Sized>(reader: Pin<&mut R>, cx: &mut Context, state: &mut ReadExactState, buf: &mut [u8]) -> Poll<io::Result<bool>> {
+            poll_read_exact_or_eof_with(reader, cx, state, &ReadExactOpts::new(), buf)
+        }
+
+        /// Polls an exact read of `buf`, or zero, as configured by `opts`.
+        ///
+        /// This behaves like `poll_read_exact_or_eof`. The same `opts` should be
+        /// passed each time until the read finishes. As with
+        /// `AsyncReadExactExt::read_exact_or_eof_with`, zero read retries are
+        /// ignored.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `state` has filled more than `buf.len()` bytes.
+        pub fn poll_read_exact_or_eof_with<R: AsyncRead + ?Sized>(reader: Pin<&mut R>, cx: &mut Context, state: &mut ReadExactState, opts: &ReadExactOpts, buf: &mut [u8]) -> Poll<io::Result<bool>> {
+            let len = buf.len();
+            poll_fill(reader, cx, state, opts, buf, len)
+                .map(|ret| ret.and_then(|filled| ReadExactStatus::new(filled, len).into_result()))
+        }
+
+        /// Writes all of `request` to `stream`, then reads exactly the number
+        /// of bytes to fill `response`, or zero.
+        ///
+        /// This behaves like `crate::exchange_exact`.
+        pub async fn exchange_exact<S: AsyncRead + AsyncWrite + Unpin + ?Sized>(stream: &mut S, mut request: &[u8], response: &mut [u8]) -> io::Result<bool> {
+            while !request.is_empty() {
+                match ::std::future::poll_fn(|cx| poll_write_some(Pin::new(&mut *stream), cx, request)).await {
+                    Ok(0) => return Err(crate::write_zero()),
+                    Ok(n) => request = &request[n..],
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            ::std::future::poll_fn(|cx| poll_flush(Pin::new(&mut *stream), cx)).await?;
+
+            stream.read_exact_or_eof(response).await
+        }
+
+        /// Polls reads into `buf` until at least `min` bytes are filled or EOF
+        /// is reached, resolving to the number of bytes filled.
+        fn poll_fill<R: AsyncRead + ?Sized>(mut reader: Pin<&mut R>, cx: &mut Context, state: &mut ReadExactState, opts: &ReadExactOpts, buf: &mut [u8], min: usize) -> Poll<io::Result<usize>> {
+            assert!(state.filled <= buf.len(), "filled exceeds the buffer length");
+
+            while state.filled < min {
+                if state.reads > 0 {
+                    if let Err(e) = opts.check_reads(state.reads, state.filled, buf.len()) {
+                        state.reset();
+                        return Poll::Ready(Err(e))
+                    }
+                }
+                let ret = poll_read_some(reader.as_mut(), cx, crate::source::limit_read(&mut buf[state.filled..]));
+                if ret.is_ready() {
+                    state.reads += 1;
+                }
+                match ret {
+                    Poll::Ready(Ok(0)) => break,
+                    Poll::Ready(Ok(n)) => state.filled += n,
+                    Poll::Ready(Err(ref e)) if e.kind() == io::ErrorKind::Interrupted && opts.retry_interrupt(&mut state.interrupts) => {}
+                    Poll::Ready(Err(ref e)) if state.filled == 0 && opts.is_eof(e) => break,
+                    Poll::Ready(Err(e)) => {
+                        state.reset();
+                        return Poll::Ready(Err(e))
+                    },
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+
+            let filled = state.filled;
+            state.reset();
+            Poll::Ready(Ok(filled))
+        }
+
+        fn zeroed_vec(len: usize) -> io::Result<Vec<u8>> {
+            let mut buf = Vec::new();
+            buf.try_reserve_exact(len)
+                .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "failed to allocate buffer"))?;
+            buf.resize(len, 0);
+            Ok(buf)
+        }
+    };
+}
+
+/// Generates provided `AsyncReadExactExt` methods that read a primitive
+/// integer.
+macro_rules! async_read_int_or_eof {
+    ($($name:ident: $ty:ident::$from:ident, $len:literal, $order:literal;)*) => {
+        $(
+            #[doc = concat!("Reads a", $order, " `", stringify!($ty), "`, or resolves to `None` if no data was read.")]
+            ///
+            #[doc = concat!("This behaves like `ReadExactExt::", stringify!($name), "`.")]
+            fn $name(&mut self) -> ReadIntOrEof<'_, Self, $ty, $len> {
+                ReadIntOrEof {
+                    read: self.read_array_or_eof(),
+                    from: $ty::$from,
+                }
+            }
+        )*
+    };
+}
+
+pub(crate) use {async_read_exact_ext, async_read_int_or_eof};
@@ -0,0 +1,119 @@
+use std::{io, panic, thread};
+#[cfg(unix)]
+use std::os::unix::fs::FileExt;
+#[cfg(windows)]
+use std::os::windows::fs::FileExt;
+use crate::source::{fill_source, FnSource, Plain, SliceBuf};
+use crate::{unexpected_eof, ReadExactStatus};
+
+/// An extension trait for reading at an offset, applying to all `FileExt`
+/// types such as `std::fs::File`.
+pub trait ReadExactAtExt {
+    /// Reads exactly the number of bytes to fill `buf` starting at `offset`,
+    /// or zero.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, but uses
+    /// positioned reads. On Unix the file cursor is unaffected, while on
+    /// Windows it is left at an unspecified position.
+    fn read_exact_at_or_eof(&self, buf: &mut [u8], offset: u64) -> io::Result<bool>;
+}
+
+impl<T: FileExt + ?Sized> ReadExactAtExt for T {
+    fn read_exact_at_or_eof(&self, buf: &mut [u8], mut offset: u64) -> io::Result<bool> {
+        let len = buf.len();
+        let mut buf = SliceBuf::new(buf);
+        fill_source(&mut FnSource(|buf: &mut [u8]| {
+            let n = read_at(self, buf, offset)?;
+            offset += n as u64;
+            Ok::<_, io::Error>(n)
+        }), &mut buf, &mut Plain)?;
+
+        ReadExactStatus::new(buf.filled(), len).into_result()
+    }
+}
+
+/// Reads the `record_len` byte records at each of `offsets` on `threads`
+/// threads, returning them in the order of `offsets`.
+///
+/// The offsets are split into contiguous runs, one per thread, and each run
+/// is read with `ReadExactAtExt::read_exact_at_or_eof`. A record that is not
+/// entirely within the file fails with `UnexpectedEof`, and the error of the
+/// first run to fail is returned.
+///
+/// # Panics
+///
+/// Panics if `threads` is zero.
+pub fn read_records_at_parallel<F: FileExt + Sync + ?Sized>(file: &F, offsets: &[u64], record_len: usize, threads: usize) -> io::Result<Vec<Vec<u8>>> {
+    assert!(threads > 0, "threads must be non-zero");
+    let mut records = vec![vec![0; record_len]; offsets.len()];
+    let per_thread = offsets.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = records.chunks_mut(per_thread).zip(offsets.chunks(per_thread))
+            .map(|(records, offsets)| scope.spawn(move || {
+                records.iter_mut().zip(offsets).try_for_each(|(record, &offset)| match file.read_exact_at_or_eof(record, offset)? {
+                    true => Ok(()),
+                    false => Err(unexpected_eof()),
+                })
+            }))
+            .collect();
+        workers.into_iter().try_for_each(|worker| worker.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+    })?;
+
+    Ok(records)
+}
+
+#[cfg(unix)]
+fn read_at<T: FileExt + ?Sized>(file: &T, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    file.read_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at<T: FileExt + ?Sized>(file: &T, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    file.seek_read(buf, offset)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io;
+    use std::os::unix::fs::FileExt;
+    use super::{read_records_at_parallel, ReadExactAtExt};
+
+    /// Returns at most one byte per read.
+    struct Slow(&'static [u8]);
+
+    impl FileExt for Slow {
+        fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+            let data = self.0.get(offset as usize..).unwrap_or(&[]);
+            let n = data.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&data[..n]);
+            Ok(n)
+        }
+
+        fn write_at(&self, _: &[u8], _: u64) -> io::Result<usize> {
+            Err(io::ErrorKind::Unsupported.into())
+        }
+    }
+
+    #[test]
+    fn read_at() {
+        let file = Slow(&[1, 2, 3]);
+        let mut buf = [0, 0];
+
+        assert!(file.read_exact_at_or_eof(&mut buf, 1).unwrap());
+        assert_eq!(buf, [2, 3]);
+        assert!(file.read_exact_at_or_eof(&mut buf, 2).is_err());
+        assert!(!file.read_exact_at_or_eof(&mut buf, 3).unwrap());
+    }
+
+    #[test]
+    fn parallel() {
+        let file = Slow(&[1, 2, 3, 4]);
+
+        let records = read_records_at_parallel(&file, &[2, 0, 1], 2, 2).unwrap();
+        assert_eq!(records, [[3, 4], [1, 2], [2, 3]]);
+        let ret = read_records_at_parallel(&file, &[0, 1, 3, 4], 2, 8);
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert!(read_records_at_parallel(&file, &[], 2, 2).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::{io, unexpected_eof, ReadExactExt};
+
+/// The order in which the bits of each byte are read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BitOrder {
+    /// The most significant bit of each byte comes first, and values are
+    /// assembled most significant bit first, as in most codecs.
+    MsbFirst,
+    /// The least significant bit of each byte comes first, and values are
+    /// assembled least significant bit first, as in DEFLATE.
+    LsbFirst,
+}
+
+/// A reader of bit-packed values.
+///
+/// Bytes are read from the underlying reader only as they are needed. EOF is
+/// clean only when it falls on a byte boundary before any bit of a value was
+/// read, in the same manner as `ReadExactExt::read_exact_or_eof`.
+#[derive(Debug)]
+pub struct BitReader<R> {
+    reader: R,
+    order: BitOrder,
+    byte: u8,
+    bits: u32,
+}
+
+impl<R> BitReader<R> {
+    /// Creates a reader of the bits of `reader` in the given order.
+    pub fn new(reader: R, order: BitOrder) -> Self {
+        BitReader {
+            reader,
+            order,
+            byte: 0,
+            bits: 0,
+        }
+    }
+
+    /// Returns whether the next bit starts a byte.
+    pub fn is_aligned(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Discards the bits left in the current byte.
+    pub fn align(&mut self) {
+        self.bits = 0;
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    ///
+    /// The bits left in the current byte are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Takes up to `n` bits from the current byte into `value`.
+    fn take(&mut self, value: &mut u64, taken: &mut u32, n: u32) {
+        let k = self.bits.min(n - *taken);
+        let mask = ((1u16 << k) - 1) as u8;
+        match self.order {
+            BitOrder::MsbFirst => {
+                let chunk = (self.byte >> (self.bits - k)) & mask;
+                *value = (*value << k) | u64::from(chunk);
+            },
+            BitOrder::LsbFirst => {
+                let chunk = self.byte & mask;
+                *value |= u64::from(chunk) << *taken;
+                self.byte = self.byte.checked_shr(k).unwrap_or(0);
+            },
+        }
+        self.bits -= k;
+        *taken += k;
+    }
+}
+
+impl<R: io::Read> BitReader<R> {
+    /// Reads an `n` bit value, or returns `None` at EOF.
+    ///
+    /// Fails with `UnexpectedEof` if the reader ends partway through the
+    /// value, including when it ends within bits left over from the previous
+    /// value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is greater than 64.
+    pub fn read_bits_or_eof(&mut self, n: u32) -> io::Result<Option<u64>> {
+        assert!(n <= 64, "cannot read more than 64 bits at once");
+
+        let mut value = 0;
+        let mut taken = 0;
+        self.take(&mut value, &mut taken, n);
+
+        let needed = (n - taken).div_ceil(8) as usize;
+        let mut bytes = [0; 8];
+        if !self.reader.read_exact_or_eof(&mut bytes[..needed])? {
+            return match taken {
+                0 => Ok(None),
+                _ => Err(unexpected_eof()),
+            }
+        }
+
+        for &byte in &bytes[..needed] {
+            self.byte = byte;
+            self.bits = 8;
+            self.take(&mut value, &mut taken, n);
+        }
+        Ok(Some(value))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{BitOrder, BitReader};
+
+    #[test]
+    fn msb_first() {
+        let mut read = BitReader::new(&[0b1011_0011, 0b1100_0000][..], BitOrder::MsbFirst);
+
+        assert_eq!(read.read_bits_or_eof(3).unwrap(), Some(0b101));
+        assert_eq!(read.read_bits_or_eof(7).unwrap(), Some(0b100_1111));
+        assert_eq!(read.read_bits_or_eof(0).unwrap(), Some(0));
+        assert!(read.read_bits_or_eof(8).is_err());
+    }
+
+    #[test]
+    fn lsb_first() {
+        let mut read = BitReader::new(&[0b1011_0011, 0b0000_0010][..], BitOrder::LsbFirst);
+
+        assert_eq!(read.read_bits_or_eof(3).unwrap(), Some(0b011));
+        assert_eq!(read.read_bits_or_eof(7).unwrap(), Some(0b101_0110));
+        assert!(!read.is_aligned());
+        read.align();
+        assert_eq!(read.read_bits_or_eof(1).unwrap(), None);
+    }
+
+    #[test]
+    fn wide() {
+        let mut read = BitReader::new(&[0xff; 9][..], BitOrder::MsbFirst);
+
+        assert_eq!(read.read_bits_or_eof(4).unwrap(), Some(0xf));
+        assert_eq!(read.read_bits_or_eof(64).unwrap(), Some(u64::MAX));
+        assert_eq!(read.read_bits_or_eof(4).unwrap(), Some(0xf));
+        assert!(read.is_aligned());
+        assert_eq!(read.read_bits_or_eof(64).unwrap(), None);
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{io, unexpected_eof, ReadExactStatus};
+
+/// An extension trait that applies to all `std::io::BufRead` types.
+///
+/// Without the `std` feature, this applies to `no_std_io2::io::BufRead` types
+/// instead.
+///
+/// `BufRead` cannot move a partial record to the front of its buffer, so a
+/// borrowed record that straddles a refill is only available from
+/// `ExactBufReader::fill_exact_or_eof`.
+pub trait BufReadExactExt {
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, but copies
+    /// directly out of the internal buffer using `fill_buf` and `consume`, so
+    /// that no reads are issued while it still holds enough data.
+    fn read_exact_or_eof_buffered(&mut self, buf: &mut [u8]) -> io::Result<bool>;
+
+    /// Skips ahead to just past the next occurrence of `magic`, returning
+    /// the number of bytes skipped before it.
+    ///
+    /// This allows a reader of exact records to recover from a corrupt region
+    /// by resynchronizing on a sync word. Returns `None` if EOF is reached, or
+    /// more than `max_scan` bytes would have to be skipped, before `magic` is
+    /// found, in which case the bytes scanned are consumed.
+    fn resync_to_magic(&mut self, magic: &[u8], max_scan: usize) -> io::Result<Option<u64>>;
+
+    /// Returns whether the reader is at EOF, without consuming anything.
+    ///
+    /// This fills the internal buffer if it is empty, so it blocks until
+    /// data arrives or the stream ends, and a nonblocking reader fails with
+    /// `WouldBlock` instead. A loop can use this to tell a clean end of the
+    /// stream apart from the start of another record before reading it.
+    fn at_eof(&mut self) -> io::Result<bool>;
+
+    /// Reads up to and including the next `delim` into `buf`, without ever
+    /// reading past the end of `buf`.
+    ///
+    /// Unlike `BufRead::read_until`, this never allocates, so a line of text
+    /// can be read between fixed-size records without letting the peer grow
+    /// the buffer. If `buf` fills up before `delim` is found, the bytes read
+    /// are left in `buf` and the rest of the line is not consumed. EOF
+    /// partway through a line is an `UnexpectedEof` error.
+    fn read_until_within(&mut self, delim: u8, buf: &mut [u8]) -> io::Result<DelimStatus>;
+}
+
+/// The outcome of a delimited read into a fixed buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DelimStatus {
+    /// The delimiter was found, and is the last of this many bytes read
+    /// into the start of the buffer.
+    Found(usize),
+    /// The buffer was filled before the delimiter was found.
+    BufferFull,
+    /// EOF was reached before any data was read.
+    Eof,
+}
+
+impl<T: io::BufRead + ?Sized> BufReadExactExt for T {
+    fn read_exact_or_eof_buffered(&mut self, mut buf: &mut [u8]) -> io::Result<bool> {
+        let len = buf.len();
+
+        while !buf.is_empty() {
+            let available = match self.fill_buf() {
+                Ok(available) => available,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                break
+            }
+
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            self.consume(n);
+            buf = &mut {buf}[n..];
+        }
+
+        ReadExactStatus::new(len - buf.len(), len).into_result()
+    }
+
+    fn resync_to_magic(&mut self, magic: &[u8], max_scan: usize) -> io::Result<Option<u64>> {
+        let max_scan = max_scan as u64;
+        let mut matched = 0;
+        let mut skipped = 0;
+
+        while matched < magic.len() && skipped <= max_scan {
+            let available = match self.fill_buf() {
+                Ok([]) => return Ok(None),
+                Ok(available) => available,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            let mut used = 0;
+            for &byte in available {
+                used += 1;
+                let next = advance_match(magic, matched, byte);
+                // bytes that fall out of the partial match have been skipped
+                skipped += (matched + 1 - next) as u64;
+                matched = next;
+                if matched == magic.len() || skipped > max_scan {
+                    break
+                }
+            }
+            self.consume(used);
+        }
+
+        Ok((matched == magic.len()).then_some(skipped))
+    }
+
+    fn at_eof(&mut self) -> io::Result<bool> {
+        loop {
+            match self.fill_buf() {
+                Ok(available) => return Ok(available.is_empty()),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn read_until_within(&mut self, delim: u8, buf: &mut [u8]) -> io::Result<DelimStatus> {
+        let mut filled = 0;
+
+        while filled < buf.len() {
+            let available = match self.fill_buf() {
+                Ok([]) if filled == 0 => return Ok(DelimStatus::Eof),
+                Ok([]) => return Err(unexpected_eof()),
+                Ok(available) => available,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            let available = &available[..available.len().min(buf.len() - filled)];
+            let (n, found) = match available.iter().position(|&b| b == delim) {
+                Some(i) => (i + 1, true),
+                None => (available.len(), false),
+            };
+            buf[filled..filled + n].copy_from_slice(&available[..n]);
+            self.consume(n);
+            filled += n;
+            if found {
+                return Ok(DelimStatus::Found(filled))
+            }
+        }
+
+        Ok(DelimStatus::BufferFull)
+    }
+}
+
+/// Returns the length of the longest prefix of `magic` that ends the input,
+/// given that the previous input ended with `matched` bytes of it.
+fn advance_match(magic: &[u8], matched: usize, byte: u8) -> usize {
+    (1..=matched + 1).rev()
+        .find(|&k| magic[k - 1] == byte && magic[..k - 1] == magic[matched + 1 - k..matched])
+        .unwrap_or(0)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::io::{self, BufRead, BufReader, Read};
+    use super::{BufReadExactExt, DelimStatus};
+
+    struct Counted<R>(R, usize);
+
+    impl<R: Read> Read for Counted<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.1 += 1;
+            self.0.read(buf)
+        }
+    }
+
+    #[test]
+    fn buffered() {
+        let mut read = BufReader::with_capacity(3, Counted(&[1, 2, 3, 4, 5][..], 0));
+        let mut buf = [0, 0];
+
+        assert!(read.read_exact_or_eof_buffered(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(read.read_exact_or_eof_buffered(&mut buf).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert_eq!(read.get_ref().1, 2);
+        assert!(read.read_exact_or_eof_buffered(&mut buf).is_err());
+        assert!(!read.read_exact_or_eof_buffered(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn resync() {
+        let mut read = BufReader::with_capacity(2, &[0, 1, 1, 2, 1, 5][..]);
+        let mut buf = [0];
+
+        assert_eq!(read.resync_to_magic(&[1, 2, 1], 8).unwrap(), Some(2));
+        assert!(read.read_exact_or_eof_buffered(&mut buf).unwrap());
+        assert_eq!(buf, [5]);
+        assert_eq!(read.resync_to_magic(&[1, 2, 1], 8).unwrap(), None);
+    }
+
+    #[test]
+    fn at_eof() {
+        let mut read = BufReader::new(&[1][..]);
+
+        assert!(!read.at_eof().unwrap());
+        read.consume(1);
+        assert!(read.at_eof().unwrap());
+    }
+
+    #[test]
+    fn resync_max_scan() {
+        let mut read = &[0, 0, 0, 1][..];
+
+        assert_eq!(read.resync_to_magic(&[1], 2).unwrap(), None);
+        assert_eq!(read.resync_to_magic(&[1], 2).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn until_within() {
+        let mut read = BufReader::with_capacity(2, &b"ab\nlonger\nc"[..]);
+        let mut buf = [0; 4];
+
+        assert_eq!(read.read_until_within(b'\n', &mut buf).unwrap(), DelimStatus::Found(3));
+        assert_eq!(&buf[..3], b"ab\n");
+        assert_eq!(read.read_until_within(b'\n', &mut buf).unwrap(), DelimStatus::BufferFull);
+        assert_eq!(&buf, b"long");
+        assert_eq!(read.read_until_within(b'\n', &mut buf).unwrap(), DelimStatus::Found(3));
+        assert_eq!(&buf[..3], b"er\n");
+        assert!(read.read_until_within(b'\n', &mut buf).is_err());
+        assert_eq!(read.read_until_within(b'\n', &mut buf).unwrap(), DelimStatus::Eof);
+    }
+}
@@ -0,0 +1,69 @@
+use core::{ptr, slice};
+use ::bytes::{BufMut, Bytes, BytesMut};
+use crate::{fill, io, ReadExactStatus};
+
+pub(crate) fn read_into_buf<R: io::Read + ?Sized, B: BufMut + ?Sized>(reader: &mut R, buf: &mut B, n: usize) -> io::Result<bool> {
+    if buf.remaining_mut() < n {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "buffer is too small"))
+    }
+
+    let mut remaining = n;
+    while remaining > 0 {
+        let chunk = buf.chunk_mut();
+        let len = chunk.len().min(remaining);
+        // SAFETY: the first `len` bytes of the chunk are zeroed before being
+        // viewed as initialized
+        let chunk = unsafe {
+            ptr::write_bytes(chunk.as_mut_ptr(), 0, len);
+            slice::from_raw_parts_mut(chunk.as_mut_ptr(), len)
+        };
+
+        let read = fill(reader, chunk)?;
+        // SAFETY: at most `len` bytes were read, all of which are initialized
+        unsafe { buf.advance_mut(read) };
+        remaining -= read;
+        if read < len {
+            break
+        }
+    }
+
+    ReadExactStatus::new(n - remaining, n).into_result()
+}
+
+pub(crate) fn read_bytes<R: io::Read + ?Sized>(reader: &mut R, n: usize) -> io::Result<Option<Bytes>> {
+    let mut buf = BytesMut::with_capacity(n);
+    read_into_buf(reader, &mut buf, n).map(|full| full.then(|| buf.freeze()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ReadExactExt;
+
+    #[test]
+    fn into_buf() {
+        let mut read = &[1, 2, 3][..];
+        let mut buf = Vec::new();
+
+        assert!(read.read_exact_into_buf(&mut buf, 2).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(read.read_exact_into_buf(&mut buf, 2).is_err());
+        assert!(!read.read_exact_into_buf(&mut buf, 2).unwrap());
+    }
+
+    #[test]
+    fn small_buf() {
+        let mut read = &[1, 2][..];
+        let mut buf = [0; 1];
+
+        assert!(read.read_exact_into_buf(&mut &mut buf[..], 2).is_err());
+    }
+
+    #[test]
+    fn bytes() {
+        let mut read = &[1, 2, 3][..];
+
+        assert_eq!(read.read_exact_bytes(2).unwrap().unwrap()[..], [1, 2]);
+        assert!(read.read_exact_bytes(2).is_err());
+        assert_eq!(read.read_exact_bytes(2).unwrap(), None);
+    }
+}
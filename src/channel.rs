@@ -0,0 +1,103 @@
+use std::io::{self, BufRead, Read};
+use std::sync::mpsc::Receiver;
+
+/// A reader over byte chunks received from a channel.
+///
+/// Chunks may be of any length, and exact reads with `ReadExactExt` span as
+/// many of them as needed. Once every sender has been dropped and the
+/// remaining chunks have been read, the reader is at EOF.
+#[derive(Debug)]
+pub struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl ChannelReader {
+    /// Creates a reader over the chunks received by `receiver`.
+    pub fn new(receiver: Receiver<Vec<u8>>) -> Self {
+        ChannelReader {
+            receiver,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Gets a reference to the underlying receiver.
+    pub fn get_ref(&self) -> &Receiver<Vec<u8>> {
+        &self.receiver
+    }
+
+    /// Unwraps this reader, returning the underlying receiver.
+    ///
+    /// Any partially read chunk is lost.
+    pub fn into_inner(self) -> Receiver<Vec<u8>> {
+        self.receiver
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for ChannelReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos == self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                },
+                // every sender is gone
+                Err(..) => break,
+            }
+        }
+        Ok(&self.chunk[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.chunk.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+    use crate::ReadExactExt;
+    use super::ChannelReader;
+
+    #[test]
+    fn chunks() {
+        let (tx, rx) = mpsc::channel();
+        let sender = thread::spawn(move || {
+            for chunk in [vec![1], vec![], vec![2, 3, 4]] {
+                tx.send(chunk).unwrap();
+            }
+        });
+        let mut read = ChannelReader::new(rx);
+        let mut buf = [0; 2];
+
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn truncated() {
+        let (tx, rx) = mpsc::channel();
+        tx.send(vec![1]).unwrap();
+        drop(tx);
+
+        assert!(ChannelReader::new(rx).read_exact_or_eof(&mut [0; 2]).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use std::{error, fmt, io};
+use ::crc::{Crc, CRC_32_ISCSI, CRC_32_ISO_HDLC};
+
+const CRC32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+const CRC32C: Crc<u32> = Crc::<u32>::new(&CRC_32_ISCSI);
+
+/// A checksum that trails a record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Checksum {
+    /// The CRC-32 of zlib and Ethernet.
+    Crc32,
+    /// The CRC-32C (Castagnoli) of iSCSI, ext4 and many log formats.
+    Crc32c,
+}
+
+impl Checksum {
+    /// Computes the checksum of `data`.
+    pub fn checksum(&self, data: &[u8]) -> u32 {
+        match self {
+            Checksum::Crc32 => CRC32.checksum(data),
+            Checksum::Crc32c => CRC32C.checksum(data),
+        }
+    }
+}
+
+/// The error for a record whose trailing checksum does not match.
+///
+/// This is the inner error of the `InvalidData` error returned by
+/// `ReadExactExt::read_exact_crc_or_eof`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChecksumMismatch {
+    /// The checksum that trailed the record.
+    pub expected: u32,
+    /// The checksum of the record itself.
+    pub actual: u32,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "checksum mismatch: expected {:#010x}, got {:#010x}", self.expected, self.actual)
+    }
+}
+
+impl error::Error for ChecksumMismatch {}
+
+/// Checks `record` against its little-endian trailing checksum.
+pub(crate) fn verify(checksum: Checksum, record: &[u8], trailer: [u8; 4]) -> io::Result<()> {
+    let expected = u32::from_le_bytes(trailer);
+    match checksum.checksum(record) {
+        actual if actual == expected => Ok(()),
+        actual => Err(io::Error::new(io::ErrorKind::InvalidData, ChecksumMismatch { expected, actual })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use crate::ReadExactExt;
+    use super::{Checksum, ChecksumMismatch};
+
+    #[test]
+    fn check() {
+        assert_eq!(Checksum::Crc32.checksum(b"123456789"), 0xcbf43926);
+        assert_eq!(Checksum::Crc32c.checksum(b"123456789"), 0xe3069283);
+    }
+
+    #[test]
+    fn read_crc() {
+        let mut data = b"1234".to_vec();
+        data.extend_from_slice(&Checksum::Crc32c.checksum(b"1234").to_le_bytes());
+        data.extend_from_slice(b"5678\0\0\0\0");
+        let mut read = &data[..];
+        let mut buf = [0; 4];
+
+        assert!(read.read_exact_crc_or_eof(&mut buf, Checksum::Crc32c).unwrap());
+        assert_eq!(&buf, b"1234");
+        let err = read.read_exact_crc_or_eof(&mut buf, Checksum::Crc32c).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.get_ref().unwrap().downcast_ref::<ChecksumMismatch>().unwrap().expected, 0);
+        assert!(!read.read_exact_crc_or_eof(&mut buf, Checksum::Crc32c).unwrap());
+    }
+
+    #[test]
+    fn truncated_crc() {
+        let mut read = &b"1234\0\0"[..];
+
+        assert!(read.read_exact_crc_or_eof(&mut [0; 4], Checksum::Crc32).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use std::io::{self, Read};
+use std::process::{Child, ChildStdout, ExitStatus};
+use std::thread::{self, JoinHandle};
+use crate::{frame, FrameFormat, ReadExactExt};
+
+/// A reader of exact records or length-prefixed frames from the stdout of a
+/// child process.
+///
+/// Once stdout reaches EOF on a record boundary, the child is waited on, and
+/// its exit status is available from `exit_status`. A child that exits while
+/// writing a record is an `UnexpectedEof` error as usual, and is not waited
+/// on.
+#[derive(Debug)]
+pub struct ChildFrames {
+    child: Child,
+    stdout: ChildStdout,
+    stderr: Option<JoinHandle<Vec<u8>>>,
+    drained: Option<Vec<u8>>,
+    status: Option<ExitStatus>,
+}
+
+impl ChildFrames {
+    /// Reads from the stdout of `child`, which must be piped.
+    ///
+    /// Fails with `InvalidInput` if the stdout of `child` was not piped.
+    pub fn new(mut child: Child) -> io::Result<Self> {
+        let stdout = child.stdout.take()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "child stdout is not piped"))?;
+        Ok(ChildFrames {
+            child,
+            stdout,
+            stderr: None,
+            drained: None,
+            status: None,
+        })
+    }
+
+    /// Reads the piped stderr of the child on another thread.
+    ///
+    /// A child that fills its stderr pipe blocks until it is read, which
+    /// deadlocks with a parent that only reads stdout. The collected output
+    /// is available from `stderr` once stdout reaches EOF. This does nothing
+    /// if stderr was not piped.
+    pub fn drain_stderr(mut self) -> Self {
+        if let Some(mut stderr) = self.child.stderr.take() {
+            self.stderr = Some(thread::spawn(move || {
+                let mut output = Vec::new();
+                // keep whatever was read before an error
+                let _ = stderr.read_to_end(&mut output);
+                output
+            }));
+        }
+        self
+    }
+
+    /// Reads exactly the number of bytes to fill `buf` from stdout, or zero.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, and waits on the
+    /// child at EOF.
+    pub fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let full = self.stdout.read_exact_or_eof(buf)?;
+        if !full {
+            self.finish()?;
+        }
+        Ok(full)
+    }
+
+    /// Reads a length-prefixed frame from stdout, or returns `None` at EOF
+    /// before its length prefix.
+    ///
+    /// This behaves like `ReadExactExt::read_frame_or_eof`, and waits on the
+    /// child at EOF.
+    pub fn read_frame_or_eof(&mut self, format: &FrameFormat) -> io::Result<Option<Vec<u8>>> {
+        let frame = frame::read_frame(&mut self.stdout, format)?;
+        if frame.is_none() {
+            self.finish()?;
+        }
+        Ok(frame)
+    }
+
+    /// Returns the exit status of the child, once stdout has reached EOF.
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        self.status
+    }
+
+    /// Returns the output that the child wrote to stderr, once stdout has
+    /// reached EOF, if it was drained with `drain_stderr`.
+    pub fn stderr(&self) -> Option<&[u8]> {
+        self.drained.as_deref()
+    }
+
+    /// Gets a reference to the child process.
+    pub fn get_ref(&self) -> &Child {
+        &self.child
+    }
+
+    /// Gets a mutable reference to the child process.
+    pub fn get_mut(&mut self) -> &mut Child {
+        &mut self.child
+    }
+
+    /// Unwraps this reader, returning the child process and its stdout.
+    pub fn into_inner(self) -> (Child, ChildStdout) {
+        (self.child, self.stdout)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.status.is_none() {
+            self.status = Some(self.child.wait()?);
+        }
+        if let Some(stderr) = self.stderr.take() {
+            self.drained = Some(stderr.join().unwrap_or_default());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::process::{Command, Stdio};
+    use crate::FrameFormat;
+    use super::ChildFrames;
+
+    fn spawn(script: &str) -> ChildFrames {
+        let child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        ChildFrames::new(child).unwrap().drain_stderr()
+    }
+
+    #[test]
+    fn records() {
+        let mut frames = spawn("printf abcd; echo oops >&2; exit 3");
+        let mut buf = [0; 2];
+
+        assert!(frames.read_exact_or_eof(&mut buf).unwrap());
+        assert!(frames.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(frames.exit_status(), None);
+        assert!(!frames.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(frames.exit_status().unwrap().code(), Some(3));
+        assert_eq!(frames.stderr(), Some(&b"oops\n"[..]));
+    }
+
+    #[test]
+    fn frames() {
+        let mut frames = spawn("printf '\\000\\000\\000\\002hi'");
+
+        assert_eq!(frames.read_frame_or_eof(&FrameFormat::default()).unwrap(), Some(b"hi".to_vec()));
+        assert_eq!(frames.read_frame_or_eof(&FrameFormat::default()).unwrap(), None);
+        assert!(frames.exit_status().unwrap().success());
+    }
+
+    #[test]
+    fn not_piped() {
+        let mut child = Command::new("true").stdout(Stdio::null()).spawn().unwrap();
+        child.wait().unwrap();
+
+        assert!(ChildFrames::new(child).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use std::io::{self, IoSliceMut, Read};
+use crate::{unexpected_eof, ReadExactOpts};
+use crate::opts::OptsControl;
+use crate::source::{fill_source, IoSource, VectoredBuf};
+
+/// A reader that splits fixed-size records into columns as it reads them.
+///
+/// Each record is a sequence of fields of fixed widths, and each field is
+/// appended to its own column. The reads are vectored, with a slice for each
+/// field of each record pointing directly into its column, so that the
+/// columns are filled without a separate pass to transpose the records.
+#[derive(Debug)]
+pub struct ColumnarReader<R> {
+    reader: R,
+    widths: Vec<usize>,
+}
+
+impl<R> ColumnarReader<R> {
+    /// Creates a reader of records made of fields of the given `widths`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the widths add up to zero.
+    pub fn new<W: Into<Vec<usize>>>(reader: R, widths: W) -> Self {
+        let widths = widths.into();
+        assert!(widths.iter().any(|&width| width > 0), "record length must be non-zero");
+        ColumnarReader {
+            reader,
+            widths,
+        }
+    }
+
+    /// Returns the width of each field.
+    pub fn widths(&self) -> &[usize] {
+        &self.widths
+    }
+
+    /// Returns the length of each record.
+    pub fn record_len(&self) -> usize {
+        self.widths.iter().sum()
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> ColumnarReader<R> {
+    /// Reads up to `max_records` records, appending each field to the
+    /// corresponding column of `columns`.
+    ///
+    /// Returns the number of records read, which is less than `max_records`
+    /// only at EOF. Fails with `UnexpectedEof` if the reader ends partway
+    /// through a record, in which case the complete records before it are
+    /// kept. After any other error, the columns are left as they were.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `columns` does not have one column for each field.
+    pub fn read_columns(&mut self, columns: &mut [Vec<u8>], max_records: usize) -> io::Result<usize> {
+        assert_eq!(columns.len(), self.widths.len(), "there must be one column for each field");
+        let starts: Vec<usize> = columns.iter().map(Vec::len).collect();
+        for (column, &width) in columns.iter_mut().zip(&self.widths) {
+            column.resize(column.len() + width * max_records, 0);
+        }
+
+        let filled = {
+            let mut fields: Vec<_> = columns.iter_mut().zip(&starts).zip(&self.widths)
+                .map(|((column, &start), &width)| column[start..].chunks_mut(width.max(1)))
+                .collect();
+            let mut slices = Vec::with_capacity(max_records * fields.len());
+            for _ in 0..max_records {
+                slices.extend(fields.iter_mut().filter_map(Iterator::next).map(IoSliceMut::new));
+            }
+            fill_vectored(&mut self.reader, &mut slices)
+        };
+
+        let record_len = self.record_len();
+        let (records, ret) = match filled {
+            Ok(filled) if filled % record_len == 0 => (filled / record_len, Ok(filled / record_len)),
+            Ok(filled) => (filled / record_len, Err(unexpected_eof())),
+            Err(e) => (0, Err(e)),
+        };
+        for ((column, &start), &width) in columns.iter_mut().zip(&starts).zip(&self.widths) {
+            column.truncate(start + width * records);
+        }
+        ret
+    }
+}
+
+/// Reads into `bufs` until they are full or EOF is reached, returning the
+/// number of bytes read.
+fn fill_vectored<R: Read + ?Sized>(reader: &mut R, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+    let mut bufs = VectoredBuf::new(bufs);
+    fill_source(&mut IoSource(reader), &mut bufs, &mut OptsControl::new(&ReadExactOpts::new(), &[]))
+        .map(|()| bufs.filled())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use super::ColumnarReader;
+
+    #[test]
+    fn columns() {
+        let data = [1, 10, 11, 2, 20, 21, 3, 30, 31, 4];
+        let mut read = ColumnarReader::new(&data[..], [1, 2]);
+        let mut columns = [Vec::new(), Vec::new()];
+
+        assert_eq!(read.read_columns(&mut columns, 2).unwrap(), 2);
+        assert_eq!(columns, [vec![1, 2], vec![10, 11, 20, 21]]);
+        let err = read.read_columns(&mut columns, 4).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(columns, [vec![1, 2, 3], vec![10, 11, 20, 21, 30, 31]]);
+        assert_eq!(read.read_columns(&mut columns, 4).unwrap(), 0);
+    }
+
+    #[test]
+    fn empty_field() {
+        let mut read = ColumnarReader::new(&[1, 2, 3, 4][..], [1, 0, 1]);
+        let mut columns = [Vec::new(), Vec::new(), Vec::new()];
+
+        assert_eq!(read.read_columns(&mut columns, 4).unwrap(), 2);
+        assert_eq!(columns, [vec![1, 3], vec![], vec![2, 4]]);
+    }
+}
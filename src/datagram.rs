@@ -0,0 +1,203 @@
+use std::io;
+use std::net::UdpSocket;
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(all(feature = "libc", any(target_os = "linux", target_os = "android")))]
+use std::os::unix::io::AsRawFd;
+
+/// The outcome of receiving a datagram of an exact size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DatagramStatus {
+    /// The datagram was exactly the length of the buffer.
+    Full,
+    /// The datagram was empty, which usually signals the end of a stream of
+    /// datagrams.
+    Empty,
+    /// The datagram was shorter than the buffer.
+    Short {
+        /// The length of the datagram, which was placed at the start of the
+        /// buffer.
+        len: usize,
+    },
+    /// The datagram was longer than the buffer, and was truncated to fit.
+    Truncated,
+}
+
+impl DatagramStatus {
+    fn new(received: usize, len: usize) -> Self {
+        if received == len {
+            DatagramStatus::Full
+        } else if received == 0 {
+            DatagramStatus::Empty
+        } else if received > len {
+            DatagramStatus::Truncated
+        } else {
+            DatagramStatus::Short { len: received }
+        }
+    }
+}
+
+/// An extension trait for datagram sockets.
+pub trait RecvExactExt {
+    /// Receives a datagram into `buf`, reporting whether it was exactly
+    /// `buf.len()` bytes long.
+    ///
+    /// With the `libc` feature on Linux, truncation is detected with
+    /// `MSG_TRUNC`, and on Windows with `WSAEMSGSIZE`. Elsewhere the datagram
+    /// is received into a scratch buffer one byte longer than `buf` and then
+    /// copied. The scratch buffer is on the stack for small datagrams, and is
+    /// otherwise allocated for each datagram, which
+    /// `recv_exact_or_none_with` avoids.
+    fn recv_exact_or_none(&self, buf: &mut [u8]) -> io::Result<DatagramStatus>;
+
+    /// Receives a datagram into `buf` like `recv_exact_or_none`, using
+    /// `scratch` as the scratch buffer where one is needed.
+    ///
+    /// `scratch` is grown to one byte more than `buf.len()` as needed, so
+    /// that reusing it between receives only allocates once.
+    fn recv_exact_or_none_with(&self, buf: &mut [u8], scratch: &mut Vec<u8>) -> io::Result<DatagramStatus>;
+}
+
+/// The length of the scratch buffer of `recv_exact_or_none` on the stack.
+const SCRATCH_LEN: usize = if cfg!(not(any(windows, all(feature = "libc", any(target_os = "linux", target_os = "android"))))) { 0x800 } else { 0 };
+
+macro_rules! impl_recv {
+    ($ty:ty) => {
+        impl RecvExactExt for $ty {
+            fn recv_exact_or_none(&self, buf: &mut [u8]) -> io::Result<DatagramStatus> {
+                #[cfg(not(any(windows, all(feature = "libc", any(target_os = "linux", target_os = "android")))))]
+                if buf.len() >= SCRATCH_LEN {
+                    return self.recv_exact_or_none_with(buf, &mut Vec::new())
+                }
+
+                let received = recv_len(self, buf, &mut [0; SCRATCH_LEN])?;
+                Ok(DatagramStatus::new(received, buf.len()))
+            }
+
+            fn recv_exact_or_none_with(&self, buf: &mut [u8], scratch: &mut Vec<u8>) -> io::Result<DatagramStatus> {
+                if cfg!(not(any(windows, all(feature = "libc", any(target_os = "linux", target_os = "android"))))) && scratch.len() <= buf.len() {
+                    scratch.resize(buf.len() + 1, 0);
+                }
+
+                let received = recv_len(self, buf, scratch)?;
+                Ok(DatagramStatus::new(received, buf.len()))
+            }
+        }
+
+        #[cfg(not(all(feature = "libc", any(target_os = "linux", target_os = "android"))))]
+        impl Recv for $ty {
+            fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+                <$ty>::recv(self, buf)
+            }
+        }
+    };
+}
+
+impl_recv!(UdpSocket);
+#[cfg(unix)]
+impl_recv!(UnixDatagram);
+
+/// A socket that receives datagrams.
+#[cfg(not(all(feature = "libc", any(target_os = "linux", target_os = "android"))))]
+trait Recv {
+    fn recv(&self, buf: &mut [u8]) -> io::Result<usize>;
+}
+
+/// Receives a datagram, returning its full length even if it was truncated.
+#[cfg(all(feature = "libc", any(target_os = "linux", target_os = "android")))]
+fn recv_len<S: AsRawFd>(socket: &S, buf: &mut [u8], _: &mut [u8]) -> io::Result<usize> {
+    let fd = socket.as_raw_fd();
+    retry(|| {
+        // SAFETY: `buf` is valid for writes of `buf.len()` bytes
+        match unsafe { libc::recv(fd, buf.as_mut_ptr().cast(), buf.len(), libc::MSG_TRUNC) } {
+            -1 => Err(io::Error::last_os_error()),
+            received => Ok(received as usize),
+        }
+    })
+}
+
+/// Receives a datagram, returning a length longer than `buf` if it was
+/// truncated.
+#[cfg(windows)]
+fn recv_len<S: Recv>(socket: &S, buf: &mut [u8], _: &mut [u8]) -> io::Result<usize> {
+    match retry(|| socket.recv(buf)) {
+        Err(ref e) if e.raw_os_error() == Some(WSAEMSGSIZE) => Ok(buf.len() + 1),
+        ret => ret,
+    }
+}
+
+/// The Windows error for a datagram that was truncated to fit the buffer.
+#[cfg(windows)]
+const WSAEMSGSIZE: i32 = 10040;
+
+/// Receives a datagram into `scratch`, copying it into `buf` and returning a
+/// length longer than `buf` if it was truncated.
+#[cfg(not(any(windows, all(feature = "libc", any(target_os = "linux", target_os = "android")))))]
+fn recv_len<S: Recv>(socket: &S, buf: &mut [u8], scratch: &mut [u8]) -> io::Result<usize> {
+    let scratch = &mut scratch[..buf.len() + 1];
+    let received = retry(|| socket.recv(scratch))?;
+    let copied = received.min(buf.len());
+    buf[..copied].copy_from_slice(&scratch[..copied]);
+    Ok(received)
+}
+
+fn retry<F: FnMut() -> io::Result<usize>>(mut recv: F) -> io::Result<usize> {
+    loop {
+        match recv() {
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            ret => return ret,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+    use super::{DatagramStatus, RecvExactExt};
+
+    #[test]
+    fn udp() {
+        let recv = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send = UdpSocket::bind("127.0.0.1:0").unwrap();
+        send.connect(recv.local_addr().unwrap()).unwrap();
+        let mut buf = [0; 4];
+
+        send.send(&[1, 2, 3, 4]).unwrap();
+        assert_eq!(recv.recv_exact_or_none(&mut buf).unwrap(), DatagramStatus::Full);
+        assert_eq!(buf, [1, 2, 3, 4]);
+        send.send(&[5, 6]).unwrap();
+        assert_eq!(recv.recv_exact_or_none(&mut buf).unwrap(), DatagramStatus::Short { len: 2 });
+        send.send(&[0; 6]).unwrap();
+        assert_eq!(recv.recv_exact_or_none(&mut buf).unwrap(), DatagramStatus::Truncated);
+        send.send(&[]).unwrap();
+        assert_eq!(recv.recv_exact_or_none(&mut buf).unwrap(), DatagramStatus::Empty);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unix() {
+        let (recv, send) = std::os::unix::net::UnixDatagram::pair().unwrap();
+        let mut buf = [0; 2];
+
+        send.send(&[1, 2, 3]).unwrap();
+        assert_eq!(recv.recv_exact_or_none(&mut buf).unwrap(), DatagramStatus::Truncated);
+        assert_eq!(buf, [1, 2]);
+    }
+
+    #[test]
+    fn udp_scratch() {
+        let recv = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let send = UdpSocket::bind("127.0.0.1:0").unwrap();
+        send.connect(recv.local_addr().unwrap()).unwrap();
+        let mut buf = [0; 0x1000];
+        let mut scratch = Vec::new();
+
+        send.send(&[1; 0x1000]).unwrap();
+        assert_eq!(recv.recv_exact_or_none_with(&mut buf, &mut scratch).unwrap(), DatagramStatus::Full);
+        send.send(&[2; 0x1001]).unwrap();
+        assert_eq!(recv.recv_exact_or_none_with(&mut buf, &mut scratch).unwrap(), DatagramStatus::Truncated);
+        assert_eq!(buf, [2; 0x1000]);
+        send.send(&[3; 0x1001]).unwrap();
+        assert_eq!(recv.recv_exact_or_none(&mut buf).unwrap(), DatagramStatus::Truncated);
+    }
+}
@@ -0,0 +1,183 @@
+use std::{error, fmt};
+use std::io::{self, Read};
+
+/// A decompressing reader that reports a truncated compressed stream
+/// distinctly from the end of the decompressed data.
+///
+/// Decoders fail with `UnexpectedEof` when the compressed stream ends before
+/// they are done, which would otherwise be indistinguishable from a
+/// decompressed record that was cut short. Through this reader, exact reads
+/// with `ReadExactExt` return `false` only at the real end of the stream on a
+/// record boundary, fail with a plain `UnexpectedEof` if the decompressed
+/// data ends partway through a record, and fail with an `UnexpectedEof`
+/// whose inner error is `TruncatedStream` if the compressed stream itself is
+/// truncated.
+#[derive(Debug)]
+pub struct Decompressed<D> {
+    decoder: D,
+}
+
+impl<D> Decompressed<D> {
+    /// Wraps any decoder that reports truncation as `UnexpectedEof`.
+    pub fn new(decoder: D) -> Self {
+        Decompressed {
+            decoder,
+        }
+    }
+
+    /// Gets a reference to the underlying decoder.
+    pub fn get_ref(&self) -> &D {
+        &self.decoder
+    }
+
+    /// Gets a mutable reference to the underlying decoder.
+    pub fn get_mut(&mut self) -> &mut D {
+        &mut self.decoder
+    }
+
+    /// Unwraps this reader, returning the underlying decoder.
+    pub fn into_inner(self) -> D {
+        self.decoder
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<R: Read> Decompressed<::flate2::read::GzDecoder<R>> {
+    /// Decompresses a gzip stream.
+    pub fn gzip(reader: R) -> Self {
+        Self::new(::flate2::read::GzDecoder::new(reader))
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<R: Read> Decompressed<::flate2::read::ZlibDecoder<R>> {
+    /// Decompresses a zlib stream.
+    pub fn zlib(reader: R) -> Self {
+        Self::new(::flate2::read::ZlibDecoder::new(reader))
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl<R: Read> Decompressed<::flate2::read::DeflateDecoder<R>> {
+    /// Decompresses a raw deflate stream.
+    pub fn deflate(reader: R) -> Self {
+        Self::new(::flate2::read::DeflateDecoder::new(reader))
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<R: Read> Decompressed<::zstd::Decoder<'static, io::BufReader<R>>> {
+    /// Decompresses a zstd stream.
+    pub fn zstd(reader: R) -> io::Result<Self> {
+        ::zstd::Decoder::new(reader).map(Self::new)
+    }
+}
+
+impl<D: Read> Read for Decompressed<D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.decoder.read(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => io::Error::new(io::ErrorKind::UnexpectedEof, TruncatedStream {
+                source: e,
+            }),
+            _ => e,
+        })
+    }
+}
+
+/// The error for a compressed stream that ends before its decoder is done.
+///
+/// This is the inner error of the `UnexpectedEof` errors returned by reads
+/// of a `Decompressed` reader.
+#[derive(Debug)]
+pub struct TruncatedStream {
+    source: io::Error,
+}
+
+impl TruncatedStream {
+    /// Returns whether `err` reports a truncated compressed stream.
+    pub fn is(err: &io::Error) -> bool {
+        err.get_ref().is_some_and(|e| e.is::<TruncatedStream>())
+    }
+}
+
+impl fmt::Display for TruncatedStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "compressed stream is truncated: {}", self.source)
+    }
+}
+
+impl error::Error for TruncatedStream {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "flate2")]
+    use std::io::{self, Write};
+    use crate::ReadExactExt;
+    use super::{Decompressed, TruncatedStream};
+
+    #[cfg(feature = "flate2")]
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ::flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn gzip_records() {
+        let data = gzip(&[1, 2, 3, 4]);
+        let mut read = Decompressed::gzip(&data[..]);
+        let mut buf = [0; 2];
+
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn gzip_partial_record() {
+        let data = gzip(&[1, 2, 3]);
+        let mut read = Decompressed::gzip(&data[..]);
+        let mut buf = [0; 2];
+
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        let err = read.read_exact_or_eof(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(!TruncatedStream::is(&err));
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn gzip_truncated() {
+        let data = gzip(&[1, 2, 3, 4]);
+        let mut read = Decompressed::gzip(&data[..data.len() - 1]);
+        let mut buf = [0; 4];
+
+        // the trailer is missing, so only the end of the stream is an error
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        let err = read.read_exact_or_eof(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(TruncatedStream::is(&err));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_truncated() {
+        let data = ::zstd::encode_all(&[1, 2, 3, 4][..], 0).unwrap();
+        let mut buf = [0; 4];
+
+        let mut read = Decompressed::zstd(&data[..]).unwrap();
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+
+        let mut read = Decompressed::zstd(&data[..data.len() / 2]).unwrap();
+        assert!(TruncatedStream::is(&read.read_exact_or_eof(&mut buf).unwrap_err()));
+    }
+}
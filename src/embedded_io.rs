@@ -0,0 +1,162 @@
+//! Support for `embedded_io::Read` types.
+
+use ::embedded_io::{Error, ErrorKind, Read, ReadExactError};
+use crate::source::{fill_slice, Source};
+use crate::ReadExactStatus;
+
+/// An extension trait that applies to all `embedded_io::Read` types.
+pub trait ReadExactExt: Read {
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// This function returns `true` upon successful read, or `false` if no
+    /// data was read. No guarantees about the contents of `buf` are provided
+    /// if the function returns `false` or an error.
+    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> Result<bool, ReadExactError<Self::Error>>;
+
+    /// Reads exactly the number of bytes to fill `buf`, reporting how much
+    /// was read if EOF is encountered first.
+    fn read_exact_or_eof_status(&mut self, buf: &mut [u8]) -> Result<ReadExactStatus, Self::Error>;
+
+    /// Reads until `buf` is full or EOF is reached, returning the number of
+    /// bytes read.
+    fn read_full(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+
+    /// Appends exactly `n` bytes to a `heapless::Vec`, or zero.
+    ///
+    /// The length of `vec` is only extended once all `n` bytes have been
+    /// read; upon EOF or an error it is left as it was. Fails with
+    /// `ReadHeaplessError::Capacity`, without reading, if `vec` cannot hold
+    /// `n` more bytes.
+    #[cfg(feature = "heapless")]
+    fn read_exact_heapless<const N: usize>(&mut self, vec: &mut ::heapless::Vec<u8, N>, n: usize) -> Result<bool, ReadHeaplessError<Self::Error>>;
+
+    /// Fills the spare capacity of a `heapless::Vec` exactly, or reads
+    /// nothing.
+    ///
+    /// This is `read_exact_heapless` for all of the capacity left in `vec`.
+    #[cfg(feature = "heapless")]
+    fn fill_heapless_or_eof<const N: usize>(&mut self, vec: &mut ::heapless::Vec<u8, N>) -> Result<bool, ReadHeaplessError<Self::Error>>;
+}
+
+impl<T: Read + ?Sized> ReadExactExt for T {
+    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> Result<bool, ReadExactError<Self::Error>> {
+        into_result(self.read_exact_or_eof_status(buf)?)
+    }
+
+    fn read_exact_or_eof_status(&mut self, buf: &mut [u8]) -> Result<ReadExactStatus, Self::Error> {
+        let len = buf.len();
+        self.read_full(buf).map(|filled| ReadExactStatus::new(filled, len))
+    }
+
+    fn read_full(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        fill_slice(&mut EmbeddedSource(self), buf)
+    }
+
+    #[cfg(feature = "heapless")]
+    fn read_exact_heapless<const N: usize>(&mut self, vec: &mut ::heapless::Vec<u8, N>, n: usize) -> Result<bool, ReadHeaplessError<Self::Error>> {
+        let status = crate::heapless::append(vec, n, |buf| self.read_full(buf))
+            .ok_or(ReadHeaplessError::Capacity)?
+            .map_err(ReadHeaplessError::Other)?;
+        into_result(status).map_err(ReadHeaplessError::from)
+    }
+
+    #[cfg(feature = "heapless")]
+    fn fill_heapless_or_eof<const N: usize>(&mut self, vec: &mut ::heapless::Vec<u8, N>) -> Result<bool, ReadHeaplessError<Self::Error>> {
+        let n = vec.capacity() - vec.len();
+        self.read_exact_heapless(vec, n)
+    }
+}
+
+/// The error of `ReadExactExt::read_exact_heapless`.
+///
+/// This is `embedded_io::ReadExactError` with a variant for a vector that is
+/// too small, which the `std` extension trait reports as `InvalidInput`.
+#[cfg(feature = "heapless")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReadHeaplessError<E> {
+    /// EOF was reached partway through the bytes.
+    UnexpectedEof,
+    /// The vector cannot hold the requested number of bytes, so nothing was
+    /// read.
+    Capacity,
+    /// The reader failed.
+    Other(E),
+}
+
+#[cfg(feature = "heapless")]
+impl<E> From<ReadExactError<E>> for ReadHeaplessError<E> {
+    fn from(e: ReadExactError<E>) -> Self {
+        match e {
+            ReadExactError::UnexpectedEof => ReadHeaplessError::UnexpectedEof,
+            ReadExactError::Other(e) => ReadHeaplessError::Other(e),
+        }
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<E: core::fmt::Debug> core::fmt::Display for ReadHeaplessError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            ReadHeaplessError::UnexpectedEof => f.write_str("failed to fill whole buffer"),
+            ReadHeaplessError::Capacity => f.write_str("buffer is too small"),
+            ReadHeaplessError::Other(e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+#[cfg(feature = "heapless")]
+impl<E: core::fmt::Debug> core::error::Error for ReadHeaplessError<E> {}
+
+/// The `Source` of an `embedded_io::Read`.
+struct EmbeddedSource<'a, R: ?Sized>(&'a mut R);
+
+impl<R: Read + ?Sized> Source for EmbeddedSource<'_, R> {
+    type Error = R::Error;
+
+    fn read_some(&mut self, buf: &mut [u8]) -> Result<usize, R::Error> {
+        self.0.read(buf)
+    }
+
+    fn is_interrupted(error: &R::Error) -> bool {
+        error.kind() == ErrorKind::Interrupted
+    }
+}
+
+pub(crate) fn into_result<E>(status: ReadExactStatus) -> Result<bool, ReadExactError<E>> {
+    match status {
+        ReadExactStatus::Full => Ok(true),
+        ReadExactStatus::CleanEof => Ok(false),
+        ReadExactStatus::PartialEof { .. } => Err(ReadExactError::UnexpectedEof),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::embedded_io::ReadExactError;
+    use super::ReadExactExt;
+
+    #[test]
+    fn eof() {
+        let mut read = &[][..];
+        let mut buf = [0, 0];
+
+        assert_eq!(read.read_exact_or_eof(&mut buf), Ok(false));
+    }
+
+    #[test]
+    fn ok() {
+        let mut read = &[1, 1, 2][..];
+        let mut buf = [0, 0];
+
+        assert_eq!(read.read_exact_or_eof(&mut buf), Ok(true));
+        assert_eq!(buf, [1, 1]);
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let mut read = &[1][..];
+        let mut buf = [0, 0];
+
+        assert_eq!(read.read_exact_or_eof(&mut buf), Err(ReadExactError::UnexpectedEof));
+    }
+}
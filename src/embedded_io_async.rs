@@ -0,0 +1,81 @@
+//! Support for `embedded_io_async::Read` types.
+
+use ::embedded_io_async::{Error, ErrorKind, ErrorType, Read, ReadExactError};
+use crate::embedded_io::into_result;
+use crate::ReadExactStatus;
+
+/// An extension trait that applies to all `embedded_io_async::Read` types.
+#[allow(async_fn_in_trait)]
+pub trait AsyncReadExactExt: ErrorType {
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// Resolves to `true` upon successful read, or `false` if no data was
+    /// read, in the same manner as `ReadExactExt::read_exact_or_eof`.
+    async fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> Result<bool, ReadExactError<Self::Error>>;
+
+    /// Reads exactly the number of bytes to fill `buf`, reporting how much
+    /// was read if EOF is encountered first.
+    async fn read_exact_or_eof_status(&mut self, buf: &mut [u8]) -> Result<ReadExactStatus, Self::Error>;
+
+    /// Reads until `buf` is full or EOF is reached, returning the number of
+    /// bytes read.
+    async fn read_full(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+impl<T: Read + ?Sized> AsyncReadExactExt for T {
+    async fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> Result<bool, ReadExactError<Self::Error>> {
+        into_result(self.read_exact_or_eof_status(buf).await?)
+    }
+
+    async fn read_exact_or_eof_status(&mut self, buf: &mut [u8]) -> Result<ReadExactStatus, Self::Error> {
+        let len = buf.len();
+        self.read_full(buf).await.map(|filled| ReadExactStatus::new(filled, len))
+    }
+
+    async fn read_full(&mut self, mut buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = buf.len();
+
+        while !buf.is_empty() {
+            match self.read(buf).await {
+                Ok(0) => break,
+                Ok(n) => buf = &mut {buf}[n..],
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(len - buf.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::embedded_io_async::ReadExactError;
+    use crate::test_util::block_on;
+    use super::AsyncReadExactExt;
+
+    #[test]
+    fn eof() {
+        let mut read = &[][..];
+        let mut buf = [0, 0];
+
+        assert_eq!(block_on(read.read_exact_or_eof(&mut buf)), Ok(false));
+    }
+
+    #[test]
+    fn ok() {
+        let mut read = &[1, 1, 2][..];
+        let mut buf = [0, 0];
+
+        assert_eq!(block_on(read.read_exact_or_eof(&mut buf)), Ok(true));
+        assert_eq!(buf, [1, 1]);
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let mut read = &[1][..];
+        let mut buf = [0, 0];
+
+        assert_eq!(block_on(read.read_exact_or_eof(&mut buf)), Err(ReadExactError::UnexpectedEof));
+    }
+}
@@ -0,0 +1,99 @@
+use std::{error, fmt, io};
+use crate::ReadExactStatus;
+
+/// An error of an exact read that keeps truncation apart from I/O errors.
+///
+/// This is returned by `ReadExactExt::read_exact_or_eof_typed`, and converts
+/// into the `io::Error` that `read_exact_or_eof` would have returned.
+#[derive(Debug)]
+pub enum ReadExactError {
+    /// The underlying reader failed.
+    Io(io::Error),
+    /// The reader reached EOF partway through the buffer.
+    Truncated {
+        /// The number of bytes read into the start of the buffer.
+        filled: usize,
+        /// The length of the buffer.
+        needed: usize,
+    },
+}
+
+impl ReadExactError {
+    /// Converts the outcome of `read_exact_or_eof_status` for a buffer of
+    /// `needed` bytes.
+    pub fn from_status(status: ReadExactStatus, needed: usize) -> Result<bool, Self> {
+        match status {
+            ReadExactStatus::Full => Ok(true),
+            ReadExactStatus::CleanEof => Ok(false),
+            ReadExactStatus::PartialEof { filled } => Err(ReadExactError::Truncated { filled, needed }),
+        }
+    }
+
+    /// Returns whether the reader reached EOF partway through the buffer.
+    pub fn is_truncated(&self) -> bool {
+        matches!(self, ReadExactError::Truncated { .. })
+    }
+}
+
+impl fmt::Display for ReadExactError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadExactError::Io(ref e) => fmt::Display::fmt(e, f),
+            ReadExactError::Truncated { filled, needed } => write!(f, "failed to fill whole buffer: read {filled} of {needed} bytes"),
+        }
+    }
+}
+
+impl error::Error for ReadExactError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ReadExactError::Io(ref e) => Some(e),
+            ReadExactError::Truncated { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReadExactError {
+    fn from(e: io::Error) -> Self {
+        ReadExactError::Io(e)
+    }
+}
+
+impl From<ReadExactError> for io::Error {
+    fn from(e: ReadExactError) -> Self {
+        match e {
+            ReadExactError::Io(e) => e,
+            e @ ReadExactError::Truncated { .. } => io::Error::new(io::ErrorKind::UnexpectedEof, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use crate::testing::ScriptedReader;
+    use crate::ReadExactExt;
+    use super::ReadExactError;
+
+    #[test]
+    fn typed() {
+        let mut read = &[1, 2, 3][..];
+        let mut buf = [0; 2];
+
+        assert!(read.read_exact_or_eof_typed(&mut buf).unwrap());
+        let err = read.read_exact_or_eof_typed(&mut buf).unwrap_err();
+        assert!(matches!(err, ReadExactError::Truncated { filled: 1, needed: 2 }));
+        assert_eq!(io::Error::from(err).kind(), io::ErrorKind::UnexpectedEof);
+        assert!(!read.read_exact_or_eof_typed(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn io() {
+        let mut read = ScriptedReader::new().error(io::ErrorKind::ConnectionReset);
+
+        let err = read.read_exact_or_eof_typed(&mut [0]).unwrap_err();
+        assert!(!err.is_truncated());
+        assert!(std::error::Error::source(&err).is_some());
+        assert_eq!(io::Error::from(err).kind(), io::ErrorKind::ConnectionReset);
+    }
+}
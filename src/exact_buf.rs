@@ -0,0 +1,294 @@
+use std::borrow::Cow;
+use std::io::{self, BufRead, Read};
+use crate::{unexpected_eof, CowReadExactExt, ReadExactExt, ReadExactOpts};
+use crate::opts::OptsControl;
+use crate::source::{fill_source, FillControl, IoSource, SliceBuf};
+
+/// A buffered reader whose capacity is a whole number of records.
+///
+/// Each refill reads into the whole buffer at once, and keeps reading until
+/// it ends on a record boundary, so a record never straddles two refills and
+/// `next_record` can return it without copying. Unlike `std::io::BufReader`,
+/// this means a refill may block until the rest of a record arrives. An
+/// error partway through a refill leaves the bytes read so far buffered, and
+/// the refill resumes on the next read.
+#[derive(Debug)]
+pub struct ExactBufReader<R> {
+    reader: R,
+    buf: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+    record_len: usize,
+    // the offset within a record of the start of `buf`, which is only
+    // non-zero once `fill_exact_or_eof` has moved a partial record to it
+    shift: usize,
+}
+
+impl<R> ExactBufReader<R> {
+    /// Creates a reader that buffers `records_per_buf` records of
+    /// `record_len` bytes at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `record_len` or `records_per_buf` is zero.
+    pub fn with_record_size(reader: R, record_len: usize, records_per_buf: usize) -> Self {
+        assert!(record_len > 0 && records_per_buf > 0, "record length and capacity must be non-zero");
+        let capacity = record_len.checked_mul(records_per_buf).expect("capacity overflows usize");
+        ExactBufReader {
+            reader,
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+            record_len,
+            shift: 0,
+        }
+    }
+
+    /// Returns the length of each record.
+    pub fn record_len(&self) -> usize {
+        self.record_len
+    }
+
+    /// Returns the capacity of the internal buffer.
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns the bytes that have been read but not yet consumed.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.filled]
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    ///
+    /// Any buffered bytes are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> ExactBufReader<R> {
+    /// Returns the next record from the buffer, or `None` at EOF.
+    ///
+    /// The record is consumed. A stream that ends partway through a record
+    /// fails with `UnexpectedEof`.
+    pub fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        let record_len = self.record_len;
+        if self.fill_exact_or_eof(record_len)?.is_none() {
+            return Ok(None)
+        }
+
+        let start = self.pos;
+        self.pos += record_len;
+        Ok(Some(&self.buf[start..self.pos]))
+    }
+
+    /// Returns exactly `n` bytes from the internal buffer, or `None` at EOF.
+    ///
+    /// The bytes are not consumed, so `consume(n)` must be called once they
+    /// are no longer needed. If fewer than `n` bytes are buffered, they are
+    /// moved to the front of the buffer and the rest of the `n` bytes are
+    /// read after them, so a record of any length up to the capacity is
+    /// returned without copying it out, even when it straddles a refill. A
+    /// stream that ends partway through the `n` bytes fails with
+    /// `UnexpectedEof`, and any `n` larger than the capacity fails with
+    /// `InvalidInput`.
+    pub fn fill_exact_or_eof(&mut self, n: usize) -> io::Result<Option<&[u8]>> {
+        if n > self.buf.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "record exceeds the buffer capacity"))
+        }
+
+        match self.fill_buf()?.len() {
+            available if available >= n => {},
+            0 => return Ok(None),
+            _ => {
+                self.compact();
+                let mut buf = SliceBuf::new(&mut self.buf[self.filled..n]);
+                let ret = fill_source(&mut IoSource(&mut self.reader), &mut buf, &mut OptsControl::new(&ReadExactOpts::new(), &[]));
+                self.filled += buf.filled();
+                ret?;
+                if self.filled < n {
+                    return Err(unexpected_eof())
+                }
+            },
+        }
+
+        Ok(Some(&self.buf[self.pos..self.pos + n]))
+    }
+
+    /// Moves the unconsumed bytes to the front of the buffer.
+    fn compact(&mut self) {
+        self.buf.copy_within(self.pos..self.filled, 0);
+        self.shift = (self.shift + self.pos) % self.record_len;
+        self.filled -= self.pos;
+        self.pos = 0;
+    }
+}
+
+impl<R: Read> CowReadExactExt for ExactBufReader<R> {
+    /// Reads exactly `n` bytes, or returns `None` if no data was read.
+    ///
+    /// The bytes are borrowed from the internal buffer if they are all in
+    /// it after a refill, as they always are for `n` no larger than
+    /// `record_len` at a record boundary.
+    fn read_exact_cow(&mut self, n: usize) -> io::Result<Option<Cow<'_, [u8]>>> {
+        if self.fill_buf()?.len() >= n {
+            let start = self.pos;
+            self.pos += n;
+            return Ok(Some(Cow::Borrowed(&self.buf[start..self.pos])))
+        }
+
+        self.read_exact_to_vec(n).map(|buf| buf.map(Cow::Owned))
+    }
+}
+
+impl<R: Read> Read for ExactBufReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // bypass the buffer for reads at least as large as it
+        if self.pos == self.filled && buf.len() >= self.buf.len() {
+            return self.reader.read(buf)
+        }
+
+        let n = {
+            let available = self.fill_buf()?;
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for ExactBufReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.filled {
+            self.compact();
+        }
+
+        // an unaligned buffer is the remainder of an interrupted refill, or
+        // the end of the stream
+        let mut buf = SliceBuf::with_filled(&mut self.buf, self.filled);
+        let ret = fill_source(&mut IoSource(&mut self.reader), &mut buf, &mut Aligned {
+            shift: self.shift,
+            record_len: self.record_len,
+        });
+        self.filled = buf.filled();
+        ret?;
+
+        Ok(&self.buf[self.pos..self.filled])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.filled);
+    }
+}
+
+/// The fill policy of a refill, which ends on a record boundary.
+struct Aligned {
+    shift: usize,
+    record_len: usize,
+}
+
+impl<B: ?Sized> FillControl<B, io::Error> for Aligned {
+    fn is_done(&self, filled: usize, len: usize) -> bool {
+        filled == len || (filled > 0 && (self.shift + filled).is_multiple_of(self.record_len))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::io::{self, BufRead, Read};
+    use crate::CowReadExactExt;
+    use crate::testing::ScriptedReader;
+    use super::ExactBufReader;
+
+    #[test]
+    fn records() {
+        let reader = ScriptedReader::new().data(&[1, 2, 3]).data(&[4, 5, 6, 7]).data(&[8, 9]);
+        let mut read = ExactBufReader::with_record_size(reader, 2, 2);
+
+        assert_eq!(read.capacity(), 4);
+        assert_eq!(read.next_record().unwrap(), Some(&[1, 2][..]));
+        assert_eq!(read.buffer(), [3, 4]);
+        assert_eq!(read.next_record().unwrap(), Some(&[3, 4][..]));
+        assert_eq!(read.next_record().unwrap(), Some(&[5, 6][..]));
+        assert_eq!(read.next_record().unwrap(), Some(&[7, 8][..]));
+        assert_eq!(read.next_record().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        read.next_record().unwrap_err();
+    }
+
+    #[test]
+    fn read() {
+        let mut read = ExactBufReader::with_record_size(&[1, 2, 3, 4, 5, 6][..], 2, 1);
+        let mut buf = [0; 3];
+
+        assert_eq!(read.read(&mut buf[..1]).unwrap(), 1);
+        assert_eq!(read.read(&mut buf).unwrap(), 1);
+        assert_eq!(buf[0], 2);
+        assert_eq!(read.read(&mut buf).unwrap(), 3);
+        assert_eq!(buf, [3, 4, 5]);
+        assert_eq!(read.next_record().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn cow() {
+        let mut read = ExactBufReader::with_record_size(&[1, 2, 3, 4, 5, 6][..], 2, 2);
+
+        assert!(matches!(read.read_exact_cow(2).unwrap(), Some(Cow::Borrowed([1, 2]))));
+        assert!(matches!(read.read_exact_cow(3).unwrap(), Some(Cow::Owned(ref buf)) if buf == &[3, 4, 5]));
+        assert!(read.read_exact_cow(2).is_err());
+        assert!(read.read_exact_cow(2).unwrap().is_none());
+    }
+
+    #[test]
+    fn fill_exact() {
+        let mut read = ExactBufReader::with_record_size(&[1, 2, 3, 4, 5, 6, 7][..], 5, 1);
+
+        assert_eq!(read.fill_exact_or_eof(2).unwrap(), Some(&[1, 2][..]));
+        read.consume(2);
+        assert_eq!(read.fill_exact_or_eof(2).unwrap(), Some(&[3, 4][..]));
+        read.consume(2);
+        assert_eq!(read.fill_exact_or_eof(2).unwrap(), Some(&[5, 6][..]));
+        read.consume(2);
+        assert_eq!(read.fill_exact_or_eof(2).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        read.consume(1);
+        assert_eq!(read.fill_exact_or_eof(2).unwrap(), None);
+        assert_eq!(read.fill_exact_or_eof(6).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn fill_exact_keeps_alignment() {
+        let mut read = ExactBufReader::with_record_size(&[1, 2, 3, 4, 5, 6, 7, 8][..], 4, 1);
+
+        assert_eq!(read.fill_buf().unwrap(), [1, 2, 3, 4]);
+        read.consume(3);
+        assert_eq!(read.fill_exact_or_eof(2).unwrap(), Some(&[4, 5][..]));
+        read.consume(1);
+        assert_eq!(read.next_record().unwrap(), Some(&[5, 6, 7, 8][..]));
+        assert_eq!(read.next_record().unwrap(), None);
+    }
+
+    #[test]
+    fn error_keeps_data() {
+        let reader = ScriptedReader::new().data(&[1]).error(io::ErrorKind::WouldBlock).data(&[2]);
+        let mut read = ExactBufReader::with_record_size(reader, 2, 1);
+        let mut buf = [0; 2];
+
+        assert_eq!(read.read(&mut buf[..1]).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(read.buffer(), [1]);
+        assert_eq!(read.read(&mut buf[..1]).unwrap(), 1);
+        assert_eq!(read.next_record().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
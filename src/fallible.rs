@@ -0,0 +1,58 @@
+use std::io;
+use ::fallible_iterator::FallibleIterator;
+#[cfg(unix)]
+use crate::FifoRecords;
+#[cfg(feature = "mmap")]
+use crate::MmapRecords;
+use crate::{FixedWidthRecords, RecordReader, Records};
+
+/// Generates `FallibleIterator` impls from the `next_record` method of each
+/// reader.
+macro_rules! impl_fallible {
+    ($($(#[$attr:meta])* impl<$($gen:ident),*> $ty:ty => $item:ty;)*) => {
+        $(
+            $(#[$attr])*
+            impl<$($gen: io::Read),*> FallibleIterator for $ty {
+                type Item = $item;
+                type Error = io::Error;
+
+                fn next(&mut self) -> io::Result<Option<$item>> {
+                    self.next_record().map(|record| record.map(Into::into))
+                }
+            }
+        )*
+    };
+}
+
+impl_fallible! {
+    impl<R> Records<R> => Vec<u8>;
+    impl<R> RecordReader<R> => Vec<u8>;
+    impl<R> FixedWidthRecords<R> => String;
+    #[cfg(unix)]
+    impl<> FifoRecords => Vec<u8>;
+    #[cfg(feature = "mmap")]
+    impl<> MmapRecords => Vec<u8>;
+}
+
+#[cfg(test)]
+mod tests {
+    use ::fallible_iterator::FallibleIterator;
+    use crate::{FixedWidthRecords, Records};
+
+    #[test]
+    fn records() {
+        let mut records = Records::new(&[1, 2, 3, 4, 5][..], 2);
+
+        assert_eq!(records.next().unwrap(), Some(vec![1, 2]));
+        assert_eq!(records.next().unwrap(), Some(vec![3, 4]));
+        assert!(records.next().is_err());
+        assert_eq!(records.next().unwrap(), None);
+    }
+
+    #[test]
+    fn iterator() {
+        let records = FixedWidthRecords::new(&b"ab\ncd\n"[..], 2).newline(true);
+
+        assert_eq!(records.iterator().collect::<Result<Vec<_>, _>>().unwrap(), ["ab", "cd"]);
+    }
+}
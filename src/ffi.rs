@@ -0,0 +1,146 @@
+//! C functions for exact reads from file descriptors and handles.
+//!
+//! These give C and other foreign callers the EOF semantics of
+//! `ReadExactExt::read_exact_or_eof`, for linking into a `cdylib` or
+//! `staticlib`. Each read returns one of the `READ_EXACT_*` codes, and after
+//! `READ_EXACT_ERR_OS`, the OS error code is available from
+//! `read_exact_last_os_error` on the same thread.
+
+use std::cell::Cell;
+use std::ffi::c_int;
+use std::fs::File;
+use std::io;
+use std::mem::ManuallyDrop;
+use crate::ReadExactExt;
+
+/// The buffer was filled completely.
+pub const READ_EXACT_FULL: c_int = 1;
+/// EOF was reached before any data was read.
+pub const READ_EXACT_EOF: c_int = 0;
+/// EOF was reached after reading only part of the buffer.
+pub const READ_EXACT_ERR_PARTIAL: c_int = -1;
+/// The descriptor was negative, or the buffer pointer was null for a
+/// non-empty buffer.
+pub const READ_EXACT_ERR_INVALID: c_int = -2;
+/// The read failed with an error of the OS.
+pub const READ_EXACT_ERR_OS: c_int = -3;
+/// The read failed with an error that did not come from the OS.
+pub const READ_EXACT_ERR_OTHER: c_int = -4;
+
+thread_local! {
+    static LAST_OS_ERROR: Cell<c_int> = const { Cell::new(0) };
+}
+
+/// Returns the OS error code of the last read on this thread that returned
+/// `READ_EXACT_ERR_OS`, such as an `errno` value on Unix.
+#[no_mangle]
+pub extern "C" fn read_exact_last_os_error() -> c_int {
+    LAST_OS_ERROR.with(Cell::get)
+}
+
+/// Reads exactly `len` bytes from the file descriptor `fd` into `ptr`, or
+/// zero.
+///
+/// The descriptor is borrowed, and is not closed.
+///
+/// # Safety
+///
+/// `fd` must be an open file descriptor, and `ptr` must be valid for writes
+/// of `len` bytes unless `len` is zero.
+#[cfg(unix)]
+#[no_mangle]
+pub unsafe extern "C" fn read_exact_or_eof_fd(fd: c_int, ptr: *mut u8, len: usize) -> c_int {
+    use std::os::unix::io::FromRawFd;
+
+    if fd < 0 {
+        return READ_EXACT_ERR_INVALID
+    }
+    // SAFETY: the caller guarantees that `fd` is open, and the file is never
+    // dropped, so it stays open
+    let file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+    // SAFETY: the caller guarantees that `ptr` is valid for `len` bytes
+    unsafe { read_exact(file, ptr, len) }
+}
+
+/// Reads exactly `len` bytes from the file handle `handle` into `ptr`, or
+/// zero.
+///
+/// The handle is borrowed, and is not closed.
+///
+/// # Safety
+///
+/// `handle` must be an open handle, and `ptr` must be valid for writes of
+/// `len` bytes unless `len` is zero.
+#[cfg(windows)]
+#[no_mangle]
+pub unsafe extern "C" fn read_exact_or_eof_handle(handle: *mut std::ffi::c_void, ptr: *mut u8, len: usize) -> c_int {
+    use std::os::windows::io::FromRawHandle;
+
+    // SAFETY: the caller guarantees that `handle` is open, and the file is
+    // never dropped, so it stays open
+    let file = ManuallyDrop::new(unsafe { File::from_raw_handle(handle) });
+    // SAFETY: the caller guarantees that `ptr` is valid for `len` bytes
+    unsafe { read_exact(file, ptr, len) }
+}
+
+/// Reads exactly `len` bytes from `file` into `ptr`, returning the code of
+/// the outcome.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `len` bytes unless `len` is zero.
+unsafe fn read_exact(mut file: ManuallyDrop<File>, ptr: *mut u8, len: usize) -> c_int {
+    let buf = match len {
+        0 => &mut [][..],
+        _ if ptr.is_null() => return READ_EXACT_ERR_INVALID,
+        // SAFETY: the caller guarantees that `ptr` is valid for `len` bytes
+        _ => unsafe { std::slice::from_raw_parts_mut(ptr, len) },
+    };
+
+    match file.read_exact_or_eof(buf) {
+        Ok(true) => READ_EXACT_FULL,
+        Ok(false) => READ_EXACT_EOF,
+        Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => READ_EXACT_ERR_PARTIAL,
+        Err(e) => match crate::adapters::raw_os_error(&e) {
+            Some(code) => {
+                LAST_OS_ERROR.with(|last| last.set(code));
+                READ_EXACT_ERR_OS
+            },
+            None => READ_EXACT_ERR_OTHER,
+        },
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::Write;
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::UnixStream;
+    use super::*;
+
+    #[test]
+    fn fd() {
+        let (mut tx, rx) = UnixStream::pair().unwrap();
+        tx.write_all(&[1, 2, 3]).unwrap();
+        drop(tx);
+        let mut buf = [0; 2];
+        let read = |buf: &mut [u8]| unsafe { read_exact_or_eof_fd(rx.as_raw_fd(), buf.as_mut_ptr(), buf.len()) };
+
+        assert_eq!(read(&mut buf), READ_EXACT_FULL);
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(read(&mut buf), READ_EXACT_ERR_PARTIAL);
+        assert_eq!(read(&mut buf), READ_EXACT_EOF);
+        assert_eq!(unsafe { read_exact_or_eof_fd(rx.as_raw_fd(), std::ptr::null_mut(), 1) }, READ_EXACT_ERR_INVALID);
+    }
+
+    #[test]
+    fn os_error() {
+        // the write end of a pipe is open, but reading it fails with EBADF
+        let (_read, write) = std::io::pipe().unwrap();
+
+        assert_eq!(unsafe { read_exact_or_eof_fd(-1, [0].as_mut_ptr(), 1) }, READ_EXACT_ERR_INVALID);
+        assert_eq!(unsafe { read_exact_or_eof_fd(write.as_raw_fd(), [0].as_mut_ptr(), 1) }, READ_EXACT_ERR_OS);
+        // EBADF is 9 on every Unix
+        assert_eq!(read_exact_last_os_error(), 9);
+    }
+}
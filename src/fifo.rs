@@ -0,0 +1,132 @@
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::{unexpected_eof, ReadExactExt, ReadExactStatus};
+
+/// A reader of fixed-size records from a named pipe.
+///
+/// Opening a FIFO blocks until a writer opens it too, and the reader sees
+/// EOF once every writer has closed it. This suits command channels, where
+/// clients connect, write a few records and disconnect.
+#[derive(Debug)]
+pub struct FifoRecords {
+    path: PathBuf,
+    file: File,
+    buf: Vec<u8>,
+    reopen: bool,
+}
+
+impl FifoRecords {
+    /// Opens the FIFO at `path` for reading `record_len` byte records,
+    /// blocking until a writer appears.
+    pub fn open<P: AsRef<Path>>(path: P, record_len: usize) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        Ok(FifoRecords {
+            file: File::open(&path)?,
+            path,
+            buf: vec![0; record_len],
+            reopen: false,
+        })
+    }
+
+    /// Sets whether the FIFO is reopened when its writers close it, rather
+    /// than that ending the records.
+    ///
+    /// When enabled, a close on a record boundary waits for the next writer
+    /// to appear and carries on reading. The default is `false`.
+    pub fn reopen(mut self, reopen: bool) -> Self {
+        self.reopen = reopen;
+        self
+    }
+
+    /// Returns the length of each record.
+    pub fn record_len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Gets a reference to the open FIFO.
+    pub fn get_ref(&self) -> &File {
+        &self.file
+    }
+
+    /// Unwraps this reader, returning the open FIFO.
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+
+    /// Reads the next record, or returns `None` once the writers close the
+    /// FIFO on a record boundary and it is not to be reopened.
+    ///
+    /// A writer that closes partway through a record is an `UnexpectedEof`
+    /// error either way. The returned slice borrows the internal buffer,
+    /// which is overwritten by the next call.
+    pub fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        loop {
+            match self.file.read_exact_or_eof_status(&mut self.buf)? {
+                ReadExactStatus::Full => return Ok(Some(&self.buf)),
+                ReadExactStatus::CleanEof if self.reopen => self.file = File::open(&self.path)?,
+                ReadExactStatus::CleanEof => return Ok(None),
+                ReadExactStatus::PartialEof { .. } => return Err(unexpected_eof()),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "libc"))]
+mod tests {
+    use std::ffi::CString;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+    use std::thread;
+    use super::FifoRecords;
+
+    fn mkfifo(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("read_exact-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let cpath = CString::new(path.as_os_str().as_bytes()).unwrap();
+        // SAFETY: `cpath` is a valid NUL-terminated string
+        assert_eq!(unsafe { ::libc::mkfifo(cpath.as_ptr(), 0o600) }, 0);
+        path
+    }
+
+    fn write(path: &PathBuf, data: &[u8]) {
+        OpenOptions::new().write(true).open(path).unwrap().write_all(data).unwrap();
+    }
+
+    #[test]
+    fn eof() {
+        let path = mkfifo("fifo-eof");
+        let writer = thread::spawn({
+            let path = path.clone();
+            move || write(&path, &[1, 2])
+        });
+        let mut records = FifoRecords::open(&path, 2).unwrap();
+
+        assert_eq!(records.next_record().unwrap(), Some(&[1, 2][..]));
+        assert_eq!(records.next_record().unwrap(), None);
+        writer.join().unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reopen() {
+        let path = mkfifo("fifo-reopen");
+        let writer = thread::spawn({
+            let path = path.clone();
+            move || {
+                write(&path, &[1, 2]);
+                write(&path, &[3, 4]);
+                write(&path, &[5]);
+            }
+        });
+        let mut records = FifoRecords::open(&path, 2).unwrap().reopen(true);
+
+        assert_eq!(records.next_record().unwrap(), Some(&[1, 2][..]));
+        assert_eq!(records.next_record().unwrap(), Some(&[3, 4][..]));
+        assert!(records.next_record().is_err());
+        writer.join().unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
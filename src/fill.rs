@@ -0,0 +1,276 @@
+use std::io::{self, Read};
+use crate::source::{fill_source, IoSource, NonBlocking, Plain, SliceBuf};
+use crate::ReadExactStatus;
+
+/// The progress of an `ExactFill`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FillState {
+    /// The buffer has been filled completely.
+    Full,
+    /// The given number of bytes are still needed to fill the buffer.
+    Needs(usize),
+}
+
+/// A sans-IO exact fill, for sources that aren't `Read`.
+///
+/// Data is fed in as it arrives, and the accounting follows that of
+/// `ReadExactExt::read_exact_or_eof_status`.
+#[derive(Clone, Debug)]
+pub struct ExactFill {
+    buf: Vec<u8>,
+    filled: usize,
+}
+
+impl ExactFill {
+    /// Creates a new fill of `len` bytes.
+    pub fn new(len: usize) -> Self {
+        ExactFill {
+            buf: vec![0; len],
+            filled: 0,
+        }
+    }
+
+    /// Copies as much of `data` as is needed into the buffer, returning the
+    /// number of bytes consumed.
+    pub fn feed(&mut self, data: &[u8]) -> usize {
+        let n = data.len().min(self.buf.len() - self.filled);
+        self.buf[self.filled..self.filled + n].copy_from_slice(&data[..n]);
+        self.filled += n;
+        n
+    }
+
+    /// Returns the current progress of the fill.
+    pub fn state(&self) -> FillState {
+        match self.buf.len() - self.filled {
+            0 => FillState::Full,
+            needs => FillState::Needs(needs),
+        }
+    }
+
+    /// Returns the outcome of the fill if the source were to end now.
+    pub fn eof(&self) -> ReadExactStatus {
+        ReadExactStatus::new(self.filled, self.buf.len())
+    }
+
+    /// Returns the bytes filled so far.
+    pub fn filled(&self) -> &[u8] {
+        &self.buf[..self.filled]
+    }
+
+    /// Starts a new fill of the same length, reusing the buffer.
+    pub fn reset(&mut self) {
+        self.filled = 0;
+    }
+
+    /// Unwraps this fill, returning the bytes filled so far.
+    pub fn into_inner(mut self) -> Vec<u8> {
+        self.buf.truncate(self.filled);
+        self.buf
+    }
+}
+
+/// The outcome of `PartialFill::advance`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FillStatus {
+    /// The record has been filled, and is available from `record`.
+    Complete,
+    /// The source would block before the record could be filled.
+    Pending,
+    /// The source reached EOF on a record boundary.
+    Eof,
+}
+
+/// A resumable exact fill for nonblocking sources, such as those registered
+/// with a `mio` event loop.
+///
+/// `advance` is called on each readable event, and keeps the progress of a
+/// partially filled record between them.
+#[derive(Clone, Debug)]
+pub struct PartialFill {
+    fill: ExactFill,
+}
+
+impl PartialFill {
+    /// Creates a new fill of `len` byte records.
+    pub fn new(len: usize) -> Self {
+        PartialFill {
+            fill: ExactFill::new(len),
+        }
+    }
+
+    /// Reads from `source` until the record is filled, the source would
+    /// block, or EOF is reached.
+    ///
+    /// A record that is complete is discarded by the next call, so the
+    /// caller should consume it from `record` first. Edge-triggered event
+    /// loops must keep calling this until it returns `Pending`, as more
+    /// records may already be available. EOF partway through a record fails
+    /// with `UnexpectedEof`.
+    pub fn advance<R: Read + ?Sized>(&mut self, source: &mut R) -> io::Result<FillStatus> {
+        if self.fill.state() == FillState::Full {
+            self.fill.reset();
+        }
+
+        let fill = &mut self.fill;
+        let mut buf = SliceBuf::with_filled(&mut fill.buf, fill.filled);
+        let mut control = NonBlocking { blocked: false };
+        let ret = fill_source(&mut IoSource(source), &mut buf, &mut control);
+        fill.filled = buf.filled();
+        ret?;
+
+        match fill.state() {
+            FillState::Full => Ok(FillStatus::Complete),
+            FillState::Needs(..) if control.blocked => Ok(FillStatus::Pending),
+            FillState::Needs(..) => fill.eof().into_result().map(|_| FillStatus::Eof),
+        }
+    }
+
+    /// Returns the completed record, if any.
+    pub fn record(&self) -> Option<&[u8]> {
+        match self.fill.state() {
+            FillState::Full => Some(&self.fill.buf),
+            FillState::Needs(..) => None,
+        }
+    }
+
+    /// Returns the current progress of the record.
+    pub fn state(&self) -> FillState {
+        self.fill.state()
+    }
+
+    /// Discards any partially filled record.
+    pub fn reset(&mut self) {
+        self.fill.reset()
+    }
+}
+
+/// An exact fill of a caller's buffer that can be continued against another
+/// reader after an error.
+///
+/// This suits sources that can be reopened at an offset, such as a server
+/// that supports range requests: when a read fails partway through a record,
+/// `filled` gives the offset to resume from, and `fill_from` completes the
+/// same buffer from the new reader.
+#[derive(Debug)]
+pub struct ResumableFill<'a> {
+    buf: &'a mut [u8],
+    filled: usize,
+}
+
+impl<'a> ResumableFill<'a> {
+    /// Creates a new fill of `buf`.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        ResumableFill {
+            buf,
+            filled: 0,
+        }
+    }
+
+    /// Returns the number of bytes of the buffer filled so far.
+    pub fn filled(&self) -> usize {
+        self.filled
+    }
+
+    /// Returns the current progress of the fill.
+    pub fn state(&self) -> FillState {
+        match self.buf.len() - self.filled {
+            0 => FillState::Full,
+            needs => FillState::Needs(needs),
+        }
+    }
+
+    /// Reads from `reader` until the buffer is filled.
+    ///
+    /// Returns `false` if `reader` was at EOF before any byte of the buffer
+    /// was read, in the same manner as `ReadExactExt::read_exact_or_eof`.
+    /// Upon any error, including `UnexpectedEof`, the bytes read so far are
+    /// kept, and the fill may be continued with another reader.
+    pub fn fill_from<R: Read + ?Sized>(&mut self, reader: &mut R) -> io::Result<bool> {
+        let mut buf = SliceBuf::with_filled(self.buf, self.filled);
+        let ret = fill_source(&mut IoSource(reader), &mut buf, &mut Plain);
+        self.filled = buf.filled();
+        ret?;
+
+        ReadExactStatus::new(self.filled, self.buf.len()).into_result()
+    }
+
+    /// Unwraps this fill, returning the buffer.
+    pub fn into_inner(self) -> &'a mut [u8] {
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use crate::testing::ScriptedReader;
+    use crate::ReadExactStatus;
+    use super::{ExactFill, FillState, FillStatus, PartialFill, ResumableFill};
+
+    #[test]
+    fn feed() {
+        let mut fill = ExactFill::new(3);
+        assert_eq!(fill.eof(), ReadExactStatus::CleanEof);
+
+        assert_eq!(fill.feed(&[1]), 1);
+        assert_eq!(fill.state(), FillState::Needs(2));
+        assert_eq!(fill.eof(), ReadExactStatus::PartialEof { filled: 1 });
+
+        assert_eq!(fill.feed(&[2, 3, 4]), 2);
+        assert_eq!(fill.state(), FillState::Full);
+        assert_eq!(fill.eof(), ReadExactStatus::Full);
+        assert_eq!(fill.feed(&[4]), 0);
+        assert_eq!(fill.into_inner(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn reset() {
+        let mut fill = ExactFill::new(1);
+
+        fill.feed(&[1]);
+        fill.reset();
+        assert_eq!(fill.state(), FillState::Needs(1));
+        assert!(fill.filled().is_empty());
+    }
+
+    #[test]
+    fn partial() {
+        let mut read = ScriptedReader::new()
+            .data(&[1]).error(ErrorKind::WouldBlock)
+            .data(&[2, 3, 4]).error(ErrorKind::WouldBlock)
+            .data(&[5]);
+        let mut fill = PartialFill::new(2);
+
+        assert_eq!(fill.advance(&mut read).unwrap(), FillStatus::Pending);
+        assert_eq!(fill.state(), FillState::Needs(1));
+        assert_eq!(fill.advance(&mut read).unwrap(), FillStatus::Complete);
+        assert_eq!(fill.record(), Some(&[1, 2][..]));
+        assert_eq!(fill.advance(&mut read).unwrap(), FillStatus::Complete);
+        assert_eq!(fill.record(), Some(&[3, 4][..]));
+        assert_eq!(fill.advance(&mut read).unwrap(), FillStatus::Pending);
+        assert!(fill.record().is_none());
+        assert!(fill.advance(&mut read).is_err());
+    }
+
+    #[test]
+    fn partial_eof() {
+        let mut fill = PartialFill::new(2);
+
+        assert_eq!(fill.advance(&mut &[1, 2][..]).unwrap(), FillStatus::Complete);
+        assert_eq!(fill.advance(&mut &[][..]).unwrap(), FillStatus::Eof);
+    }
+
+    #[test]
+    fn resumable() {
+        let mut buf = [0; 4];
+        let mut fill = ResumableFill::new(&mut buf);
+
+        let mut read = ScriptedReader::new().data(&[1]).error(ErrorKind::ConnectionReset);
+        assert_eq!(fill.fill_from(&mut read).unwrap_err().kind(), ErrorKind::ConnectionReset);
+        assert_eq!(fill.filled(), 1);
+        assert_eq!(fill.fill_from(&mut &[2][..]).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(fill.state(), FillState::Needs(2));
+        assert!(fill.fill_from(&mut &[3, 4, 5][..]).unwrap());
+        assert_eq!(fill.into_inner(), [1, 2, 3, 4]);
+    }
+}
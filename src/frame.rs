@@ -0,0 +1,290 @@
+use std::io;
+use crate::{unexpected_eof, ReadExactExt};
+
+/// The width of a frame's length prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LengthPrefix {
+    /// A 2 byte prefix.
+    U16,
+    /// A 4 byte prefix.
+    U32,
+    /// An 8 byte prefix.
+    U64,
+    /// A variable width unsigned LEB128 prefix, as used by protobuf's
+    /// delimited streams.
+    ///
+    /// The byte order is ignored for this prefix.
+    Varint,
+}
+
+
+/// A byte order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endian {
+    /// Most significant byte first.
+    Big,
+    /// Least significant byte first.
+    Little,
+}
+
+/// Describes the encoding of length-prefixed frames.
+///
+/// The default format uses a big-endian `u32` prefix with no maximum length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FrameFormat {
+    prefix: LengthPrefix,
+    endian: Endian,
+    max_len: usize,
+}
+
+impl FrameFormat {
+    /// Creates a big-endian format with the given prefix width and no
+    /// maximum length.
+    pub const fn new(prefix: LengthPrefix) -> Self {
+        FrameFormat {
+            prefix,
+            endian: Endian::Big,
+            max_len: usize::MAX,
+        }
+    }
+
+    /// Sets the byte order of the length prefix.
+    pub const fn endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// Sets the maximum accepted payload length.
+    ///
+    /// The payload buffer is allocated before it is read, so this should be
+    /// set when reading from untrusted sources to bound memory use.
+    pub const fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+}
+
+impl Default for FrameFormat {
+    fn default() -> Self {
+        FrameFormat::new(LengthPrefix::U32)
+    }
+}
+
+/// Reads a length prefix, or returns `None` at EOF before the prefix.
+fn read_len<R: io::Read + ?Sized>(mut reader: &mut R, format: &FrameFormat) -> io::Result<Option<usize>> {
+    let width = match prefix_width(format) {
+        Some(width) => width,
+        None => return match read_varint(reader)? {
+            Some(len) => check_len(len, format).map(Some),
+            None => Ok(None),
+        },
+    };
+    let mut prefix = [0; 8];
+    if !reader.read_exact_or_eof(&mut prefix[..width])? {
+        return Ok(None)
+    }
+
+    decode_len(&prefix[..width], format).map(Some)
+}
+
+/// Reads an unsigned LEB128 integer, or returns `None` at EOF before its
+/// first byte.
+fn read_varint<R: io::Read + ?Sized>(mut reader: &mut R) -> io::Result<Option<u64>> {
+    let mut varint = Varint::default();
+    loop {
+        let mut byte = [0];
+        if !reader.read_exact_or_eof(&mut byte)? {
+            return match varint.is_empty() {
+                true => Ok(None),
+                false => Err(unexpected_eof()),
+            }
+        }
+
+        if let Some(value) = varint.push(byte[0])? {
+            return Ok(Some(value))
+        }
+    }
+}
+
+/// Returns the width of the length prefix of `format`, or `None` for a
+/// varint.
+pub(crate) fn prefix_width(format: &FrameFormat) -> Option<usize> {
+    match format.prefix {
+        LengthPrefix::U16 => Some(2),
+        LengthPrefix::U32 => Some(4),
+        LengthPrefix::U64 => Some(8),
+        LengthPrefix::Varint => None,
+    }
+}
+
+/// Decodes a fixed-width length prefix of `format`.
+pub(crate) fn decode_len(bytes: &[u8], format: &FrameFormat) -> io::Result<usize> {
+    let mut prefix = [0; 8];
+    let len = match format.endian {
+        Endian::Big => {
+            prefix[8 - bytes.len()..].copy_from_slice(bytes);
+            u64::from_be_bytes(prefix)
+        },
+        Endian::Little => {
+            prefix[..bytes.len()].copy_from_slice(bytes);
+            u64::from_le_bytes(prefix)
+        },
+    };
+    check_len(len, format)
+}
+
+/// An unsigned LEB128 integer being decoded one byte at a time.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Varint {
+    value: u64,
+    shift: u32,
+}
+
+impl Varint {
+    /// Returns whether no byte has been decoded yet.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.shift == 0
+    }
+
+    /// Decodes the next byte, returning the integer once it is complete.
+    pub(crate) fn push(&mut self, byte: u8) -> io::Result<Option<u64>> {
+        let bits = u64::from(byte & 0x7f);
+        if self.shift == 63 && bits > 1 {
+            return Err(varint_overflow())
+        }
+        self.value |= bits << self.shift;
+        if byte & 0x80 == 0 {
+            return Ok(Some(self.value))
+        }
+
+        self.shift += 7;
+        match self.shift {
+            shift if shift > 63 => Err(varint_overflow()),
+            _ => Ok(None),
+        }
+    }
+}
+
+fn varint_overflow() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "varint length prefix overflows u64")
+}
+
+pub(crate) fn check_len(len: u64, format: &FrameFormat) -> io::Result<usize> {
+    match usize::try_from(len) {
+        Ok(len) if len <= format.max_len => Ok(len),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "frame length exceeds maximum")),
+    }
+}
+
+pub(crate) fn read_frame<R: io::Read + ?Sized>(mut reader: &mut R, format: &FrameFormat) -> io::Result<Option<Vec<u8>>> {
+    let len = match read_len(reader, format)? {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+
+    match reader.read_exact_to_vec(len)? {
+        Some(payload) => Ok(Some(payload)),
+        None => Err(unexpected_eof()),
+    }
+}
+
+#[cfg(feature = "bincode")]
+pub(crate) fn read_message<T: ::serde::de::DeserializeOwned, R: io::Read + ?Sized>(reader: &mut R, format: &FrameFormat) -> io::Result<Option<T>> {
+    match read_frame(reader, format)? {
+        Some(payload) => ::bincode::deserialize(&payload)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use crate::ReadExactExt;
+    use super::{Endian, FrameFormat, LengthPrefix};
+
+    #[test]
+    fn frames() {
+        let mut read = &[0, 0, 0, 2, 1, 2, 0, 0, 0, 0][..];
+        let format = FrameFormat::default();
+
+        assert_eq!(read.read_frame_or_eof(&format).unwrap(), Some(vec![1, 2]));
+        assert_eq!(read.read_frame_or_eof(&format).unwrap(), Some(vec![]));
+        assert_eq!(read.read_frame_or_eof(&format).unwrap(), None);
+    }
+
+    #[test]
+    fn little_endian() {
+        let mut read = &[1, 0, 3][..];
+        let format = FrameFormat::new(LengthPrefix::U16).endian(Endian::Little);
+
+        assert_eq!(read.read_frame_or_eof(&format).unwrap(), Some(vec![3]));
+    }
+
+    #[test]
+    fn varint() {
+        let mut payload = vec![0xac, 0x02];
+        payload.extend_from_slice(&[7; 300]);
+        payload.push(0);
+        let mut read = &payload[..];
+        let format = FrameFormat::new(LengthPrefix::Varint);
+
+        assert_eq!(read.read_frame_or_eof(&format).unwrap(), Some(vec![7; 300]));
+        assert_eq!(read.read_frame_or_eof(&format).unwrap(), Some(vec![]));
+        assert_eq!(read.read_frame_or_eof(&format).unwrap(), None);
+    }
+
+    #[test]
+    fn varint_truncated() {
+        let format = FrameFormat::new(LengthPrefix::Varint);
+
+        let ret = (&[0x80][..]).read_frame_or_eof(&format);
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        let ret = (&[0xff; 11][..]).read_frame_or_eof(&format);
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn truncated() {
+        let format = FrameFormat::default();
+
+        assert!((&[0, 0][..]).read_frame_or_eof(&format).is_err());
+        assert!((&[0, 0, 0, 2, 1][..]).read_frame_or_eof(&format).is_err());
+    }
+
+    #[test]
+    fn max_len() {
+        let mut read = &[0, 0, 0, 3, 1, 2, 3][..];
+        let format = FrameFormat::default().max_len(2);
+
+        let ret = read.read_frame_or_eof(&format);
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn messages() {
+        let mut data = Vec::new();
+        for message in [(1u16, "ab".to_owned()), (2, "c".to_owned())] {
+            let payload = ::bincode::serialize(&message).unwrap();
+            data.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            data.extend_from_slice(&payload);
+        }
+        let mut read = &data[..];
+        let format = FrameFormat::default();
+
+        assert_eq!(read.read_message_or_eof(&format).unwrap(), Some((1u16, "ab".to_owned())));
+        assert_eq!(read.read_message_or_eof(&format).unwrap(), Some((2u16, "c".to_owned())));
+        assert_eq!(read.read_message_or_eof::<(u16, String)>(&format).unwrap(), None);
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn invalid_message() {
+        let mut read = &[0, 0, 0, 1, 0xff][..];
+
+        let ret = read.read_message_or_eof::<String>(&FrameFormat::default());
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
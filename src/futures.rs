@@ -0,0 +1,268 @@
+//! Support for `futures::io::AsyncRead` types.
+//!
+//! Both `async_std::io::Read` and `futures_lite::AsyncRead` are re-exports of
+//! this trait, so the `async-std` feature simply enables this module.
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use futures_core::{FusedStream, Stream};
+use futures_io::{AsyncRead, AsyncWrite};
+use crate::async_ext::async_read_exact_ext;
+use crate::{FrameFormat, ReadExactOpts, ReadExactState, ReadExactStatus};
+
+/// Issues a single read into `buf`.
+fn poll_read_some<R: AsyncRead + ?Sized>(reader: Pin<&mut R>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+    reader.poll_read(cx, buf)
+}
+
+/// Issues a single write of `buf`.
+fn poll_write_some<W: AsyncWrite + ?Sized>(writer: Pin<&mut W>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+    writer.poll_write(cx, buf)
+}
+
+/// Flushes the writer.
+fn poll_flush<W: AsyncWrite + ?Sized>(writer: Pin<&mut W>, cx: &mut Context) -> Poll<io::Result<()>> {
+    writer.poll_flush(cx)
+}
+
+async_read_exact_ext!("An extension trait that applies to all `futures::io::AsyncRead` types.");
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll, Waker};
+    use futures_io::{AsyncRead, AsyncWrite};
+    use futures_lite::StreamExt;
+    use crate::{FrameFormat, LengthPrefix, ReadExactOpts, ReadExactState, ReadExactStatus};
+    use crate::test_util::block_on;
+    use super::{exchange_exact, poll_read_exact_or_eof, AsyncReadExactExt};
+
+    /// Returns `Pending` before every read, and reads one byte at a time.
+    struct Trickle(bool, &'static [u8]);
+
+    impl AsyncRead for Trickle {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            self.0 = !self.0;
+            if self.0 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending
+            }
+
+            match (buf.first_mut(), self.1.split_first()) {
+                (Some(dest), Some((&byte, rest))) => {
+                    *dest = byte;
+                    self.1 = rest;
+                    Poll::Ready(Ok(1))
+                },
+                _ => Poll::Ready(Ok(0)),
+            }
+        }
+    }
+
+    /// Fails every read with `ConnectionReset`.
+    struct Reset;
+
+    impl AsyncRead for Reset {
+        fn poll_read(self: Pin<&mut Self>, _: &mut Context, _: &mut [u8]) -> Poll<io::Result<usize>> {
+            Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()))
+        }
+    }
+
+    /// Reads from the first slice, and writes to the vector.
+    struct Duplex(&'static [u8], Vec<u8>);
+
+    impl AsyncRead for Duplex {
+        fn poll_read(mut self: Pin<&mut Self>, _: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            Poll::Ready(io::Read::read(&mut self.0, buf))
+        }
+    }
+
+    impl AsyncWrite for Duplex {
+        fn poll_write(mut self: Pin<&mut Self>, _: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+            self.1.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn exchange() {
+        let mut stream = Duplex(&[3, 4, 5], Vec::new());
+        let mut buf = [0; 2];
+
+        assert!(block_on(exchange_exact(&mut stream, &[1], &mut buf)).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert!(block_on(exchange_exact(&mut stream, &[2], &mut buf)).is_err());
+        assert!(!block_on(exchange_exact(&mut stream, &[], &mut buf)).unwrap());
+        assert_eq!(stream.1, [1, 2]);
+    }
+
+    #[test]
+    fn eof() {
+        let mut read = &[][..];
+        let mut buf = [0, 0];
+
+        let ret = block_on(read.read_exact_or_eof(&mut buf));
+
+        assert!(!ret.unwrap());
+    }
+
+    #[test]
+    fn ok() {
+        let mut read = &[1, 1, 2][..];
+        let mut buf = [0, 0];
+
+        let ret = block_on(read.read_exact_or_eof(&mut buf));
+
+        assert!(ret.unwrap());
+        assert_eq!(buf, [1, 1]);
+        assert_eq!(read, [2]);
+    }
+
+    #[test]
+    fn futures_lite() {
+        let mut read = futures_lite::io::Cursor::new(vec![1, 1, 2]);
+        let mut buf = [0, 0];
+
+        let ret = block_on(read.read_exact_or_eof(&mut buf));
+
+        assert!(ret.unwrap());
+        assert_eq!(buf, [1, 1]);
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let mut read = &[1][..];
+        let mut buf = [0, 0];
+
+        let ret = block_on(read.read_exact_or_eof(&mut buf));
+
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn poll_resume() {
+        let mut read = Trickle(false, &[1, 2, 3]);
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut state = ReadExactState::new();
+        let mut buf = [0, 0];
+
+        assert!(poll_read_exact_or_eof(Pin::new(&mut read), &mut cx, &mut state, &mut buf).is_pending());
+        assert!(poll_read_exact_or_eof(Pin::new(&mut read), &mut cx, &mut state, &mut buf).is_pending());
+        assert_eq!(state.filled(), 1);
+
+        let ret = poll_read_exact_or_eof(Pin::new(&mut read), &mut cx, &mut state, &mut buf);
+        assert!(matches!(ret, Poll::Ready(Ok(true))));
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(state.filled(), 0);
+    }
+
+    #[test]
+    fn exact_chunks() {
+        let mut chunks = Trickle(false, &[1, 2, 3, 4, 5]).exact_chunks::<2>();
+
+        assert_eq!(block_on(chunks.next()).unwrap().unwrap(), [1, 2]);
+        assert_eq!(block_on(chunks.next()).unwrap().unwrap(), [3, 4]);
+        assert!(block_on(chunks.next()).unwrap().is_err());
+        assert!(block_on(chunks.next()).is_none());
+    }
+
+    #[test]
+    fn with_opts() {
+        let mut buf = [0, 0];
+
+        assert!(block_on(Reset.read_exact_or_eof(&mut buf)).is_err());
+        let opts = ReadExactOpts::new().connection_reset_eof(true);
+        assert!(!block_on(Reset.read_exact_or_eof_with(&opts, &mut buf)).unwrap());
+    }
+
+    #[test]
+    fn status() {
+        let mut read = Trickle(false, &[1]);
+        let mut buf = [0, 0];
+
+        let ret = block_on(read.read_exact_or_eof_status(&mut buf));
+
+        assert_eq!(ret.unwrap(), ReadExactStatus::PartialEof { filled: 1 });
+    }
+
+    #[test]
+    fn array() {
+        let mut read = Trickle(false, &[1, 2]);
+
+        assert_eq!(block_on(read.read_array_or_eof::<2>()).unwrap(), Some([1, 2]));
+        assert_eq!(block_on(read.read_array_or_eof::<2>()).unwrap(), None);
+    }
+
+    #[test]
+    fn full() {
+        let mut read = Trickle(false, &[1, 2, 3]);
+        let mut buf = [9; 2];
+
+        assert_eq!(block_on(read.read_full(&mut buf)).unwrap(), 2);
+        assert_eq!(block_on(read.read_exact_or_zero_pad(&mut buf)).unwrap(), 1);
+        assert_eq!(buf, [3, 0]);
+    }
+
+    #[test]
+    fn at_least() {
+        let mut read = Trickle(false, &[1, 2, 3]);
+        let mut buf = [0; 3];
+
+        assert_eq!(block_on(read.read_at_least_or_eof(&mut buf, 1)).unwrap(), Some(1));
+        assert!(block_on(read.read_at_least_or_eof(&mut buf, 3)).is_err());
+        assert_eq!(block_on(read.read_at_least_or_eof(&mut buf, 1)).unwrap(), None);
+    }
+
+    #[test]
+    fn ints() {
+        let mut read = Trickle(false, &[1, 2, 3, 4, 5]);
+
+        assert_eq!(block_on(read.read_u16_le_or_eof()).unwrap(), Some(0x0201));
+        assert_eq!(block_on(read.read_u16_be_or_eof()).unwrap(), Some(0x0304));
+        assert!(block_on(read.read_u16_be_or_eof()).is_err());
+        assert_eq!(block_on(read.read_u8_or_eof()).unwrap(), None);
+    }
+
+    #[test]
+    fn to_vec() {
+        let mut read = Trickle(false, &[1, 2, 3]);
+
+        assert_eq!(block_on(read.read_exact_to_vec(2)).unwrap(), Some(vec![1, 2]));
+        assert!(block_on(read.read_exact_to_vec(2)).is_err());
+        assert_eq!(block_on(read.read_exact_to_vec(2)).unwrap(), None);
+        assert_eq!(block_on(read.read_exact_to_vec(usize::MAX)).unwrap_err().kind(), io::ErrorKind::OutOfMemory);
+    }
+
+    #[test]
+    fn frame() {
+        let mut read = Trickle(false, &[0, 0, 0, 2, 1, 2, 0, 0, 0, 0, 0, 0]);
+        let format = FrameFormat::default();
+
+        assert_eq!(block_on(read.read_frame_or_eof(&format)).unwrap(), Some(vec![1, 2]));
+        assert_eq!(block_on(read.read_frame_or_eof(&format)).unwrap(), Some(vec![]));
+        assert_eq!(block_on(read.read_frame_or_eof(&format)).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(block_on(read.read_frame_or_eof(&format)).unwrap(), None);
+    }
+
+    #[test]
+    fn frame_varint() {
+        let mut read = Trickle(false, &[0x02, 1, 2, 0x80]);
+        let format = FrameFormat::new(LengthPrefix::Varint);
+
+        assert_eq!(block_on(read.read_frame_or_eof(&format)).unwrap(), Some(vec![1, 2]));
+        assert_eq!(block_on(read.read_frame_or_eof(&format)).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        let format = FrameFormat::default().max_len(1);
+        assert_eq!(block_on((&mut &[0, 0, 0, 2][..]).read_frame_or_eof(&format)).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,57 @@
+use ::heapless::Vec;
+use crate::ReadExactStatus;
+
+/// Appends `n` bytes to `vec` with `fill`, keeping them only if all of them
+/// were read.
+///
+/// Returns `None` without reading if `vec` cannot hold `n` more bytes.
+pub(crate) fn append<const N: usize, E>(vec: &mut Vec<u8, N>, n: usize, fill: impl FnOnce(&mut [u8]) -> Result<usize, E>) -> Option<Result<ReadExactStatus, E>> {
+    let start = vec.len();
+    vec.resize(start.checked_add(n)?, 0).ok()?;
+
+    let status = fill(&mut vec[start..]).map(|filled| ReadExactStatus::new(filled, n));
+    if !matches!(status, Ok(ReadExactStatus::Full)) {
+        vec.truncate(start);
+    }
+    Some(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use ::heapless::Vec;
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "no-std-io"))]
+    fn io() {
+        use crate::ReadExactExt;
+
+        let mut read = &[1, 2, 3, 4, 5][..];
+        let mut vec = Vec::<u8, 4>::new();
+
+        assert!(read.read_exact_heapless(&mut vec, 1).unwrap());
+        assert!(read.read_exact_heapless(&mut vec, 4).is_err());
+        assert!(read.fill_heapless_or_eof(&mut vec).unwrap());
+        assert_eq!(vec, [1, 2, 3, 4]);
+        vec.clear();
+        assert!(read.fill_heapless_or_eof(&mut vec).is_err());
+        assert!(vec.is_empty());
+        assert!(!read.fill_heapless_or_eof(&mut vec).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-io")]
+    fn embedded_io() {
+        use crate::embedded_io::{ReadExactExt, ReadHeaplessError};
+
+        let mut read = &[1, 2, 3][..];
+        let mut vec = Vec::<u8, 2>::new();
+
+        assert_eq!(read.read_exact_heapless(&mut vec, 1), Ok(true));
+        assert_eq!(read.fill_heapless_or_eof(&mut vec), Ok(true));
+        assert_eq!(vec, [1, 2]);
+        vec.clear();
+        assert_eq!(read.read_exact_heapless(&mut vec, 3), Err(ReadHeaplessError::Capacity));
+        assert_eq!(read.fill_heapless_or_eof(&mut vec), Err(ReadHeaplessError::UnexpectedEof));
+        assert_eq!(read.fill_heapless_or_eof(&mut vec), Ok(false));
+    }
+}
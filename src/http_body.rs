@@ -0,0 +1,189 @@
+//! Support for exact reads of `http_body::Body` payloads.
+
+use std::error::Error;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use ::bytes::Buf;
+use ::http_body::Body;
+use crate::{ReadExactState, ReadExactStatus};
+
+/// A reader of exact records out of the data frames of an HTTP body.
+///
+/// Records may span any number of data frames, so payloads framed on top of
+/// a body, such as gRPC messages, are read with the same semantics as from a
+/// socket. Trailers are kept once they are received.
+#[derive(Debug)]
+pub struct BodyReader<B: Body> {
+    body: B,
+    chunk: Option<B::Data>,
+    trailers: Option<::http::HeaderMap>,
+    done: bool,
+}
+
+impl<B: Body> BodyReader<B> {
+    /// Creates a reader over the data frames of `body`.
+    pub fn new(body: B) -> Self {
+        BodyReader {
+            body,
+            chunk: None,
+            trailers: None,
+            done: false,
+        }
+    }
+
+    /// Returns the trailers of the body, if they have been received.
+    pub fn trailers(&self) -> Option<&::http::HeaderMap> {
+        self.trailers.as_ref()
+    }
+
+    /// Gets a reference to the underlying body.
+    pub fn get_ref(&self) -> &B {
+        &self.body
+    }
+
+    /// Gets a mutable reference to the underlying body.
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.body
+    }
+
+    /// Unwraps this reader, returning the underlying body.
+    ///
+    /// Any data frame that has only been partially read is lost.
+    pub fn into_inner(self) -> B {
+        self.body
+    }
+}
+
+impl<B: Body + Unpin> BodyReader<B> where
+    B::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`. Errors of the
+    /// body are returned as `io::Error`s of kind `Other`. The bytes read so
+    /// far are lost if the returned future is dropped before it completes,
+    /// so `poll_read_exact_or_eof` should be used where that matters.
+    pub async fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let mut state = ReadExactState::new();
+        poll_fn(|cx| self.poll_read_exact_or_eof(cx, &mut state, buf)).await
+    }
+
+    /// Polls an exact read of `buf`, or zero, keeping its progress in `state`.
+    ///
+    /// This behaves like the `poll_read_exact_or_eof` functions of the
+    /// `futures` and `tokio` modules: the same `buf` must be passed each time
+    /// until the read finishes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `state` has filled more than `buf.len()` bytes.
+    pub fn poll_read_exact_or_eof(&mut self, cx: &mut Context, state: &mut ReadExactState, buf: &mut [u8]) -> Poll<io::Result<bool>> {
+        assert!(state.filled <= buf.len(), "filled exceeds the buffer length");
+
+        while state.filled < buf.len() {
+            match ready!(self.poll_read_some(cx, &mut buf[state.filled..])) {
+                Ok(0) => break,
+                Ok(n) => state.filled += n,
+                Err(e) => {
+                    state.reset();
+                    return Poll::Ready(Err(e))
+                },
+            }
+        }
+
+        let filled = state.filled;
+        state.reset();
+        Poll::Ready(ReadExactStatus::new(filled, buf.len()).into_result())
+    }
+
+    fn poll_read_some(&mut self, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        loop {
+            if let Some(chunk) = &mut self.chunk {
+                if chunk.has_remaining() {
+                    let n = chunk.remaining().min(buf.len());
+                    chunk.copy_to_slice(&mut buf[..n]);
+                    return Poll::Ready(Ok(n))
+                }
+                self.chunk = None;
+            }
+            if self.done {
+                return Poll::Ready(Ok(0))
+            }
+
+            match ready!(Pin::new(&mut self.body).poll_frame(cx)) {
+                Some(Ok(frame)) => match frame.into_data() {
+                    Ok(data) => self.chunk = Some(data),
+                    Err(frame) => self.trailers = frame.into_trailers().ok(),
+                },
+                Some(Err(e)) => return Poll::Ready(Err(io::Error::other(e))),
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::convert::Infallible;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use ::bytes::Bytes;
+    use ::http_body::{Body, Frame};
+    use crate::test_util::block_on;
+    use super::BodyReader;
+
+    /// A body that yields a fixed list of frames.
+    struct Frames(VecDeque<Frame<Bytes>>);
+
+    impl Body for Frames {
+        type Data = Bytes;
+        type Error = Infallible;
+
+        fn poll_frame(mut self: Pin<&mut Self>, _: &mut Context) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+            Poll::Ready(self.0.pop_front().map(Ok))
+        }
+    }
+
+    fn body(chunks: &[&'static [u8]]) -> Frames {
+        Frames(chunks.iter().map(|&chunk| Frame::data(Bytes::from_static(chunk))).collect())
+    }
+
+    #[test]
+    fn records() {
+        let mut read = BodyReader::new(body(&[&[1], &[2, 3], &[], &[4]]));
+        let mut buf = [0; 2];
+
+        assert!(block_on(read.read_exact_or_eof(&mut buf)).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(block_on(read.read_exact_or_eof(&mut buf)).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert!(!block_on(read.read_exact_or_eof(&mut buf)).unwrap());
+    }
+
+    #[test]
+    fn truncated() {
+        let mut read = BodyReader::new(body(&[&[1, 2, 3]]));
+        let mut buf = [0; 2];
+
+        assert!(block_on(read.read_exact_or_eof(&mut buf)).unwrap());
+        assert!(block_on(read.read_exact_or_eof(&mut buf)).is_err());
+    }
+
+    #[test]
+    fn trailers() {
+        let mut trailers = ::http::HeaderMap::new();
+        trailers.insert("grpc-status", "0".parse().unwrap());
+        let mut frames = body(&[&[1]]);
+        frames.0.push_back(Frame::trailers(trailers));
+        let mut read = BodyReader::new(frames);
+        let mut buf = [0];
+
+        assert!(block_on(read.read_exact_or_eof(&mut buf)).unwrap());
+        assert!(read.trailers().is_none());
+        assert!(!block_on(read.read_exact_or_eof(&mut buf)).unwrap());
+        assert_eq!(read.trailers().unwrap()["grpc-status"], "0");
+    }
+}
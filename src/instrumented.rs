@@ -0,0 +1,191 @@
+use crate::{io, ReadExactExt, ReadExactStatus};
+
+/// Counters collected by an `Instrumented` reader.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ReadStats {
+    /// The total number of bytes read.
+    pub bytes: u64,
+    /// The number of calls to `read`, including those that failed.
+    pub reads: u64,
+    /// The number of reads that failed with `Interrupted`.
+    pub interrupts: u64,
+    /// The number of exact reads that ended at EOF before any data was read.
+    pub clean_eofs: u64,
+    /// The number of exact reads that ended at EOF after a partial read.
+    pub truncations: u64,
+}
+
+/// The outcome of a single exact read, as recorded in `FillStats`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// The buffer was filled completely.
+    Full,
+    /// EOF was reached before any data was read.
+    CleanEof,
+    /// EOF was reached after reading only part of the buffer.
+    PartialEof,
+    /// The read failed with an error other than EOF.
+    Error,
+}
+
+/// Counters for a single exact read.
+///
+/// This is returned by `ReadExactExt::read_exact_or_eof_stats`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct FillStats {
+    /// The number of bytes read.
+    pub bytes: usize,
+    /// The number of calls to `read`, including those that failed.
+    pub read_calls: u32,
+    /// The number of reads that failed with `Interrupted` and were retried.
+    pub interrupts: u32,
+    /// How the read ended.
+    pub outcome: Outcome,
+}
+
+/// Summarizes an exact read issued through an `Instrumented` reader.
+pub(crate) fn fill_stats(stats: ReadStats, ret: io::Result<ReadExactStatus>) -> (io::Result<bool>, FillStats) {
+    let outcome = match ret {
+        Ok(ReadExactStatus::Full) => Outcome::Full,
+        Ok(ReadExactStatus::CleanEof) => Outcome::CleanEof,
+        Ok(ReadExactStatus::PartialEof { .. }) => Outcome::PartialEof,
+        Err(..) => Outcome::Error,
+    };
+    let stats = FillStats {
+        bytes: stats.bytes as usize,
+        read_calls: stats.reads.try_into().unwrap_or(u32::MAX),
+        interrupts: stats.interrupts.try_into().unwrap_or(u32::MAX),
+        outcome,
+    };
+    (ret.and_then(ReadExactStatus::into_result), stats)
+}
+
+/// A reader that counts the reads issued against another reader.
+///
+/// Every `read` is counted, however it is issued. Exact read outcomes are
+/// counted by calling `read_exact_or_eof` or `read_exact_or_eof_status` on
+/// the wrapper directly.
+#[derive(Debug)]
+pub struct Instrumented<R> {
+    reader: R,
+    stats: ReadStats,
+}
+
+impl<R> Instrumented<R> {
+    /// Wraps a reader with zeroed counters.
+    pub fn new(reader: R) -> Self {
+        Instrumented {
+            reader,
+            stats: ReadStats::default(),
+        }
+    }
+
+    /// Returns the counters collected so far.
+    pub fn stats(&self) -> ReadStats {
+        self.stats
+    }
+
+    /// Zeroes the counters.
+    pub fn reset_stats(&mut self) {
+        self.stats = ReadStats::default();
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::Read> Instrumented<R> {
+    /// Reads exactly the number of bytes to fill `buf`, or zero, counting the
+    /// outcome.
+    ///
+    /// See `ReadExactExt::read_exact_or_eof`.
+    pub fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        self.read_exact_or_eof_status(buf)?.into_result()
+    }
+
+    /// Reads exactly the number of bytes to fill `buf`, counting the outcome.
+    ///
+    /// See `ReadExactExt::read_exact_or_eof_status`.
+    pub fn read_exact_or_eof_status(&mut self, buf: &mut [u8]) -> io::Result<ReadExactStatus> {
+        let status = ReadExactExt::read_exact_or_eof_status(self, buf)?;
+        match status {
+            ReadExactStatus::Full => (),
+            ReadExactStatus::CleanEof => self.stats.clean_eofs += 1,
+            ReadExactStatus::PartialEof { .. } => self.stats.truncations += 1,
+        }
+        Ok(status)
+    }
+}
+
+impl<R: io::Read> io::Read for Instrumented<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stats.reads += 1;
+        let ret = self.reader.read(buf);
+        match ret {
+            Ok(n) => self.stats.bytes += n as u64,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => self.stats.interrupts += 1,
+            Err(..) => (),
+        }
+        ret
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::io;
+    use crate::testing::ScriptedReader;
+    use crate::ReadExactExt;
+    use super::{FillStats, Instrumented, Outcome};
+
+    #[test]
+    fn stats() {
+        let read = ScriptedReader::new()
+            .data(&[1])
+            .error(io::ErrorKind::Interrupted)
+            .data(&[2, 3]);
+        let mut read = Instrumented::new(read);
+        let mut buf = [0, 0];
+
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert!(read.read_exact_or_eof(&mut buf).is_err());
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+
+        let stats = read.stats();
+        assert_eq!(stats.bytes, 3);
+        assert_eq!(stats.reads, 6);
+        assert_eq!(stats.interrupts, 1);
+        assert_eq!(stats.clean_eofs, 1);
+        assert_eq!(stats.truncations, 1);
+    }
+
+    #[test]
+    fn fill_stats() {
+        let mut read = ScriptedReader::new()
+            .data(&[1])
+            .error(io::ErrorKind::Interrupted)
+            .data(&[2, 3])
+            .error(io::ErrorKind::Other);
+        let mut buf = [0, 0];
+
+        let (ret, stats) = read.read_exact_or_eof_stats(&mut buf);
+        assert!(ret.unwrap());
+        assert_eq!(stats, FillStats { bytes: 2, read_calls: 3, interrupts: 1, outcome: Outcome::Full });
+        let (ret, stats) = read.read_exact_or_eof_stats(&mut buf);
+        assert!(ret.is_err());
+        assert_eq!(stats, FillStats { bytes: 1, read_calls: 2, interrupts: 0, outcome: Outcome::Error });
+    }
+}
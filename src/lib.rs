@@ -1,4 +1,6 @@
 #![deny(missing_docs)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(feature = "nightly", feature(read_buf, core_io_borrowed_buf))]
 
 //! Provides a variant of `read_exact` that succeeds on EOF if no data has been
 //! read.
@@ -8,14 +10,15 @@
 //! ```
 //! # fn main() {
 //! use std::io;
+//! # #[cfg(feature = "std")]
 //! # fn foo() -> io::Result<()> {
 //! use std::io::prelude::*;
 //! use std::fs::File;
 //! use read_exact::ReadExactExt;
 //!
-//! let mut f = try!(File::open("foo.txt"));
+//! let mut f = File::open("foo.txt")?;
 //! let mut buffer = [0; 10];
-//! let success = try!(f.read_exact_or_eof(&mut buffer));
+//! let success = f.read_exact_or_eof(&mut buffer)?;
 //! if success {
 //!     // buffer is full
 //! } else {
@@ -25,76 +28,2481 @@
 //! # }
 //! # }
 //! ```
+//!
+//! # Integrations
+//!
+//! The `zerocopy` feature enables `read_pod_or_eof`, which reads a value of
+//! any type implementing `zerocopy::FromBytes` and `zerocopy::IntoBytes`,
+//! such as a `#[repr(C)]` on-disk header.
+//!
+//! The `bytes` feature enables `read_exact_into_buf` and `read_exact_bytes`,
+//! which read directly into the buffers of the `bytes` crate.
+//!
+//! The `arrayvec` feature enables `read_exact_arrayvec_or_eof`, which reads a
+//! small record into a stack-allocated `ArrayVec`. The `smallvec` feature
+//! enables `read_exact_smallvec_or_eof`, which reads small records inline and
+//! spills larger ones to the heap.
+//!
+//! The `bincode` feature enables `read_message_or_eof`, which deserializes
+//! each length-prefixed frame with `serde` and `bincode`.
+//!
+//! The `mmap` feature enables `MmapRecords`, which reads records directly out
+//! of a memory mapped file.
+//!
+//! The `crc` feature enables `read_exact_crc_or_eof`, which verifies the
+//! CRC-32 or CRC-32C that trails each record.
+//!
+//! The `digest` feature enables `read_exact_or_eof_hashing`, which feeds the
+//! bytes read to any hasher of the `digest` crate.
+//!
+//! The `zeroize` feature enables `read_exact_secret_or_eof` and
+//! `read_exact_secret_to_vec`, which wipe partially read secrets.
+//!
+//! The `http-body` feature enables the `http_body` module, which reads exact
+//! records out of the data frames of an `http_body::Body`.
+//!
+//! The `rayon` feature enables `par_records`, which processes records on the
+//! rayon thread pool as they are read.
+//!
+//! The `serialport` feature enables `read_serial_frame_or_eof`, which reads
+//! fixed-size frames from a serial port with a per-frame deadline and an
+//! inter-byte timeout.
+//!
+//! The `nom` feature enables the `nom` module, which drives `nom` streaming
+//! parsers over a reader, reading exactly the input each parser asks for.
+//!
+//! The `flate2` and `zstd` features enable `Decompressed`, which reads exact
+//! records of decompressed data while reporting a truncated compressed
+//! stream as a `TruncatedStream` error.
+//!
+//! The `fallible-iterator` feature implements `FallibleIterator` for the
+//! record readers that lend out their records, such as `Records`, copying
+//! each record. Iterators that already yield `io::Result` items, such as
+//! `ExactChunks`, can be adapted with `fallible_iterator::convert`.
+//!
+//! The `error` feature enables `read_exact_or_eof_typed`, which reports a
+//! truncated read as a `ReadExactError` that records how much was read.
+//!
+//! The `tls` feature enables `read_exact_or_eof_tls`, which tells a TLS peer
+//! that closed without `close_notify` on a record boundary apart from one
+//! that truncated a record.
+//!
+//! The `ffi` feature enables the `ffi` module, which exports C functions that
+//! read exactly from file descriptors or handles, with the same EOF
+//! semantics, for use from other languages.
+//!
+//! The `tracing` feature emits a `trace` level `fill` span for each fill of a
+//! buffer, recording the requested and obtained lengths, the number of
+//! interrupted reads retried, and the outcome.
+//!
+//! The `metrics` feature emits metrics for each fill of a buffer through the
+//! `metrics` facade: the counters `read_exact.bytes`, `read_exact.clean_eof`
+//! and `read_exact.truncations`, and the histogram `read_exact.fill_duration`
+//! in seconds.
+//!
+//! The `tokio-uring` feature enables the `tokio_uring` module on Linux, which
+//! reads into owned buffers through `io_uring` completions rather than
+//! readiness notifications. The `monoio` feature enables the `monoio` module,
+//! which does the same for `monoio::io::AsyncReadRent` types. `glommio`
+//! streams implement `futures_io::AsyncRead`, and are supported by the
+//! `futures` feature.
+//!
+//! The `wasm` feature enables the `wasm` module on `wasm32` targets, which
+//! reads from a browser `ReadableStream` through its BYOB reader. The `wasi`
+//! feature enables the `wasi` module on `wasm32-wasip2`, which reads from
+//! WASI preview 2 input streams.
+//!
+//! # Testing
+//!
+//! The `testing` feature enables the `testing` module, which provides
+//! scripted readers for exercising code built on this crate, `Recording` for
+//! capturing the reads of a real stream to replay later, and `Simulated` for
+//! delivering data as a slow, fragmented network would.
+//!
+//! # Nightly
+//!
+//! The `nightly` feature enables `read_exact_or_eof_buf`, which reads into
+//! uninitialized memory using the unstable `BorrowedCursor` API.
+//!
+//! # `no_std`
+//!
+//! Disabling the default `std` feature and enabling `no-std-io` implements
+//! `ReadExactExt` for `no_std_io2::io::Read` instead. The `embedded-io` and
+//! `embedded-io-async` features provide equivalent extension traits for the
+//! `embedded_io` family of traits.
+//!
+//! The `heapless` feature enables `read_exact_heapless` and
+//! `fill_heapless_or_eof` on both the `std` and `embedded_io` extension
+//! traits, which append exact records to a `heapless::Vec` for targets
+//! without an allocator.
+
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(all(feature = "no-std-io", not(feature = "std")))]
+use no_std_io2::io;
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "futures")]
+pub mod futures;
+
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+#[cfg(feature = "tokio-util")]
+pub mod tokio_util;
+
+#[cfg(all(feature = "tokio-uring", target_os = "linux"))]
+pub mod tokio_uring;
+
+#[cfg(feature = "monoio")]
+pub mod monoio;
+
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
+
+#[cfg(all(feature = "wasi", target_os = "wasi", target_env = "p2"))]
+pub mod wasi;
+
+#[cfg(feature = "embedded-io")]
+pub mod embedded_io;
+
+#[cfg(feature = "embedded-io-async")]
+pub mod embedded_io_async;
+
+#[cfg(feature = "http-body")]
+pub mod http_body;
+
+#[cfg(feature = "nom")]
+pub mod nom;
+
+#[cfg(feature = "tls")]
+pub mod tls;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+mod adapters;
+
+#[cfg(any(feature = "futures", feature = "tokio"))]
+mod async_ext;
+
+#[cfg(feature = "std")]
+mod aligned;
+
+#[cfg(all(feature = "std", any(unix, windows)))]
+mod at;
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+mod bits;
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+mod buf;
+
+#[cfg(feature = "std")]
+mod channel;
+
+#[cfg(feature = "std")]
+mod child;
+
+#[cfg(feature = "crc")]
+mod checksum;
+
+#[cfg(feature = "std")]
+mod columnar;
+
+#[cfg(all(feature = "bytes", any(feature = "std", feature = "no-std-io")))]
+mod bytes;
+
+#[cfg(feature = "std")]
+mod datagram;
+
+#[cfg(any(feature = "flate2", feature = "zstd"))]
+mod decompress;
+
+#[cfg(feature = "error")]
+mod error;
+
+#[cfg(feature = "std")]
+mod exact_buf;
+
+#[cfg(feature = "fallible-iterator")]
+mod fallible;
+
+#[cfg(all(feature = "std", unix))]
+mod fifo;
+
+#[cfg(feature = "std")]
+mod fill;
+
+#[cfg(feature = "std")]
+mod frame;
+
+#[cfg(all(feature = "heapless", any(feature = "std", feature = "no-std-io", feature = "embedded-io")))]
+mod heapless;
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+mod instrumented;
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+mod memory;
+
+#[cfg(feature = "mmap")]
+mod mmap;
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+mod opts;
+
+#[cfg(all(feature = "windows-sys", windows))]
+mod overlapped;
+
+#[cfg(feature = "rayon")]
+mod par;
+
+#[cfg(all(feature = "std", feature = "libc", unix))]
+mod peek;
+
+#[cfg(feature = "std")]
+mod prefetch;
+
+#[cfg(all(feature = "std", feature = "libc", unix))]
+mod readv;
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+mod records;
+
+#[cfg(feature = "std")]
+mod ring;
+
+#[cfg(feature = "std")]
+mod schema;
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+mod seek;
+
+#[cfg(feature = "serialport")]
+mod serial;
+
+#[cfg(feature = "std")]
+mod shared;
+
+#[cfg(any(feature = "std", feature = "no-std-io", feature = "embedded-io"))]
+mod source;
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+mod take;
+
+#[cfg(feature = "std")]
+mod throttled;
+
+#[cfg(all(feature = "std", feature = "libc", any(target_os = "linux", target_os = "android")))]
+mod splice;
+
+#[cfg(feature = "std")]
+mod stdin;
+
+#[cfg(feature = "std")]
+mod timeout;
+
+#[cfg(feature = "std")]
+mod writer;
+
+#[cfg(any(feature = "testing", all(test, feature = "std")))]
+pub mod testing;
+
+#[cfg(test)]
+mod test_util;
+
+#[cfg(feature = "std")]
+pub use adapters::{AssertExact, Labeled, LabeledError, PeekExact, Unread};
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub use adapters::ExactOrEof;
+#[cfg(feature = "std")]
+pub use aligned::AlignedRecordBuf;
+#[cfg(all(feature = "std", any(unix, windows)))]
+pub use at::{read_records_at_parallel, ReadExactAtExt};
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub use bits::{BitOrder, BitReader};
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub use buf::{BufReadExactExt, DelimStatus};
+#[cfg(feature = "std")]
+pub use channel::ChannelReader;
+#[cfg(feature = "std")]
+pub use child::ChildFrames;
+#[cfg(feature = "crc")]
+pub use checksum::{Checksum, ChecksumMismatch};
+#[cfg(feature = "std")]
+pub use columnar::ColumnarReader;
+#[cfg(feature = "std")]
+pub use datagram::{DatagramStatus, RecvExactExt};
+#[cfg(any(feature = "flate2", feature = "zstd"))]
+pub use decompress::{Decompressed, TruncatedStream};
+#[cfg(feature = "error")]
+pub use error::ReadExactError;
+#[cfg(feature = "std")]
+pub use exact_buf::ExactBufReader;
+#[cfg(all(feature = "std", unix))]
+pub use fifo::FifoRecords;
+#[cfg(feature = "std")]
+pub use fill::{ExactFill, FillState, FillStatus, PartialFill, ResumableFill};
+#[cfg(feature = "std")]
+pub use frame::{Endian, FrameFormat, LengthPrefix};
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub use instrumented::{FillStats, Instrumented, Outcome, ReadStats};
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub use memory::MemReadExactExt;
+#[cfg(feature = "std")]
+pub use memory::{CowOwned, CowReadExactExt};
+#[cfg(feature = "mmap")]
+pub use mmap::MmapRecords;
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub use opts::{ReadExactOpts, StalledFill};
+#[cfg(all(feature = "windows-sys", windows))]
+pub use overlapped::ReadExactOverlappedExt;
+#[cfg(feature = "rayon")]
+pub use par::{par_records, ParRecords};
+#[cfg(all(feature = "std", feature = "libc", unix))]
+pub use peek::PeekExactExt;
+#[cfg(feature = "std")]
+pub use prefetch::{DoubleBuffered, Prefetching};
+#[cfg(all(feature = "std", feature = "libc", unix))]
+pub use readv::ReadvExactExt;
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub use records::ExactChunks;
+#[cfg(feature = "std")]
+pub use records::{FixedSizeRecord, FixedWidthRecords, RecordReader, Records, TruncatedRecord, TypedRecords};
+#[cfg(feature = "std")]
+pub use ring::{RingReader, VecDequeExactExt};
+#[cfg(feature = "std")]
+pub use schema::{Field, FieldType, FieldValue, Schema, SchemaReader, SchemaRecord};
+#[cfg(feature = "std")]
+pub use seek::{FileReadExactExt, RevRecords};
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub use seek::SeekReadExactExt;
+#[cfg(feature = "serialport")]
+pub use serial::{SerialReadExactExt, SerialTiming};
+#[cfg(feature = "std")]
+pub use shared::SharedExactReader;
+#[cfg(any(feature = "std", feature = "no-std-io", feature = "embedded-io"))]
+pub use source::{read_exact_or_eof_source, FnSource, Source};
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub use source::fill_exact_with;
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+use source::{fill_source, AtLeast, FillControl, IoSource, NonBlocking, SliceBuf};
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+use opts::OptsControl;
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub use take::ReadExactTakeExt;
+#[cfg(feature = "std")]
+pub use take::{Quota, QuotaExceeded, TakeExact, TruncatedRegion};
+#[cfg(all(feature = "std", feature = "libc", any(target_os = "linux", target_os = "android")))]
+pub use splice::splice_exact;
+#[cfg(feature = "std")]
+pub use stdin::{read_exact_or_eof_stdin, stdin_records};
+#[cfg(feature = "std")]
+pub use throttled::Throttled;
+#[cfg(feature = "std")]
+pub use timeout::ReadExactTimeoutExt;
+#[cfg(all(feature = "std", feature = "libc", unix))]
+pub use timeout::ReadExactPollExt;
+#[cfg(feature = "std")]
+pub use writer::RecordWriter;
+
+/// The outcome of an exact read that tolerates EOF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReadExactStatus {
+    /// The buffer was filled completely.
+    Full,
+    /// EOF was reached before any data was read.
+    CleanEof,
+    /// EOF was reached after reading only part of the buffer.
+    PartialEof {
+        /// The number of bytes read into the start of the buffer.
+        filled: usize,
+    },
+}
+
+impl ReadExactStatus {
+    #[cfg(any(feature = "std", feature = "no-std-io", feature = "embedded-io"))]
+    fn new(filled: usize, len: usize) -> Self {
+        if filled == len {
+            ReadExactStatus::Full
+        } else if filled == 0 {
+            ReadExactStatus::CleanEof
+        } else {
+            ReadExactStatus::PartialEof { filled }
+        }
+    }
+
+    #[cfg(any(feature = "std", feature = "no-std-io"))]
+    fn into_result(self) -> io::Result<bool> {
+        match self {
+            ReadExactStatus::Full => Ok(true),
+            ReadExactStatus::CleanEof => Ok(false),
+            ReadExactStatus::PartialEof { .. } => Err(unexpected_eof()),
+        }
+    }
+}
+
+/// The outcome of an exact read from a nonblocking source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TryReadStatus {
+    /// The read finished, as if by `read_exact_or_eof`.
+    Done(bool),
+    /// The source would block before the buffer could be filled.
+    Pending {
+        /// The number of bytes read into the start of the buffer so far.
+        filled: usize,
+    },
+}
+
+/// The outcome of an exact read that can be cancelled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CancelStatus {
+    /// The read finished, as if by `read_exact_or_eof`.
+    Done(bool),
+    /// The read was cancelled before the buffer could be filled.
+    Cancelled {
+        /// The number of bytes read into the start of the buffer so far.
+        filled: usize,
+    },
+}
+
+/// The outcome of an exact read from a TLS stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TlsStatus {
+    /// The read finished, as if by `read_exact_or_eof`.
+    ///
+    /// This includes a peer that closed without `close_notify` on a record
+    /// boundary.
+    Done(bool),
+    /// The peer closed without `close_notify` partway through the buffer,
+    /// which may mean that the stream was truncated by an attacker.
+    TruncatedTls {
+        /// The number of bytes read into the start of the buffer.
+        filled: usize,
+    },
+}
+
+/// The outcome of a read that stops early once a predicate is satisfied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UntilStatus {
+    /// The predicate was never satisfied, and the read finished as if by
+    /// `read_exact_or_eof`.
+    Done(bool),
+    /// The predicate was satisfied before the buffer was filled.
+    Matched {
+        /// The number of bytes read into the start of the buffer.
+        filled: usize,
+    },
+}
+
+/// The outcome of an exact read with a deadline.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimeoutStatus {
+    /// The read finished, as if by `read_exact_or_eof`.
+    Done(bool),
+    /// The deadline passed before the buffer could be filled.
+    TimedOut {
+        /// The number of bytes read into the start of the buffer so far.
+        filled: usize,
+    },
+}
+
+/// Generates provided `ReadExactExt` methods that read a primitive integer.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+macro_rules! read_int_or_eof {
+    ($($name:ident: $ty:ident::$from:ident, $order:literal;)*) => {
+        $(
+            #[doc = concat!("Reads a", $order, " `", stringify!($ty), "`, or returns `None` if no data was read.")]
+            ///
+            /// EOF after a partial read is an error, as with `read_exact_or_eof`.
+            fn $name(&mut self) -> io::Result<Option<$ty>> {
+                self.read_array_or_eof().map(|buf| buf.map($ty::$from))
+            }
+        )*
+    };
+}
+
+/// The outcome of reading a header that starts with a magic number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HeaderStatus<const N: usize> {
+    /// EOF was reached before any data was read.
+    Eof,
+    /// The header was read and starts with the expected magic number.
+    Ok([u8; N]),
+    /// The header was read, but does not start with the expected magic
+    /// number.
+    BadMagic([u8; N]),
+}
+
+/// The progress of an exact read that is driven by polling.
+///
+/// This is kept outside of the read itself, so that the bytes read so far are
+/// not lost if the caller stops polling before the read finishes. It is reset
+/// once the read finishes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ReadExactState {
+    filled: usize,
+    interrupts: u32,
+    reads: u32,
+}
+
+impl ReadExactState {
+    /// Creates the state of a read that has not started.
+    pub const fn new() -> Self {
+        ReadExactState {
+            filled: 0,
+            interrupts: 0,
+            reads: 0,
+        }
+    }
+
+    /// Returns the number of bytes read into the start of the buffer so far.
+    pub const fn filled(&self) -> usize {
+        self.filled
+    }
+
+    /// Abandons the read in progress.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// An extension trait that applies to all `std::io::Read` types.
+///
+/// Without the `std` feature, this applies to `no_std_io2::io::Read` types
+/// instead.
+///
+/// The implementation passes the reader on as a `&mut dyn Read`, so that the
+/// read loops are compiled once rather than for every reader type, at the
+/// cost of a virtual call per read. The exceptions are the adapters that take
+/// the reader by value, such as `exact_chunks` and `take_exact`, which stay
+/// generic over it so that it can be returned by `into_inner`. Methods with a
+/// closure, hasher or writer parameter are still compiled for each of those.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub trait ReadExactExt {
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// This function returns `true` upon successful read, or `false` if no
+    /// data was read. No guarantees about the contents of `buf` are provided
+    /// if the function returns `false` or an error.
+    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill `buf`, reporting how much
+    /// was read if EOF is encountered first.
+    ///
+    /// Unlike `read_exact_or_eof`, a partial read is not an error. The bytes
+    /// that were read are placed at the start of `buf`.
+    fn read_exact_or_eof_status(&mut self, buf: &mut [u8]) -> io::Result<ReadExactStatus>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, as
+    /// configured by `opts`.
+    ///
+    /// `read_exact_or_eof` is equivalent to calling this with the default
+    /// options.
+    fn read_exact_or_eof_with(&mut self, opts: &ReadExactOpts, buf: &mut [u8]) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, leaving
+    /// `buf` unchanged unless it was filled.
+    ///
+    /// This behaves like `read_exact_or_eof`, but stages the read in a
+    /// temporary buffer, so `buf` keeps its previous contents whenever this
+    /// does not return `Ok(true)`. Bytes read before an error are lost.
+    #[cfg(feature = "std")]
+    fn read_exact_or_eof_atomic(&mut self, buf: &mut [u8]) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, treating
+    /// any of the error `kinds` before any data is read as EOF.
+    ///
+    /// This normalizes the different ways in which transports report that the
+    /// peer has gone away. Those errors are still returned if they occur
+    /// partway through `buf`.
+    fn read_exact_or_eof_ignoring(&mut self, buf: &mut [u8], kinds: &[io::ErrorKind]) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, reporting
+    /// progress to `progress`.
+    ///
+    /// `progress` is called with the number of bytes filled so far and
+    /// `buf.len()` after every read that returns data, so that long reads can
+    /// drive a progress bar without wrapping the reader.
+    fn read_exact_or_eof_with_progress<F: FnMut(usize, usize)>(&mut self, buf: &mut [u8], progress: F) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, calling
+    /// `yield_fn` between reads.
+    ///
+    /// `yield_fn` is called before every read after the first, including
+    /// retries after `Interrupted`, so that a green-thread scheduler or a
+    /// game loop can run other work while a slow source trickles in a long
+    /// record. It is not called if the first read fills `buf`.
+    fn read_exact_or_eof_yielding<Y: FnMut()>(&mut self, buf: &mut [u8], yield_fn: Y) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, updating
+    /// `hasher` with the bytes as they are read.
+    ///
+    /// `hasher` sees exactly the bytes placed into `buf`, including those
+    /// read before an error, so that no second pass over the data is needed.
+    #[cfg(feature = "digest")]
+    fn read_exact_or_eof_hashing<D: ::digest::Update + ?Sized>(&mut self, buf: &mut [u8], hasher: &mut D) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, writing the
+    /// bytes to `writer` as they are read.
+    ///
+    /// `writer` receives exactly the bytes placed into `buf`, including those
+    /// read before an error, which allows consumed records to be archived
+    /// without a second pass. If `writer` fails, the read stops and its error
+    /// is returned; the bytes of the read that failed to be written have
+    /// still been consumed from the reader and placed into `buf`.
+    fn read_exact_or_eof_tee<W: io::Write + ?Sized>(&mut self, buf: &mut [u8], writer: &mut W) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, wiping `buf`
+    /// unless it was filled.
+    ///
+    /// This behaves like `read_exact_or_eof`, but guarantees that partially
+    /// read secrets, such as keys or tokens, do not remain in `buf` after an
+    /// error or EOF.
+    #[cfg(feature = "zeroize")]
+    fn read_exact_secret_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool>;
+
+    /// Reads exactly `n` secret bytes into a new buffer that is wiped on drop,
+    /// or returns `None` if no data was read.
+    ///
+    /// The buffer is allocated as in `read_exact_to_vec`, and wiped before an
+    /// error or EOF is returned.
+    #[cfg(all(feature = "zeroize", feature = "std"))]
+    fn read_exact_secret_to_vec(&mut self, n: usize) -> io::Result<Option<::zeroize::Zeroizing<Vec<u8>>>>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, reporting
+    /// truncation as a typed error.
+    ///
+    /// This behaves like `read_exact_or_eof`, but a reader that ends partway
+    /// through `buf` fails with `ReadExactError::Truncated`, which records
+    /// how much was read, rather than with an `UnexpectedEof` that can only
+    /// be matched by its kind.
+    #[cfg(feature = "error")]
+    fn read_exact_or_eof_typed(&mut self, buf: &mut [u8]) -> Result<bool, ReadExactError>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, from a TLS
+    /// stream.
+    ///
+    /// This behaves like `read_exact_or_eof`, but a peer that closed the
+    /// connection without a `close_notify` alert, as recognized by
+    /// `tls::is_unclean_close`, is treated as EOF if it happens on a record
+    /// boundary, and reported as `TlsStatus::TruncatedTls` otherwise.
+    #[cfg(feature = "tls")]
+    fn read_exact_or_eof_tls(&mut self, buf: &mut [u8]) -> io::Result<TlsStatus>;
+
+    /// Reads until `buf` is full or EOF is reached.
+    ///
+    /// Returns the number of bytes read, which is only less than `buf.len()`
+    /// if EOF was encountered. Reads that fail with `Interrupted` are retried.
+    fn read_full(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Reads exactly the number of bytes to fill each of `bufs` in turn, or
+    /// zero.
+    ///
+    /// This behaves like a single `read_exact_or_eof` over the concatenation
+    /// of `bufs`, so `false` is only returned if no data was read into any of
+    /// them.
+    fn read_exact_all_or_eof(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<bool>;
+
+    /// Reads at least `min` bytes into `buf`, or returns `None` if no data was
+    /// read.
+    ///
+    /// Returns the number of bytes read, which may be anywhere between `min`
+    /// and `buf.len()`. No further reads are issued once `min` bytes are
+    /// available. EOF after fewer than `min` bytes is an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min` is greater than `buf.len()`.
+    fn read_at_least_or_eof(&mut self, buf: &mut [u8], min: usize) -> io::Result<Option<usize>>;
+
+    /// Reads until `buf` is full or EOF is reached, zero-filling the rest of
+    /// `buf` at EOF.
+    ///
+    /// Returns the number of bytes read, as with `read_full`. This suits
+    /// block formats in which a short final block is implicitly zero-padded.
+    fn read_exact_or_zero_pad(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, without
+    /// losing progress when the source would block.
+    ///
+    /// If a read fails with `WouldBlock`, this returns
+    /// `TryReadStatus::Pending` with the number of bytes read so far, and
+    /// the fill can be continued later with `try_read_exact_or_eof_resume`.
+    fn try_read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<TryReadStatus>;
+
+    /// Continues a fill started by `try_read_exact_or_eof`, given the number
+    /// of bytes at the start of `buf` that were already `filled`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `filled` is greater than `buf.len()`.
+    fn try_read_exact_or_eof_resume(&mut self, buf: &mut [u8], filled: usize) -> io::Result<TryReadStatus>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, unless
+    /// `cancel` is set first.
+    ///
+    /// `cancel` is checked before each read, including retries of reads
+    /// that fail with `Interrupted`, so a signal handler that sets it aborts
+    /// a blocked read. Once cancelled, this returns `CancelStatus::Cancelled`
+    /// with the number of bytes read so far.
+    fn read_exact_or_eof_cancellable(&mut self, buf: &mut [u8], cancel: &AtomicBool) -> io::Result<CancelStatus>;
+
+    /// Reads until `buf` is full, or `pred` returns `true` for the bytes read
+    /// into the start of it so far.
+    ///
+    /// This allows a record with a terminator to be read into a buffer of its
+    /// maximum size. `pred` is called after each read, which may read past the
+    /// point at which it would first have returned `true`, so a `BufRead`
+    /// wrapped in `Read::take`, or a reader that returns one record per read,
+    /// should be used where that matters. EOF is handled as in
+    /// `read_exact_or_eof`.
+    fn read_until_filled_or<F: FnMut(&[u8]) -> bool>(&mut self, buf: &mut [u8], pred: F) -> io::Result<UntilStatus>;
+
+    /// Reads exactly the number of bytes to fill an owned buffer, or zero,
+    /// returning the buffer alongside the result.
+    ///
+    /// This behaves like `read_exact_or_eof` for any buffer that derefs to
+    /// bytes through `AsMut<[u8]>`, such as arrays, `Vec<u8>`, `Box<[u8]>`
+    /// or `AlignedRecordBuf`. Passing the buffer by value suits code that
+    /// moves buffers between owners, in the manner of the completion-based
+    /// backends.
+    fn read_exact_or_eof_into<B: AsMut<[u8]>>(&mut self, buf: B) -> (io::Result<bool>, B);
+
+    /// Reads exactly `len` bytes into the start of `buf`, or zero, returning
+    /// the part of `buf` that was filled.
+    ///
+    /// This lets a scratch buffer sized for the largest record be reused for
+    /// smaller ones, handing back a view of exactly the record read. Returns
+    /// `None` if no data was read, and fails with `InvalidInput`, without
+    /// reading, if `buf` is shorter than `len`.
+    fn read_exact_or_eof_slice<'a>(&mut self, buf: &'a mut [u8], len: usize) -> io::Result<Option<&'a mut [u8]>>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, returning
+    /// counters for the read alongside its result.
+    ///
+    /// This behaves like `read_exact_or_eof`, and allows the number of reads
+    /// and retries each record took to be observed without wrapping the
+    /// reader in an `Instrumented`.
+    fn read_exact_or_eof_stats(&mut self, buf: &mut [u8]) -> (io::Result<bool>, FillStats);
+
+    /// Discards exactly `n` bytes, or zero.
+    ///
+    /// This behaves like `read_exact_or_eof` into a buffer of `n` bytes, but
+    /// reads through a small scratch buffer instead, so that padding or
+    /// unknown sections of a format can be skipped with the same truncation
+    /// checks.
+    fn skip_exact_or_eof(&mut self, n: u64) -> io::Result<bool>;
+
+    /// Reads exactly `N` bytes into an array.
+    ///
+    /// Returns `None` if no data was read, in the same manner as
+    /// `read_exact_or_eof`.
+    fn read_array_or_eof<const N: usize>(&mut self) -> io::Result<Option<[u8; N]>>;
+
+    /// Reads as many `N` byte records into `out` as the reader holds, up to
+    /// `out.len()`.
+    ///
+    /// All of the records are filled with a single read loop, which avoids
+    /// the per-call overhead of `read_exact_or_eof` for many small records.
+    /// Returns the number of records read, which is less than `out.len()`
+    /// only at EOF. Fails with `UnexpectedEof` if the reader ends partway
+    /// through a record.
+    fn read_records_or_eof<const N: usize>(&mut self, out: &mut [[u8; N]]) -> io::Result<usize>;
+
+    /// Reads an `N` byte header and checks that it starts with `magic`.
+    ///
+    /// EOF is handled as in `read_array_or_eof`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `magic` is longer than `N`.
+    fn read_header_or_eof<const N: usize>(&mut self, magic: &[u8]) -> io::Result<HeaderStatus<N>>;
+
+    /// Creates an iterator over `N` byte chunks of this reader.
+    ///
+    /// The iterator ends at EOF on a chunk boundary, and yields an error if
+    /// the reader ends partway through a chunk.
+    fn exact_chunks<const N: usize>(self) -> ExactChunks<Self, N> where Self: Sized;
+
+    /// Reads and parses an `F`, or returns `None` if no data was read.
+    ///
+    /// EOF is handled as in `read_exact_or_eof`. A record that `F` fails to
+    /// parse is an `InvalidData` error, with the parse error as its inner
+    /// error.
+    #[cfg(feature = "std")]
+    fn read_record_or_eof<F: FixedSizeRecord>(&mut self) -> io::Result<Option<F>>;
+
+    /// Creates an iterator over the records of this reader, parsed as `F`.
+    ///
+    /// Records are read into a single reused buffer. The iterator ends at
+    /// EOF on a record boundary, and ends after yielding the first error.
+    #[cfg(feature = "std")]
+    fn typed_records<F: FixedSizeRecord>(self) -> TypedRecords<Self, F> where Self: Sized;
+
+    /// Creates a reader of exactly the next `n` bytes of this reader.
+    ///
+    /// Like `Read::take`, the reader reports EOF once `n` bytes have been
+    /// read, but it fails with an `UnexpectedEof` error if this reader
+    /// reaches EOF first, with a `TruncatedRegion` as its inner error. This
+    /// allows a bounded region to be handed to a decoder that trusts its
+    /// length.
+    #[cfg(feature = "std")]
+    fn take_exact(self, n: u64) -> TakeExact<Self> where Self: Sized;
+
+    read_int_or_eof! {
+        read_u8_or_eof: u8::from_ne_bytes, "";
+        read_i8_or_eof: i8::from_ne_bytes, "n";
+        read_u16_le_or_eof: u16::from_le_bytes, " little-endian";
+        read_u16_be_or_eof: u16::from_be_bytes, " big-endian";
+        read_i16_le_or_eof: i16::from_le_bytes, " little-endian";
+        read_i16_be_or_eof: i16::from_be_bytes, " big-endian";
+        read_u32_le_or_eof: u32::from_le_bytes, " little-endian";
+        read_u32_be_or_eof: u32::from_be_bytes, " big-endian";
+        read_i32_le_or_eof: i32::from_le_bytes, " little-endian";
+        read_i32_be_or_eof: i32::from_be_bytes, " big-endian";
+        read_u64_le_or_eof: u64::from_le_bytes, " little-endian";
+        read_u64_be_or_eof: u64::from_be_bytes, " big-endian";
+        read_i64_le_or_eof: i64::from_le_bytes, " little-endian";
+        read_i64_be_or_eof: i64::from_be_bytes, " big-endian";
+    }
+
+    /// Reads exactly `n` bytes into a new `Vec`, or returns `None` if no data
+    /// was read.
+    ///
+    /// The buffer is allocated fallibly, so an `n` that cannot be allocated
+    /// fails with `OutOfMemory` rather than aborting. This makes it suitable
+    /// for lengths read from untrusted sources.
+    #[cfg(feature = "std")]
+    fn read_exact_to_vec(&mut self, n: usize) -> io::Result<Option<Vec<u8>>>;
+
+    /// Appends exactly `n` bytes to `vec`, or zero.
+    ///
+    /// The bytes are read into the spare capacity of `vec` as
+    /// `Read::read_to_end` does, which for the readers of the standard
+    /// library avoids zero-filling it first. The length of `vec` is only
+    /// extended once all `n` bytes have been read; upon EOF or an error it is
+    /// left as it was, and any bytes read are discarded. Capacity is reserved
+    /// as in `read_exact_to_vec`.
+    #[cfg(feature = "std")]
+    fn read_exact_append(&mut self, vec: &mut Vec<u8>, n: usize) -> io::Result<bool>;
+
+    /// Reads exactly `n` bytes into a new boxed slice, or returns `None` if no
+    /// data was read.
+    ///
+    /// This allocates as in `read_exact_to_vec`, but the result carries no
+    /// spare capacity, which suits buffers that are kept around long-term.
+    #[cfg(feature = "std")]
+    fn read_exact_owned(&mut self, n: usize) -> io::Result<Option<Box<[u8]>>>;
+
+    /// Reads an `n` byte UTF-8 string, or returns `None` if no data was read.
+    ///
+    /// The buffer is allocated as in `read_exact_to_vec`, and strings that
+    /// are not valid UTF-8 fail with `InvalidData`.
+    #[cfg(feature = "std")]
+    fn read_str_exact_or_eof(&mut self, n: usize) -> io::Result<Option<String>>;
+
+    /// Reads an `n` byte UTF-8 string with any trailing NUL or space padding
+    /// removed, or returns `None` if no data was read.
+    ///
+    /// This behaves like `read_str_exact_or_eof`, but suits fixed-size name
+    /// fields.
+    #[cfg(feature = "std")]
+    fn read_str_exact_or_eof_trimmed(&mut self, n: usize) -> io::Result<Option<String>>;
+
+    /// Reads a NUL-terminated string of at most `max_len` bytes, or returns
+    /// `None` if no data was read.
+    ///
+    /// The string is read one byte at a time so that nothing past its NUL is
+    /// consumed, so unbuffered readers should be wrapped in a `BufReader`.
+    /// Strings longer than `max_len` fail with `InvalidData`, and EOF before
+    /// the NUL is an error.
+    #[cfg(feature = "std")]
+    fn read_cstr_or_eof(&mut self, max_len: usize) -> io::Result<Option<std::ffi::CString>>;
+
+    /// Reads a length-prefixed frame, or returns `None` at EOF on a frame
+    /// boundary.
+    ///
+    /// The length prefix is decoded according to `format`, and frames longer
+    /// than its maximum length fail with `InvalidData` before the payload is
+    /// read. The payload is allocated as in `read_exact_to_vec`. EOF anywhere
+    /// within the frame is an error.
+    #[cfg(feature = "std")]
+    fn read_frame_or_eof(&mut self, format: &FrameFormat) -> io::Result<Option<Vec<u8>>>;
+
+    /// Reads a length-prefixed frame and deserializes its payload with
+    /// `bincode`, or returns `None` at EOF on a frame boundary.
+    ///
+    /// The frame is read as in `read_frame_or_eof`, so the maximum length of
+    /// `format` bounds the size of each message. A payload that does not
+    /// deserialize fails with `InvalidData`.
+    #[cfg(feature = "bincode")]
+    fn read_message_or_eof<M: ::serde::de::DeserializeOwned>(&mut self, format: &FrameFormat) -> io::Result<Option<M>>;
+
+    /// Reads exactly the number of bytes to fill `buf`, followed by a
+    /// little-endian `checksum` of those bytes, or zero.
+    ///
+    /// EOF before the end of the checksum is an error. A checksum that does
+    /// not match fails with `InvalidData`, with a `ChecksumMismatch` as its
+    /// inner error.
+    #[cfg(feature = "crc")]
+    fn read_exact_crc_or_eof(&mut self, buf: &mut [u8], checksum: Checksum) -> io::Result<bool>;
+
+    /// Reads exactly enough bytes to fill a `P`, or returns `None` if no data
+    /// was read.
+    ///
+    /// The value is read in place, so no intermediate buffer or alignment
+    /// fixup is needed.
+    #[cfg(feature = "zerocopy")]
+    fn read_pod_or_eof<P: ::zerocopy::FromBytes + ::zerocopy::IntoBytes>(&mut self) -> io::Result<Option<P>>;
+
+    /// Reads exactly `n` bytes into the spare capacity of `buf`, or zero.
+    ///
+    /// This behaves like `read_exact_or_eof`, and fails with `InvalidInput`
+    /// if `buf` cannot hold `n` more bytes. The bytes read are appended to
+    /// `buf`, which may have been partially advanced if an error is returned.
+    #[cfg(feature = "bytes")]
+    fn read_exact_into_buf<B: ::bytes::BufMut + ?Sized>(&mut self, buf: &mut B, n: usize) -> io::Result<bool>;
+
+    /// Reads exactly `n` bytes into a new `Bytes`, or returns `None` if no
+    /// data was read.
+    #[cfg(feature = "bytes")]
+    fn read_exact_bytes(&mut self, n: usize) -> io::Result<Option<::bytes::Bytes>>;
+
+    /// Reads exactly `N` bytes into an `ArrayVec`, or returns `None` if no
+    /// data was read.
+    ///
+    /// This behaves like `read_array_or_eof`, but the record carries its
+    /// length, so it can be handed on as a stack-allocated byte buffer.
+    #[cfg(feature = "arrayvec")]
+    fn read_exact_arrayvec_or_eof<const N: usize>(&mut self) -> io::Result<Option<::arrayvec::ArrayVec<u8, N>>>;
+
+    /// Reads exactly `n` bytes into a new `SmallVec`, or returns `None` if no
+    /// data was read.
+    ///
+    /// Records that fit in the inline array `A`, such as `[u8; 64]`, are read
+    /// without allocating, and larger ones spill to the heap, where capacity
+    /// is reserved as in `read_exact_to_vec`.
+    #[cfg(feature = "smallvec")]
+    fn read_exact_smallvec_or_eof<A: ::smallvec::Array<Item = u8>>(&mut self, n: usize) -> io::Result<Option<::smallvec::SmallVec<A>>>;
+
+    /// Appends exactly `n` bytes to a `heapless::Vec`, or zero.
+    ///
+    /// The length of `vec` is only extended once all `n` bytes have been
+    /// read; upon EOF or an error it is left as it was. Fails with
+    /// `InvalidInput`, without reading, if `vec` cannot hold `n` more bytes.
+    #[cfg(feature = "heapless")]
+    fn read_exact_heapless<const N: usize>(&mut self, vec: &mut ::heapless::Vec<u8, N>, n: usize) -> io::Result<bool>;
+
+    /// Fills the spare capacity of a `heapless::Vec` exactly, or reads
+    /// nothing.
+    ///
+    /// This is `read_exact_heapless` for all of the capacity left in `vec`.
+    #[cfg(feature = "heapless")]
+    fn fill_heapless_or_eof<const N: usize>(&mut self, vec: &mut ::heapless::Vec<u8, N>) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill all of `bufs`, or zero.
+    ///
+    /// This behaves like `read_exact_or_eof`, but reads into each buffer in
+    /// order using `read_vectored`. The slices in `bufs` are advanced past the
+    /// data read, so their state after this call is unspecified.
+    #[cfg(feature = "std")]
+    fn read_exact_vectored_or_eof(&mut self, bufs: &mut [io::IoSliceMut]) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill `cursor`, or zero.
+    ///
+    /// This behaves like `read_exact_or_eof`, but allows the buffer to be
+    /// uninitialized. The bytes read are appended to `cursor`.
+    #[cfg(all(feature = "std", feature = "nightly"))]
+    fn read_exact_or_eof_buf(&mut self, cursor: io::BorrowedCursor) -> io::Result<bool>;
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+impl<T: io::Read> ReadExactExt for T {
+    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        read_exact_or_eof(self, buf)
+    }
+
+    fn read_exact_or_eof_status(&mut self, buf: &mut [u8]) -> io::Result<ReadExactStatus> {
+        read_exact_or_eof_status(self, buf)
+    }
+
+    fn read_exact_or_eof_with(&mut self, opts: &ReadExactOpts, buf: &mut [u8]) -> io::Result<bool> {
+        let len = buf.len();
+        fill_with(&mut IoSource(self as &mut dyn io::Read), buf, opts, &[]).and_then(|filled| ReadExactStatus::new(filled, len).into_result())
+    }
+
+    fn read_exact_or_eof_ignoring(&mut self, buf: &mut [u8], kinds: &[io::ErrorKind]) -> io::Result<bool> {
+        let len = buf.len();
+        fill_with(&mut IoSource(self as &mut dyn io::Read), buf, &ReadExactOpts::new(), kinds).and_then(|filled| ReadExactStatus::new(filled, len).into_result())
+    }
+
+    fn read_exact_or_eof_with_progress<F: FnMut(usize, usize)>(&mut self, buf: &mut [u8], progress: F) -> io::Result<bool> {
+        let len = buf.len();
+        let mut reader = Progress {
+            reader: self as &mut dyn io::Read,
+            filled: 0,
+            len,
+            progress,
+        };
+        fill(&mut reader, buf).and_then(|filled| ReadExactStatus::new(filled, len).into_result())
+    }
+
+    fn read_exact_or_eof_yielding<Y: FnMut()>(&mut self, buf: &mut [u8], yield_fn: Y) -> io::Result<bool> {
+        let len = buf.len();
+        let mut reader = Yielding {
+            reader: self as &mut dyn io::Read,
+            started: false,
+            yield_fn,
+        };
+        fill(&mut reader, buf).and_then(|filled| ReadExactStatus::new(filled, len).into_result())
+    }
+
+    #[cfg(feature = "zeroize")]
+    fn read_exact_secret_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let ret = self.read_exact_or_eof(buf);
+        if !matches!(ret, Ok(true)) {
+            ::zeroize::Zeroize::zeroize(buf);
+        }
+        ret
+    }
+
+    #[cfg(all(feature = "zeroize", feature = "std"))]
+    fn read_exact_secret_to_vec(&mut self, n: usize) -> io::Result<Option<::zeroize::Zeroizing<Vec<u8>>>> {
+        let mut buf = Vec::new();
+        buf.try_reserve_exact(n)
+            .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "failed to allocate buffer"))?;
+        buf.resize(n, 0);
+        let mut buf = ::zeroize::Zeroizing::new(buf);
+        self.read_exact_secret_or_eof(&mut buf).map(|full| full.then_some(buf))
+    }
+
+    #[cfg(feature = "digest")]
+    fn read_exact_or_eof_hashing<D: ::digest::Update + ?Sized>(&mut self, buf: &mut [u8], hasher: &mut D) -> io::Result<bool> {
+        let len = buf.len();
+        let mut reader = Hashing {
+            reader: self as &mut dyn io::Read,
+            hasher,
+        };
+        fill(&mut reader, buf).and_then(|filled| ReadExactStatus::new(filled, len).into_result())
+    }
+
+    fn read_exact_or_eof_tee<W: io::Write + ?Sized>(&mut self, buf: &mut [u8], writer: &mut W) -> io::Result<bool> {
+        let len = buf.len();
+        let mut reader = Tee {
+            reader: self as &mut dyn io::Read,
+            writer,
+        };
+        fill(&mut reader, buf).and_then(|filled| ReadExactStatus::new(filled, len).into_result())
+    }
+
+    #[cfg(feature = "std")]
+    fn read_exact_or_eof_atomic(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        match self.read_exact_to_vec(buf.len())? {
+            Some(staged) => {
+                buf.copy_from_slice(&staged);
+                Ok(true)
+            },
+            None => Ok(false),
+        }
+    }
+
+    #[cfg(feature = "error")]
+    fn read_exact_or_eof_typed(&mut self, buf: &mut [u8]) -> Result<bool, ReadExactError> {
+        let len = buf.len();
+        ReadExactError::from_status(self.read_exact_or_eof_status(buf)?, len)
+    }
+
+    #[cfg(feature = "tls")]
+    fn read_exact_or_eof_tls(&mut self, buf: &mut [u8]) -> io::Result<TlsStatus> {
+        tls::read_exact_or_eof_tls(self as &mut dyn io::Read, buf)
+    }
+
+    fn read_full(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        fill(self as &mut dyn io::Read, buf)
+    }
+
+    fn read_exact_all_or_eof(&mut self, bufs: &mut [&mut [u8]]) -> io::Result<bool> {
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        let mut filled = 0;
+
+        for buf in bufs {
+            let n = fill(self as &mut dyn io::Read, buf)?;
+            filled += n;
+            if n < buf.len() {
+                break
+            }
+        }
+
+        ReadExactStatus::new(filled, len).into_result()
+    }
+
+    fn read_at_least_or_eof(&mut self, buf: &mut [u8], min: usize) -> io::Result<Option<usize>> {
+        assert!(min <= buf.len(), "min exceeds the buffer length");
+        let mut buf = SliceBuf::new(buf);
+        fill_source(&mut IoSource(self as &mut dyn io::Read), &mut buf, &mut AtLeast(min))?;
+        let filled = buf.filled();
+
+        ReadExactStatus::new(filled.min(min), min).into_result()
+            .map(|full| full.then_some(filled))
+    }
+
+    fn read_exact_or_zero_pad(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let filled = fill(self as &mut dyn io::Read, buf)?;
+        buf[filled..].fill(0);
+        Ok(filled)
+    }
+
+    fn try_read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<TryReadStatus> {
+        self.try_read_exact_or_eof_resume(buf, 0)
+    }
+
+    fn try_read_exact_or_eof_resume(&mut self, buf: &mut [u8], filled: usize) -> io::Result<TryReadStatus> {
+        let len = buf.len();
+        let mut buf = SliceBuf::with_filled(buf, filled);
+        let mut control = NonBlocking { blocked: false };
+        fill_source(&mut IoSource(self as &mut dyn io::Read), &mut buf, &mut control)?;
+        let filled = buf.filled();
+
+        if control.blocked {
+            return Ok(TryReadStatus::Pending { filled })
+        }
+        ReadExactStatus::new(filled, len).into_result().map(TryReadStatus::Done)
+    }
+
+    fn read_exact_or_eof_cancellable(&mut self, buf: &mut [u8], cancel: &AtomicBool) -> io::Result<CancelStatus> {
+        let len = buf.len();
+        let mut buf = SliceBuf::new(buf);
+        let mut control = Cancellable { cancel, cancelled: false };
+        fill_source(&mut IoSource(self as &mut dyn io::Read), &mut buf, &mut control)?;
+        let filled = buf.filled();
+
+        if control.cancelled {
+            return Ok(CancelStatus::Cancelled { filled })
+        }
+        ReadExactStatus::new(filled, len).into_result().map(CancelStatus::Done)
+    }
+
+    fn read_until_filled_or<F: FnMut(&[u8]) -> bool>(&mut self, buf: &mut [u8], pred: F) -> io::Result<UntilStatus> {
+        let len = buf.len();
+        let mut buf = SliceBuf::new(buf);
+        let mut control = Until { pred, matched: false };
+        fill_source(&mut IoSource(self as &mut dyn io::Read), &mut buf, &mut control)?;
+        let filled = buf.filled();
+
+        if control.matched {
+            return Ok(UntilStatus::Matched { filled })
+        }
+        ReadExactStatus::new(filled, len).into_result().map(UntilStatus::Done)
+    }
+
+    fn read_exact_or_eof_into<B: AsMut<[u8]>>(&mut self, mut buf: B) -> (io::Result<bool>, B) {
+        (self.read_exact_or_eof(buf.as_mut()), buf)
+    }
+
+    fn read_exact_or_eof_slice<'a>(&mut self, buf: &'a mut [u8], len: usize) -> io::Result<Option<&'a mut [u8]>> {
+        let buf = buf.get_mut(..len)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "buffer is too small"))?;
+        self.read_exact_or_eof(buf).map(|full| full.then_some(buf))
+    }
+
+    fn read_exact_or_eof_stats(&mut self, buf: &mut [u8]) -> (io::Result<bool>, FillStats) {
+        let mut reader = Instrumented::new(self as &mut dyn io::Read);
+        let ret = read_exact_or_eof_status(&mut reader, buf);
+        instrumented::fill_stats(reader.stats(), ret)
+    }
+
+    fn skip_exact_or_eof(&mut self, n: u64) -> io::Result<bool> {
+        let mut scratch = [0; 0x1000];
+        let mut remaining = n;
+
+        // each chunk is a fill of its own, which only falls short at EOF
+        while remaining > 0 {
+            let chunk = &mut scratch[..remaining.min(0x1000) as usize];
+            let read = fill(self as &mut dyn io::Read, chunk)?;
+            remaining -= read as u64;
+            if read < chunk.len() {
+                return match remaining == n {
+                    true => Ok(false),
+                    false => Err(unexpected_eof()),
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn read_array_or_eof<const N: usize>(&mut self) -> io::Result<Option<[u8; N]>> {
+        let mut buf = [0; N];
+        self.read_exact_or_eof(&mut buf).map(|full| full.then_some(buf))
+    }
+
+    fn read_records_or_eof<const N: usize>(&mut self, out: &mut [[u8; N]]) -> io::Result<usize> {
+        let filled = fill(self as &mut dyn io::Read, out.as_flattened_mut())?;
+        match N {
+            0 => Ok(out.len()),
+            _ if filled % N != 0 => Err(unexpected_eof()),
+            _ => Ok(filled / N),
+        }
+    }
+
+    fn read_header_or_eof<const N: usize>(&mut self, magic: &[u8]) -> io::Result<HeaderStatus<N>> {
+        assert!(magic.len() <= N, "magic is longer than the header");
+        Ok(match self.read_array_or_eof()? {
+            Some(header) if header.starts_with(magic) => HeaderStatus::Ok(header),
+            Some(header) => HeaderStatus::BadMagic(header),
+            None => HeaderStatus::Eof,
+        })
+    }
+
+    fn exact_chunks<const N: usize>(self) -> ExactChunks<Self, N> {
+        ExactChunks::new(self)
+    }
+
+    #[cfg(feature = "std")]
+    fn read_record_or_eof<F: FixedSizeRecord>(&mut self) -> io::Result<Option<F>> {
+        let mut buf = vec![0; F::SIZE];
+        match self.read_exact_or_eof(&mut buf)? {
+            true => records::parse_record(&buf).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn typed_records<F: FixedSizeRecord>(self) -> TypedRecords<Self, F> {
+        TypedRecords::new(self)
+    }
+
+    #[cfg(feature = "std")]
+    fn take_exact(self, n: u64) -> TakeExact<Self> {
+        TakeExact::new(self, n)
+    }
+
+    #[cfg(feature = "std")]
+    fn read_exact_to_vec(&mut self, n: usize) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = Vec::new();
+        buf.try_reserve_exact(n)
+            .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "failed to allocate buffer"))?;
+        buf.resize(n, 0);
+        self.read_exact_or_eof(&mut buf).map(|full| full.then_some(buf))
+    }
+
+    #[cfg(feature = "std")]
+    fn read_exact_append(&mut self, vec: &mut Vec<u8>, n: usize) -> io::Result<bool> {
+        use std::io::Read;
+
+        let start = vec.len();
+        vec.try_reserve_exact(n)
+            .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "failed to allocate buffer"))?;
+        let ret = (self as &mut dyn io::Read).take(n as u64).read_to_end(vec);
+        let filled = vec.len() - start;
+        if filled < n {
+            vec.truncate(start);
+        }
+        ret.and_then(|_| ReadExactStatus::new(filled, n).into_result())
+    }
+
+    #[cfg(feature = "std")]
+    fn read_exact_owned(&mut self, n: usize) -> io::Result<Option<Box<[u8]>>> {
+        self.read_exact_to_vec(n).map(|buf| buf.map(Vec::into_boxed_slice))
+    }
+
+    #[cfg(feature = "std")]
+    fn read_str_exact_or_eof(&mut self, n: usize) -> io::Result<Option<String>> {
+        self.read_exact_to_vec(n)?
+            .map(|bytes| String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+            .transpose()
+    }
+
+    #[cfg(feature = "std")]
+    fn read_str_exact_or_eof_trimmed(&mut self, n: usize) -> io::Result<Option<String>> {
+        self.read_exact_to_vec(n)?
+            .map(|mut bytes| {
+                let len = bytes.iter().rposition(|&b| b != 0 && b != b' ').map_or(0, |i| i + 1);
+                bytes.truncate(len);
+                String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            })
+            .transpose()
+    }
+
+    #[cfg(feature = "std")]
+    fn read_cstr_or_eof(&mut self, max_len: usize) -> io::Result<Option<std::ffi::CString>> {
+        let mut bytes = Vec::new();
+
+        loop {
+            let mut byte = [0];
+            if !self.read_exact_or_eof(&mut byte)? {
+                return match bytes.is_empty() {
+                    true => Ok(None),
+                    false => Err(unexpected_eof()),
+                }
+            }
+
+            match byte[0] {
+                0 => return std::ffi::CString::new(bytes).map(Some).map_err(io::Error::from),
+                _ if bytes.len() == max_len => return Err(io::Error::new(io::ErrorKind::InvalidData, "string exceeds maximum length")),
+                byte => bytes.push(byte),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn read_frame_or_eof(&mut self, format: &FrameFormat) -> io::Result<Option<Vec<u8>>> {
+        frame::read_frame(self as &mut dyn io::Read, format)
+    }
+
+    #[cfg(feature = "bincode")]
+    fn read_message_or_eof<M: ::serde::de::DeserializeOwned>(&mut self, format: &FrameFormat) -> io::Result<Option<M>> {
+        frame::read_message(self as &mut dyn io::Read, format)
+    }
+
+    #[cfg(feature = "crc")]
+    fn read_exact_crc_or_eof(&mut self, buf: &mut [u8], checksum: Checksum) -> io::Result<bool> {
+        let mut trailer = [0; 4];
+        if !self.read_exact_all_or_eof(&mut [&mut *buf, &mut trailer])? {
+            return Ok(false)
+        }
+        checksum::verify(checksum, buf, trailer).map(|()| true)
+    }
+
+    #[cfg(feature = "zerocopy")]
+    fn read_pod_or_eof<P: ::zerocopy::FromBytes + ::zerocopy::IntoBytes>(&mut self) -> io::Result<Option<P>> {
+        let mut value = P::new_zeroed();
+        self.read_exact_or_eof(value.as_mut_bytes()).map(|full| full.then_some(value))
+    }
+
+    #[cfg(feature = "bytes")]
+    fn read_exact_into_buf<B: ::bytes::BufMut + ?Sized>(&mut self, buf: &mut B, n: usize) -> io::Result<bool> {
+        bytes::read_into_buf(self as &mut dyn io::Read, buf, n)
+    }
+
+    #[cfg(feature = "bytes")]
+    fn read_exact_bytes(&mut self, n: usize) -> io::Result<Option<::bytes::Bytes>> {
+        bytes::read_bytes(self as &mut dyn io::Read, n)
+    }
+
+    #[cfg(feature = "arrayvec")]
+    fn read_exact_arrayvec_or_eof<const N: usize>(&mut self) -> io::Result<Option<::arrayvec::ArrayVec<u8, N>>> {
+        self.read_array_or_eof().map(|buf| buf.map(::arrayvec::ArrayVec::from))
+    }
+
+    #[cfg(feature = "smallvec")]
+    fn read_exact_smallvec_or_eof<A: ::smallvec::Array<Item = u8>>(&mut self, n: usize) -> io::Result<Option<::smallvec::SmallVec<A>>> {
+        let mut buf = ::smallvec::SmallVec::new();
+        buf.try_reserve_exact(n)
+            .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "failed to allocate buffer"))?;
+        buf.resize(n, 0);
+        self.read_exact_or_eof(&mut buf).map(|full| full.then_some(buf))
+    }
+
+    #[cfg(feature = "heapless")]
+    fn read_exact_heapless<const N: usize>(&mut self, vec: &mut ::heapless::Vec<u8, N>, n: usize) -> io::Result<bool> {
+        heapless::append(vec, n, |buf| fill(self as &mut dyn io::Read, buf))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "buffer is too small"))??
+            .into_result()
+    }
+
+    #[cfg(feature = "heapless")]
+    fn fill_heapless_or_eof<const N: usize>(&mut self, vec: &mut ::heapless::Vec<u8, N>) -> io::Result<bool> {
+        let n = vec.capacity() - vec.len();
+        self.read_exact_heapless(vec, n)
+    }
+
+    #[cfg(feature = "std")]
+    fn read_exact_vectored_or_eof(&mut self, bufs: &mut [io::IoSliceMut]) -> io::Result<bool> {
+        let mut bufs = source::VectoredBuf::new(bufs);
+        fill_source(&mut IoSource(self as &mut dyn io::Read), &mut bufs, &mut OptsControl::new(&ReadExactOpts::new(), &[]))?;
+        bufs.status().into_result()
+    }
+
+    #[cfg(all(feature = "std", feature = "nightly"))]
+    fn read_exact_or_eof_buf(&mut self, cursor: io::BorrowedCursor) -> io::Result<bool> {
+        let mut cursor = source::CursorBuf::new(cursor);
+        fill_source(&mut IoSource(self as &mut dyn io::Read), &mut cursor, &mut OptsControl::new(&ReadExactOpts::new(), &[]))?;
+        cursor.status().into_result()
+    }
+}
+
+/// An extension trait that applies to all `std::io::Write` types.
+///
+/// Without the `std` feature, this applies to `no_std_io2::io::Write` types
+/// instead.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub trait WriteExactExt {
+    /// Writes all of `buf`, or stops if the peer has closed.
+    ///
+    /// This function returns `true` if the whole buffer was written, or
+    /// `false` if a write failed with `BrokenPipe` or `ConnectionReset`. Any
+    /// amount of `buf` may have been written if the function returns `false`
+    /// or an error.
+    fn write_all_or_closed(&mut self, buf: &[u8]) -> io::Result<bool>;
+
+    /// Writes all of `bufs`, or stops if the peer has closed.
+    ///
+    /// This behaves like `write_all_or_closed`, but writes each buffer in
+    /// order using `write_vectored`. The slices in `bufs` are advanced past
+    /// the data written, so their state after this call is unspecified.
+    #[cfg(feature = "std")]
+    fn write_all_vectored_or_closed(&mut self, bufs: &mut [io::IoSlice]) -> io::Result<bool>;
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+impl<T: io::Write> WriteExactExt for T {
+    fn write_all_or_closed(&mut self, mut buf: &[u8]) -> io::Result<bool> {
+        while !buf.is_empty() {
+            match self.write(buf) {
+                Ok(0) => return Err(write_zero()),
+                Ok(n) => buf = &buf[n..],
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(ref e) if is_closed(e) => return Ok(false),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(true)
+    }
+
+    #[cfg(feature = "std")]
+    fn write_all_vectored_or_closed(&mut self, mut bufs: &mut [io::IoSlice]) -> io::Result<bool> {
+        io::IoSlice::advance_slices(&mut bufs, 0);
+        while !bufs.is_empty() {
+            match self.write_vectored(bufs) {
+                Ok(0) => return Err(write_zero()),
+                Ok(n) => io::IoSlice::advance_slices(&mut bufs, n),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(ref e) if is_closed(e) => return Ok(false),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Writes all of `request` to `stream`, then reads exactly the number of
+/// bytes to fill `response`, or zero.
+///
+/// The stream is flushed between the two, so that a buffered request is sent
+/// before waiting on the response. This returns `false` if the peer closed
+/// the stream without sending any of the response, and is otherwise the
+/// `read_exact_or_eof` of the response.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub fn exchange_exact<S: io::Read + io::Write + ?Sized>(stream: &mut S, request: &[u8], response: &mut [u8]) -> io::Result<bool> {
+    stream.write_all(request)?;
+    stream.flush()?;
+    let len = response.len();
+    fill(stream, response).and_then(|filled| ReadExactStatus::new(filled, len).into_result())
+}
+
+/// Copies exactly `n` bytes from `reader` to `writer`, or zero.
+///
+/// The bytes are streamed through a fixed-size buffer rather than being read
+/// in full first. This returns `true` once all `n` bytes are written, or
+/// `false` if `reader` was at EOF. EOF after some bytes were copied is an
+/// error, and leaves those bytes written.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub fn copy_exact<R: io::Read + ?Sized, W: io::Write + ?Sized>(reader: &mut R, writer: &mut W, n: usize) -> io::Result<bool> {
+    copy_fill(reader, writer, n).and_then(|copied| ReadExactStatus::new(copied, n).into_result())
+}
+
+/// Copies up to `n` bytes from `reader` to `writer`, stopping early only at
+/// EOF, and returns the number of bytes copied.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+fn copy_fill<R: io::Read + ?Sized, W: io::Write + ?Sized>(reader: &mut R, writer: &mut W, n: usize) -> io::Result<usize> {
+    let mut buf = [0; 8192];
+    let mut copied = 0;
+
+    while copied < n {
+        let chunk = &mut buf[..(n - copied).min(8192)];
+        let read = fill(reader, chunk)?;
+        writer.write_all(&chunk[..read])?;
+        copied += read;
+        if read < chunk.len() {
+            break
+        }
+    }
+
+    Ok(copied)
+}
+
+/// Reads exactly the number of bytes to fill `buf`, or zero.
+///
+/// This is `ReadExactExt::read_exact_or_eof` for trait objects. The methods
+/// of `ReadExactExt` delegate to it, so that the read loop is compiled once
+/// rather than for every reader type.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub fn read_exact_or_eof(reader: &mut dyn io::Read, buf: &mut [u8]) -> io::Result<bool> {
+    read_exact_or_eof_status(reader, buf)?.into_result()
+}
+
+/// Reads exactly the number of bytes to fill `buf`, reporting how much was
+/// read if EOF is encountered first.
+///
+/// This is `ReadExactExt::read_exact_or_eof_status` for trait objects.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub fn read_exact_or_eof_status(reader: &mut dyn io::Read, buf: &mut [u8]) -> io::Result<ReadExactStatus> {
+    let len = buf.len();
+    fill(reader, buf).map(|filled| ReadExactStatus::new(filled, len))
+}
+
+/// Reads exactly the number of bytes to fill `buf` from a sequence of
+/// readers, or zero.
+///
+/// Each reader is drained in order before moving on to the next, so a record
+/// may span several readers, and EOF is only reached once the last reader is
+/// exhausted. Readers that were exhausted by a previous call are expected to
+/// keep returning EOF.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub fn read_exact_or_eof_multi(readers: &mut [&mut dyn io::Read], buf: &mut [u8]) -> io::Result<bool> {
+    let len = buf.len();
+    let mut filled = 0;
+
+    for reader in readers {
+        if filled == len {
+            break
+        }
+        filled += fill(&mut **reader, &mut buf[filled..])?;
+    }
+
+    ReadExactStatus::new(filled, len).into_result()
+}
+
+/// Reads into `buf` until it is full or EOF is reached, returning the number
+/// of bytes read.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+fn fill<R: io::Read + ?Sized>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    fill_with(&mut IoSource(reader), buf, &ReadExactOpts::new(), &[])
+}
+
+/// The fill policy of `ReadExactExt::read_exact_or_eof_cancellable`, which
+/// ends the fill once `cancel` is set.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+struct Cancellable<'a> {
+    cancel: &'a AtomicBool,
+    cancelled: bool,
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+impl<B: ?Sized> FillControl<B, io::Error> for Cancellable<'_> {
+    fn before_read(&mut self, _: u32, _: usize, _: usize) -> io::Result<bool> {
+        self.cancelled = self.cancel.load(Ordering::Acquire);
+        Ok(!self.cancelled)
+    }
+}
+
+/// The fill policy of `ReadExactExt::read_until_filled_or`, which ends the
+/// fill once `pred` matches the bytes read.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+struct Until<F> {
+    pred: F,
+    matched: bool,
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+impl<F: FnMut(&[u8]) -> bool> FillControl<SliceBuf<'_>, io::Error> for Until<F> {
+    fn after_read(&mut self, buf: &SliceBuf) -> bool {
+        self.matched = !buf.is_full() && (self.pred)(buf.filled_bytes());
+        self.matched
+    }
+}
+
+/// A reader that reports the progress of a fill of `len` bytes.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+struct Progress<'a, R: ?Sized, F> {
+    reader: &'a mut R,
+    filled: usize,
+    len: usize,
+    progress: F,
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+impl<R: io::Read + ?Sized, F: FnMut(usize, usize)> io::Read for Progress<'_, R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        if n > 0 {
+            self.filled += n;
+            (self.progress)(self.filled, self.len);
+        }
+        Ok(n)
+    }
+}
+
+/// A reader that yields before every read after the first.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+struct Yielding<'a, R: ?Sized, Y> {
+    reader: &'a mut R,
+    started: bool,
+    yield_fn: Y,
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+impl<R: io::Read + ?Sized, Y: FnMut()> io::Read for Yielding<'_, R, Y> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.started {
+            (self.yield_fn)();
+        }
+        self.started = true;
+        self.reader.read(buf)
+    }
+}
+
+/// A reader that hashes the bytes it reads.
+#[cfg(all(feature = "digest", any(feature = "std", feature = "no-std-io")))]
+struct Hashing<'a, R: ?Sized, D: ?Sized> {
+    reader: &'a mut R,
+    hasher: &'a mut D,
+}
+
+#[cfg(all(feature = "digest", any(feature = "std", feature = "no-std-io")))]
+impl<R: io::Read + ?Sized, D: ::digest::Update + ?Sized> io::Read for Hashing<'_, R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// A reader that writes the bytes it reads to a writer.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+struct Tee<'a, R: ?Sized, W: ?Sized> {
+    reader: &'a mut R,
+    writer: &'a mut W,
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+impl<R: io::Read + ?Sized, W: io::Write + ?Sized> io::Read for Tee<'_, R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.writer.write_all(&buf[..n])?;
+        Ok(n)
+    }
+}
+
+/// Reads into `buf` until it is full or EOF is reached, as configured by
+/// `opts`, additionally treating `eof_kinds` before any data as EOF.
+///
+/// This is `source::fill_source` for sources of `io::Error`s.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+fn fill_with<S: Source<Error = io::Error> + ?Sized>(source: &mut S, buf: &mut [u8], opts: &ReadExactOpts, eof_kinds: &[io::ErrorKind]) -> io::Result<usize> {
+    let mut buf = SliceBuf::new(buf);
+    fill_source(source, &mut buf, &mut OptsControl::new(opts, eof_kinds)).map(|()| buf.filled())
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+fn unexpected_eof() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer")
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+fn write_zero() -> io::Error {
+    io::Error::new(io::ErrorKind::WriteZero, "failed to write whole buffer")
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+fn is_closed(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::io::{self, IoSlice, IoSliceMut, Read};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+    use super::{copy_exact, exchange_exact, read_exact_or_eof_multi, CancelStatus, HeaderStatus, ReadExactExt, ReadExactOpts, ReadExactStatus, TryReadStatus, UntilStatus, WriteExactExt};
+    use super::testing::ScriptedReader;
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
+    fn eof() {
+        let mut read = io::empty();
+        let mut buf = [0, 0];
+
+        let ret = read.read_exact_or_eof(&mut buf);
+
+        assert_eq!(ret.unwrap(), false);
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
+    fn ok() {
+        let mut read = io::repeat(1);
+        let mut buf = [0, 0];
+
+        let ret = read.read_exact_or_eof(&mut buf);
+
+        assert_eq!(ret.unwrap(), true);
+        assert_eq!(buf, [1, 1]);
+    }
+
+    /// Claims to fill every read without touching the buffer, failing if a
+    /// read is larger than `MAX_READ`.
+    #[cfg(target_pointer_width = "64")]
+    struct Unbounded;
+
+    #[cfg(target_pointer_width = "64")]
+    impl Read for Unbounded {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match buf.len() {
+                len if len > crate::source::MAX_READ => Err(io::ErrorKind::InvalidInput.into()),
+                len => Ok(len),
+            }
+        }
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    #[ignore = "allocates a buffer larger than MAX_READ"]
+    fn huge() {
+        let mut buf = vec![0u8; crate::source::MAX_READ + 1];
+
+        assert!(Unbounded.read_exact_or_eof(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn until() {
+        let mut read = ScriptedReader::new().data(b"ab").data(b"c\n").data(b"defg").data(b"h");
+        let mut buf = [0; 8];
+        let newline = |filled: &[u8]| filled.ends_with(b"\n");
+
+        assert_eq!(read.read_until_filled_or(&mut buf, newline).unwrap(), UntilStatus::Matched { filled: 4 });
+        assert_eq!(&buf[..4], b"abc\n");
+        assert_eq!(read.read_until_filled_or(&mut buf[..4], newline).unwrap(), UntilStatus::Done(true));
+        assert_eq!(&buf[..4], b"defg");
+        assert!(read.read_until_filled_or(&mut buf[..2], newline).is_err());
+        assert_eq!(read.read_until_filled_or(&mut buf, newline).unwrap(), UntilStatus::Done(false));
+    }
+
+    #[test]
+    fn skip() {
+        let mut read = io::repeat(1).take(0x1000 + 3);
+
+        assert!(read.skip_exact_or_eof(0x1000 + 1).unwrap());
+        assert!(read.skip_exact_or_eof(0).unwrap());
+        assert!(read.skip_exact_or_eof(3).is_err());
+        assert!(!read.skip_exact_or_eof(3).unwrap());
+    }
+
+    #[test]
+    fn records() {
+        let mut read = &[1, 2, 3, 4, 5, 6, 7][..];
+        let mut out = [[0; 2]; 2];
+
+        assert_eq!(read.read_records_or_eof(&mut out).unwrap(), 2);
+        assert_eq!(out, [[1, 2], [3, 4]]);
+        assert!(read.read_records_or_eof(&mut out).is_err());
+        assert_eq!(out[0], [5, 6]);
+        assert_eq!(read.read_records_or_eof(&mut out).unwrap(), 0);
+
+        let mut read = &[1, 2][..];
+        assert_eq!(read.read_records_or_eof(&mut out).unwrap(), 1);
+    }
+
+    #[test]
+    fn dyn_reader() {
+        let mut read: Box<dyn Read> = Box::new(&[1, 2, 3][..]);
+        let mut buf = [0, 0];
+
+        assert!(super::read_exact_or_eof(&mut read, &mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(super::read_exact_or_eof_status(&mut *read, &mut buf).unwrap(), ReadExactStatus::PartialEof { filled: 1 });
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let mut read = io::repeat(1).take(1);
+        let mut buf = [0, 0];
+
+        let ret = read.read_exact_or_eof(&mut buf);
+
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn status_partial() {
+        let mut read = io::repeat(1).take(1);
+        let mut buf = [0, 0];
+
+        let ret = read.read_exact_or_eof_status(&mut buf);
+
+        assert_eq!(ret.unwrap(), ReadExactStatus::PartialEof { filled: 1 });
+        assert_eq!(buf[0], 1);
+    }
+
+    #[test]
+    fn status_eof() {
+        let mut read = io::empty();
+        let mut buf = [0, 0];
+
+        let ret = read.read_exact_or_eof_status(&mut buf);
+
+        assert_eq!(ret.unwrap(), ReadExactStatus::CleanEof);
+    }
+
+    #[test]
+    fn atomic() {
+        let mut read = &[1, 2, 3][..];
+        let mut buf = [0, 0];
+
+        assert!(read.read_exact_or_eof_atomic(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(read.read_exact_or_eof_atomic(&mut buf).is_err());
+        assert_eq!(buf, [1, 2]);
+        assert!(!read.read_exact_or_eof_atomic(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+    }
+
+    #[test]
+    fn read_full_short() {
+        let mut read = io::repeat(1).take(3);
+        let mut buf = [0; 4];
+
+        let ret = read.read_full(&mut buf);
+
+        assert_eq!(ret.unwrap(), 3);
+        assert_eq!(buf, [1, 1, 1, 0]);
+    }
 
-use std::io;
+    #[test]
+    fn all() {
+        let mut read = &[1, 2, 3, 4][..];
+        let (mut a, mut b) = ([0; 1], [0; 2]);
 
-/// An extension trait that applies to all `std::io::Read` types.
-pub trait ReadExactExt {
-    /// Reads exactly the number of bytes to fill `buf`, or zero.
-    ///
-    /// This function returns `true` upon successful read, or `false` if no
-    /// data was read. No guarantees about the contents of `buf` are provided
-    /// if the function returns `false` or an error.
-    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool>;
-}
+        assert!(read.read_exact_all_or_eof(&mut [&mut a, &mut b]).unwrap());
+        assert_eq!((a, b), ([1], [2, 3]));
+        assert!(read.read_exact_all_or_eof(&mut [&mut a, &mut b]).is_err());
+        assert!(!read.read_exact_all_or_eof(&mut [&mut a, &mut b]).unwrap());
+    }
 
-impl<T: io::Read> ReadExactExt for T {
-    fn read_exact_or_eof(&mut self, mut buf: &mut [u8]) -> io::Result<bool> {
-        let mut read_some = buf.is_empty();
+    #[test]
+    fn at_least() {
+        let mut read = &[1, 2, 3][..];
+        let mut buf = [0; 4];
 
-        while !buf.is_empty() {
-            match self.read(buf) {
-                Ok(0) => break,
-                Ok(n) => {
-                    read_some = true;
-                    buf = &mut {buf}[n..];
+        assert_eq!(read.read_at_least_or_eof(&mut buf, 1).unwrap(), Some(3));
+        assert_eq!(read.read_at_least_or_eof(&mut buf, 1).unwrap(), None);
+        assert!((&[1][..]).read_at_least_or_eof(&mut buf, 2).is_err());
+    }
+
+    #[test]
+    fn zero_pad() {
+        let mut read = &[1, 2, 3][..];
+        let mut buf = [9, 9];
+
+        assert_eq!(read.read_exact_or_zero_pad(&mut buf).unwrap(), 2);
+        assert_eq!(read.read_exact_or_zero_pad(&mut buf).unwrap(), 1);
+        assert_eq!(buf, [3, 0]);
+        assert_eq!(read.read_exact_or_zero_pad(&mut buf).unwrap(), 0);
+        assert_eq!(buf, [0, 0]);
+    }
+
+    #[test]
+    fn array() {
+        let mut read = &[1, 2, 3][..];
+
+        assert_eq!(read.read_array_or_eof::<2>().unwrap(), Some([1, 2]));
+        assert!(read.read_array_or_eof::<2>().is_err());
+        assert_eq!(read.read_array_or_eof::<2>().unwrap(), None);
+    }
+
+    #[test]
+    fn to_vec() {
+        let mut read = &[1, 2, 3][..];
+
+        assert_eq!(read.read_exact_to_vec(2).unwrap(), Some(vec![1, 2]));
+        assert!(read.read_exact_to_vec(2).is_err());
+        assert_eq!(read.read_exact_to_vec(2).unwrap(), None);
+    }
+
+    #[test]
+    fn owned() {
+        let mut read = &[1, 2, 3][..];
+
+        assert_eq!(read.read_exact_owned(2).unwrap().as_deref(), Some(&[1, 2][..]));
+        assert!(read.read_exact_owned(2).is_err());
+        assert_eq!(read.read_exact_owned(2).unwrap(), None);
+    }
+
+    #[test]
+    fn to_vec_oom() {
+        let mut read = &[1, 2, 3][..];
+
+        let ret = read.read_exact_to_vec(usize::MAX);
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::OutOfMemory);
+    }
+
+    #[test]
+    fn header() {
+        let mut read = &b"RXv1RYv1RX"[..];
+
+        assert_eq!(read.read_header_or_eof::<4>(b"RX").unwrap(), HeaderStatus::Ok(*b"RXv1"));
+        assert_eq!(read.read_header_or_eof::<4>(b"RX").unwrap(), HeaderStatus::BadMagic(*b"RYv1"));
+        assert!(read.read_header_or_eof::<4>(b"RX").is_err());
+        assert_eq!(read.read_header_or_eof::<4>(b"RX").unwrap(), HeaderStatus::Eof);
+    }
+
+    #[test]
+    fn str_exact() {
+        let mut read = &b"ab \0\0cd\xff"[..];
+
+        assert_eq!(read.read_str_exact_or_eof(2).unwrap().as_deref(), Some("ab"));
+        assert_eq!(read.read_str_exact_or_eof_trimmed(3).unwrap().as_deref(), Some(""));
+        assert_eq!(read.read_str_exact_or_eof(3).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(read.read_str_exact_or_eof(3).unwrap(), None);
+    }
+
+    #[test]
+    fn str_trimmed() {
+        let mut read = &b"a b \0"[..];
+
+        assert_eq!(read.read_str_exact_or_eof_trimmed(5).unwrap().as_deref(), Some("a b"));
+    }
+
+    #[test]
+    fn cstr() {
+        let mut read = &b"ab\0\0abc\0"[..];
+
+        assert_eq!(read.read_cstr_or_eof(2).unwrap().unwrap().as_bytes(), b"ab");
+        assert_eq!(read.read_cstr_or_eof(2).unwrap().unwrap().as_bytes(), b"");
+        assert_eq!(read.read_cstr_or_eof(2).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!((&b"ab"[..]).read_cstr_or_eof(2).is_err());
+        assert_eq!((&b""[..]).read_cstr_or_eof(2).unwrap(), None);
+    }
+
+    #[test]
+    fn ints() {
+        let mut read = &[1, 2, 1, 2, 3, 4, 5, 6][..];
+
+        assert_eq!(read.read_u16_le_or_eof().unwrap(), Some(0x0201));
+        assert_eq!(read.read_u32_be_or_eof().unwrap(), Some(0x01020304));
+        assert_eq!(read.read_i8_or_eof().unwrap(), Some(5));
+        assert!(read.read_u16_be_or_eof().is_err());
+        assert_eq!(read.read_u64_le_or_eof().unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "zerocopy")]
+    fn pod() {
+        #[derive(zerocopy::FromBytes, zerocopy::IntoBytes, Debug, PartialEq)]
+        #[repr(C)]
+        struct Header {
+            magic: [u8; 2],
+            len: u16,
+        }
+
+        let mut read = &[b'R', b'E', 4, 0, 1][..];
+
+        let header = read.read_pod_or_eof::<Header>().unwrap();
+        assert_eq!(header, Some(Header { magic: *b"RE", len: u16::from_ne_bytes([4, 0]) }));
+        assert!(read.read_pod_or_eof::<Header>().is_err());
+        assert_eq!(read.read_pod_or_eof::<Header>().unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "arrayvec")]
+    fn arrayvec() {
+        let mut read = &[1, 2, 3][..];
+
+        assert_eq!(read.read_exact_arrayvec_or_eof::<2>().unwrap().unwrap().as_slice(), [1, 2]);
+        assert!(read.read_exact_arrayvec_or_eof::<2>().is_err());
+        assert_eq!(read.read_exact_arrayvec_or_eof::<2>().unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "smallvec")]
+    fn smallvec() {
+        let mut read = &[1, 2, 3, 4, 5][..];
+
+        let buf = read.read_exact_smallvec_or_eof::<[u8; 2]>(2).unwrap().unwrap();
+        assert_eq!((&buf[..], buf.spilled()), (&[1, 2][..], false));
+        let buf = read.read_exact_smallvec_or_eof::<[u8; 2]>(3).unwrap().unwrap();
+        assert_eq!((&buf[..], buf.spilled()), (&[3, 4, 5][..], true));
+        assert_eq!(read.read_exact_smallvec_or_eof::<[u8; 2]>(1).unwrap(), None);
+    }
+
+    #[test]
+    fn vectored() {
+        let mut read = &[1, 2, 3, 4][..];
+        let (mut a, mut b) = ([0; 1], [0; 2]);
+
+        let mut bufs = [IoSliceMut::new(&mut a), IoSliceMut::new(&mut []), IoSliceMut::new(&mut b)];
+
+        let ret = read.read_exact_vectored_or_eof(&mut bufs);
+
+        assert!(ret.unwrap());
+        assert_eq!((a, b), ([1], [2, 3]));
+
+        let ret = read.read_exact_vectored_or_eof(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)]);
+
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn borrowed_buf() {
+        use std::io::BorrowedBuf;
+        use std::mem::MaybeUninit;
+
+        let mut read = &[1, 2, 3][..];
+        let mut storage = [MaybeUninit::uninit(); 2];
+        let mut buf = BorrowedBuf::from(&mut storage[..]);
+
+        assert!(read.read_exact_or_eof_buf(buf.unfilled()).unwrap());
+        assert_eq!(buf.filled(), [1, 2]);
+
+        buf.clear();
+        assert!(read.read_exact_or_eof_buf(buf.unfilled()).is_err());
+    }
+
+    struct Closed(usize);
+
+    impl io::Write for Closed {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            match self.0 {
+                0 => Err(io::ErrorKind::BrokenPipe.into()),
+                n => {
+                    let n = n.min(buf.len());
+                    self.0 -= n;
+                    Ok(n)
                 },
-                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
-                Err(e) => return Err(e),
             }
         }
 
-        if !buf.is_empty() && read_some {
-            Err(io::Error::new(io::ErrorKind::UnexpectedEof, "failed to fill whole buffer"))
-        } else {
-            Ok(read_some)
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::io::{self, Read};
-    use super::ReadExactExt;
+    #[test]
+    fn copy() {
+        let mut read = &[1, 2, 3][..];
+        let mut write = Vec::new();
+
+        assert!(copy_exact(&mut read, &mut write, 2).unwrap());
+        assert_eq!(write, [1, 2]);
+        assert!(copy_exact(&mut read, &mut write, 2).is_err());
+        assert!(!copy_exact(&mut read, &mut write, 2).unwrap());
+    }
+
+    /// Reads from the first slice, and writes to the vector.
+    struct Duplex(&'static [u8], Vec<u8>);
+
+    impl Read for Duplex {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    impl io::Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.1.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
 
     #[test]
-    fn eof() {
-        let mut read = io::empty();
+    fn exchange() {
+        let mut stream = Duplex(&[3, 4, 5], Vec::new());
+        let mut buf = [0; 2];
+
+        assert!(exchange_exact(&mut stream, &[1], &mut buf).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert!(exchange_exact(&mut stream, &[2], &mut buf).is_err());
+        assert!(!exchange_exact(&mut stream, &[], &mut buf).unwrap());
+        assert_eq!(stream.1, [1, 2]);
+    }
+
+    #[test]
+    fn multi() {
+        let (mut a, mut b) = (&[1, 2, 3][..], &[4][..]);
         let mut buf = [0, 0];
 
-        let ret = read.read_exact_or_eof(&mut buf);
+        assert!(read_exact_or_eof_multi(&mut [&mut a, &mut b], &mut buf).unwrap());
+        assert!(read_exact_or_eof_multi(&mut [&mut a, &mut b], &mut buf).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert!(!read_exact_or_eof_multi(&mut [&mut a, &mut b], &mut buf).unwrap());
+    }
 
-        assert_eq!(ret.unwrap(), false);
+    #[test]
+    fn write_all() {
+        let mut write = Vec::new();
+
+        assert!(write.write_all_or_closed(&[1, 2]).unwrap());
+        assert_eq!(write, [1, 2]);
     }
 
     #[test]
-    fn ok() {
-        let mut read = io::repeat(1);
+    fn write_closed() {
+        let mut write = Closed(1);
+
+        assert!(!write.write_all_or_closed(&[1, 2]).unwrap());
+    }
+
+    #[test]
+    fn write_vectored() {
+        let mut write = Vec::new();
+        let mut bufs = [IoSlice::new(&[1]), IoSlice::new(&[]), IoSlice::new(&[2, 3])];
+
+        assert!(write.write_all_vectored_or_closed(&mut bufs).unwrap());
+        assert_eq!(write, [1, 2, 3]);
+
+        let mut write = Closed(2);
+        let mut bufs = [IoSlice::new(&[1]), IoSlice::new(&[2, 3])];
+
+        assert!(!write.write_all_vectored_or_closed(&mut bufs).unwrap());
+    }
+
+    /// Fails with `Interrupted` a number of times before reading.
+    struct Interrupts<R>(u32, R);
+
+    impl<R: Read> Read for Interrupts<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0 {
+                0 => self.1.read(buf),
+                _ => {
+                    self.0 -= 1;
+                    Err(io::ErrorKind::Interrupted.into())
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn max_reads() {
+        let opts = ReadExactOpts::new().max_reads(Some(2));
+        let read = || ScriptedReader::new().data(&[1]).error(io::ErrorKind::Interrupted).data(&[2, 3]);
+
+        assert!(read().read_exact_or_eof_with(&ReadExactOpts::new().max_reads(Some(3)), &mut [0; 3]).unwrap());
+        let err = read().read_exact_or_eof_with(&opts, &mut [0; 3]).unwrap_err();
+        assert_eq!(err.get_ref().unwrap().downcast_ref::<super::StalledFill>(), Some(&super::StalledFill { reads: 2, filled: 1, len: 3 }));
+        assert!(read().read_exact_or_eof_with(&opts, &mut [0; 1]).unwrap());
+    }
+
+    #[test]
+    fn zero_read_retries() {
+        let opts = ReadExactOpts::new().zero_read_retries(2, Duration::from_millis(1));
+        let read = || ScriptedReader::new().data(&[1]).eof().eof().data(&[2]).eof().data(&[3]);
+        let mut buf = [0; 3];
+
+        assert!(read().read_exact_or_eof_with(&opts, &mut buf).unwrap());
+        assert_eq!(buf, [1, 2, 3]);
+        assert!(read().read_exact_or_eof_with(&ReadExactOpts::new().zero_read_retries(1, Duration::ZERO), &mut buf).is_err());
+        assert!(!ScriptedReader::new().read_exact_or_eof_with(&opts, &mut buf).unwrap());
+    }
+
+    #[test]
+    fn max_interrupts() {
+        let opts = ReadExactOpts::new().max_interrupts(Some(2));
         let mut buf = [0, 0];
 
-        let ret = read.read_exact_or_eof(&mut buf);
+        let mut read = Interrupts(2, io::repeat(1));
+        assert!(read.read_exact_or_eof_with(&opts, &mut buf).unwrap());
 
-        assert_eq!(ret.unwrap(), true);
-        assert_eq!(buf, [1, 1]);
+        let mut read = Interrupts(3, io::repeat(1));
+        let ret = read.read_exact_or_eof_with(&opts, &mut buf);
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::Interrupted);
+    }
+
+    /// Fails with `WouldBlock` after every byte.
+    struct Nonblocking<R>(bool, R);
+
+    impl<R: Read> Read for Nonblocking<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0 = !self.0;
+            match self.0 {
+                true => self.1.read(&mut buf[..1]),
+                false => Err(io::ErrorKind::WouldBlock.into()),
+            }
+        }
     }
 
     #[test]
-    fn unexpected_eof() {
-        let mut read = io::repeat(1).take(1);
+    #[cfg(windows)]
+    fn no_data_eof() {
+        /// Fails with `ERROR_NO_DATA` after its data.
+        struct Closing(&'static [u8]);
+
+        impl Read for Closing {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                match self.0.read(buf)? {
+                    0 => Err(io::Error::from_raw_os_error(232)),
+                    n => Ok(n),
+                }
+            }
+        }
+
+        let opts = ReadExactOpts::new().broken_pipe_eof(true);
         let mut buf = [0, 0];
+        assert!(!Closing(&[]).read_exact_or_eof_with(&opts, &mut buf).unwrap());
+        assert!(Closing(&[1]).read_exact_or_eof_with(&opts, &mut buf).is_err());
+        assert!(Closing(&[]).read_exact_or_eof(&mut buf).is_err());
+    }
 
-        let ret = read.read_exact_or_eof(&mut buf);
+    #[test]
+    fn broken_pipe_eof() {
+        let opts = ReadExactOpts::new().broken_pipe_eof(true);
+        let mut buf = [0, 0];
+
+        let mut read = ScriptedReader::new().error(io::ErrorKind::BrokenPipe);
+        assert!(!read.read_exact_or_eof_with(&opts, &mut buf).unwrap());
+        let mut read = ScriptedReader::new().data(&[1]).error(io::ErrorKind::BrokenPipe);
+        assert!(read.read_exact_or_eof_with(&opts, &mut buf).is_err());
+        let mut read = ScriptedReader::new().error(io::ErrorKind::BrokenPipe);
+        assert!(read.read_exact_or_eof(&mut buf).is_err());
+    }
+
+    #[test]
+    fn connection_reset_eof() {
+        let opts = ReadExactOpts::new().connection_reset_eof(true);
+        let mut buf = [0, 0];
+
+        let mut read = ScriptedReader::new().data(&[1, 2]).error(io::ErrorKind::ConnectionReset);
+        assert!(read.read_exact_or_eof_with(&opts, &mut buf).unwrap());
+        assert!(!read.read_exact_or_eof_with(&opts, &mut buf).unwrap());
+        let mut read = ScriptedReader::new().error(io::ErrorKind::BrokenPipe);
+        assert!(read.read_exact_or_eof_with(&opts, &mut buf).is_err());
+    }
+
+    #[test]
+    fn ignoring() {
+        let kinds = [io::ErrorKind::BrokenPipe, io::ErrorKind::NotConnected];
+        let mut buf = [0, 0];
+
+        let mut read = ScriptedReader::new().error(io::ErrorKind::NotConnected);
+        assert!(!read.read_exact_or_eof_ignoring(&mut buf, &kinds).unwrap());
+        let mut read = ScriptedReader::new().data(&[1]).error(io::ErrorKind::BrokenPipe);
+        assert!(read.read_exact_or_eof_ignoring(&mut buf, &kinds).is_err());
+        let mut read = ScriptedReader::new().error(io::ErrorKind::Other);
+        assert!(read.read_exact_or_eof_ignoring(&mut buf, &kinds).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn metrics() {
+        use std::collections::HashMap;
+        use std::sync::atomic::AtomicU64;
+        use std::sync::{Arc, Mutex};
+        use ::metrics::{Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit};
+
+        /// Sums the counters, and counts histogram samples, by name.
+        #[derive(Default)]
+        struct Sums(Mutex<HashMap<String, Arc<AtomicU64>>>);
+
+        impl Sums {
+            fn get(&self, name: &str) -> Arc<AtomicU64> {
+                self.0.lock().unwrap().entry(name.into()).or_default().clone()
+            }
+        }
+
+        struct Samples(Arc<AtomicU64>);
+
+        impl ::metrics::HistogramFn for Samples {
+            fn record(&self, _: f64) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        impl Recorder for Sums {
+            fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+            fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+            fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+            fn register_counter(&self, key: &Key, _: &Metadata) -> Counter {
+                Counter::from_arc(self.get(key.name()))
+            }
+
+            fn register_gauge(&self, _: &Key, _: &Metadata) -> Gauge {
+                Gauge::noop()
+            }
+
+            fn register_histogram(&self, key: &Key, _: &Metadata) -> Histogram {
+                Histogram::from_arc(Arc::new(Samples(self.get(key.name()))))
+            }
+        }
+
+        let sums = Sums::default();
+        ::metrics::with_local_recorder(&sums, || {
+            let mut read = &[1, 2, 3][..];
+            let mut buf = [0, 0];
 
+            assert!(read.read_exact_or_eof(&mut buf).unwrap());
+            assert!(read.read_exact_or_eof(&mut buf).is_err());
+            assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+        });
+
+        let sum = |name| sums.get(name).load(Ordering::Relaxed);
+        assert_eq!(sum("read_exact.bytes"), 3);
+        assert_eq!(sum("read_exact.truncations"), 1);
+        assert_eq!(sum("read_exact.clean_eof"), 1);
+        assert_eq!(sum("read_exact.fill_duration"), 3);
+    }
+
+    #[test]
+    fn progress() {
+        let mut read = ScriptedReader::new().data(&[1]).error(io::ErrorKind::Interrupted).data(&[2, 3]);
+        let mut buf = [0, 0, 0];
+        let mut calls = Vec::new();
+
+        assert!(read.read_exact_or_eof_with_progress(&mut buf, |filled, len| calls.push((filled, len))).unwrap());
+        assert_eq!(calls, [(1, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn yielding() {
+        let mut read = ScriptedReader::new().data(&[1]).error(io::ErrorKind::Interrupted).data(&[2, 3]).data(&[4, 5]);
+        let mut buf = [0, 0, 0];
+        let mut yields = 0;
+
+        assert!(read.read_exact_or_eof_yielding(&mut buf, || yields += 1).unwrap());
+        assert_eq!(yields, 2);
+        assert!(read.read_exact_or_eof_yielding(&mut buf[..2], || yields += 1).unwrap());
+        assert_eq!(yields, 2);
+    }
+
+    /// Reads one byte at a time, setting the flag after the first.
+    struct CancelAfterOne<'a>(&'a AtomicBool, &'a [u8]);
+
+    impl Read for CancelAfterOne<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.store(true, Ordering::Release);
+            let n = self.1.take(1).read(buf)?;
+            self.1 = &self.1[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn cancellable() {
+        let cancel = AtomicBool::new(false);
+        let mut buf = [0, 0];
+
+        assert_eq!((&[1, 2][..]).read_exact_or_eof_cancellable(&mut buf, &cancel).unwrap(), CancelStatus::Done(true));
+        let mut read = CancelAfterOne(&cancel, &[1, 2]);
+        assert_eq!(read.read_exact_or_eof_cancellable(&mut buf, &cancel).unwrap(), CancelStatus::Cancelled { filled: 1 });
+        assert_eq!(buf[0], 1);
+    }
+
+    /// Collects the bytes it is updated with.
+    #[cfg(feature = "digest")]
+    struct Collect(Vec<u8>);
+
+    #[cfg(feature = "digest")]
+    impl ::digest::Update for Collect {
+        fn update(&mut self, data: &[u8]) {
+            self.0.extend_from_slice(data);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "digest")]
+    fn hashing() {
+        let mut read = ScriptedReader::new().data(&[1]).data(&[2, 3]);
+        let mut hasher = Collect(Vec::new());
+        let mut buf = [0, 0];
+
+        assert!(read.read_exact_or_eof_hashing(&mut buf, &mut hasher).unwrap());
+        assert!(read.read_exact_or_eof_hashing(&mut buf, &mut hasher).is_err());
+        assert_eq!(hasher.0, [1, 2, 3]);
+    }
+
+    #[test]
+    fn into() {
+        let mut read = &[1, 2, 3, 4, 5][..];
+
+        let (ret, buf) = read.read_exact_or_eof_into([0; 2]);
+        assert!(ret.unwrap());
+        assert_eq!(buf, [1, 2]);
+        let (ret, buf) = read.read_exact_or_eof_into(vec![0; 2].into_boxed_slice());
+        assert!(ret.unwrap());
+        assert_eq!(&buf[..], [3, 4]);
+        let (ret, buf) = read.read_exact_or_eof_into(vec![0; 2]);
         assert!(ret.is_err());
+        assert_eq!(buf[0], 5);
+    }
+
+    #[test]
+    fn slice() {
+        let mut read = &[1, 2, 3, 4][..];
+        let mut buf = [0; 4];
+
+        assert_eq!(read.read_exact_or_eof_slice(&mut buf, 3).unwrap(), Some(&mut [1, 2, 3][..]));
+        assert!(read.read_exact_or_eof_slice(&mut buf, 5).is_err());
+        assert!(read.read_exact_or_eof_slice(&mut buf, 2).is_err());
+        assert_eq!(read.read_exact_or_eof_slice(&mut buf, 2).unwrap(), None);
+    }
+
+    #[test]
+    fn append() {
+        let mut read = ScriptedReader::new().data(&[1]).error(io::ErrorKind::Interrupted).data(&[2, 3, 4]);
+        let mut vec = vec![0];
+
+        assert!(read.read_exact_append(&mut vec, 2).unwrap());
+        assert_eq!(vec, [0, 1, 2]);
+        assert!(read.read_exact_append(&mut vec, 3).is_err());
+        assert_eq!(vec, [0, 1, 2]);
+        assert!(!read.read_exact_append(&mut vec, 3).unwrap());
+        assert!(read.read_exact_append(&mut vec, 0).unwrap());
+    }
+
+    #[test]
+    fn tee() {
+        let mut read = ScriptedReader::new().data(&[1]).data(&[2, 3]);
+        let mut copy = Vec::new();
+        let mut buf = [0, 0];
+
+        assert!(read.read_exact_or_eof_tee(&mut buf, &mut copy).unwrap());
+        assert!(read.read_exact_or_eof_tee(&mut buf, &mut copy).is_err());
+        assert_eq!(copy, [1, 2, 3]);
+
+        let mut full = [0; 1];
+        let ret = (&[1, 2][..]).read_exact_or_eof_tee(&mut buf, &mut &mut full[..]);
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    #[cfg(feature = "zeroize")]
+    fn secret() {
+        let mut buf = [0, 0];
+
+        assert!((&[1][..]).read_exact_secret_or_eof(&mut buf).is_err());
+        assert_eq!(buf, [0, 0]);
+        assert!((&[1, 2][..]).read_exact_secret_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(*(&[1, 2][..]).read_exact_secret_to_vec(2).unwrap().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn try_read() {
+        let mut read = Nonblocking(false, &[1, 2, 3][..]);
+        let mut buf = [0, 0];
+
+        assert_eq!(read.try_read_exact_or_eof(&mut buf).unwrap(), TryReadStatus::Pending { filled: 1 });
+        assert_eq!(read.try_read_exact_or_eof_resume(&mut buf, 1).unwrap(), TryReadStatus::Done(true));
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(read.try_read_exact_or_eof(&mut buf).unwrap(), TryReadStatus::Pending { filled: 0 });
+        assert_eq!(read.try_read_exact_or_eof(&mut buf).unwrap(), TryReadStatus::Pending { filled: 1 });
+        assert!(read.try_read_exact_or_eof_resume(&mut buf, 1).is_err());
+    }
+}
+
+#[cfg(all(test, feature = "no-std-io", not(feature = "std")))]
+mod tests {
+    use super::{io, ReadExactExt};
+
+    #[test]
+    fn no_std() {
+        let mut read = io::Cursor::new([1, 1, 2]);
+        let mut buf = [0, 0];
+
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 1]);
+        assert!(read.read_exact_or_eof(&mut buf).is_err());
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
     }
 }
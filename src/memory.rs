@@ -0,0 +1,241 @@
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+use crate::{io, unexpected_eof};
+#[cfg(feature = "std")]
+use crate::ReadExactExt;
+
+/// An extension trait for exact reads out of in-memory sources.
+///
+/// `ReadExactExt` applies to these sources too, but goes through the generic
+/// read loop. These methods instead check the remaining length once and
+/// copy the record in a single `copy_from_slice`, which matters for small
+/// records.
+pub trait MemReadExactExt {
+    /// Copies exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// This behaves exactly like `ReadExactExt::read_exact_or_eof`: if fewer
+    /// bytes remain, they are consumed and an `UnexpectedEof` error is
+    /// returned.
+    fn copy_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool>;
+}
+
+impl MemReadExactExt for &[u8] {
+    #[inline]
+    fn copy_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        match self.split_at_checked(buf.len()) {
+            Some((record, rest)) => {
+                buf.copy_from_slice(record);
+                *self = rest;
+                Ok(true)
+            },
+            None => take_partial(self, buf),
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>> MemReadExactExt for io::Cursor<T> {
+    #[inline]
+    fn copy_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let data = self.get_ref().as_ref();
+        let pos = usize::try_from(self.position()).unwrap_or(usize::MAX).min(data.len());
+        let mut remaining = &data[pos..];
+        let ret = remaining.copy_exact_or_eof(buf);
+        let consumed = data.len() - pos - remaining.len();
+        if consumed > 0 {
+            self.set_position((pos + consumed) as u64);
+        }
+        ret
+    }
+}
+
+/// An extension trait for exact reads that borrow from the source when they
+/// can.
+///
+/// `read_exact_cow` returns a slice of the source's own memory when the
+/// whole record is available there, and otherwise reads it into an owned
+/// buffer, so that a parser can stay zero-copy for in-memory and buffered
+/// sources behind a single API. It is implemented for slices, cursors and
+/// `ExactBufReader`, and `CowOwned` implements it for any other reader by
+/// always returning an owned buffer.
+///
+/// It is not implemented for `BufRead` types even when they have enough
+/// data buffered, as `BufRead` only lends its buffer until the record is
+/// consumed, after which it may be refilled. `ExactBufReader` keeps consumed
+/// records in its buffer until the next read, so it takes the place of a
+/// `BufReader` here.
+#[cfg(feature = "std")]
+pub trait CowReadExactExt {
+    /// Reads exactly `n` bytes, or returns `None` if no data was read.
+    ///
+    /// EOF is handled as in `ReadExactExt::read_exact_to_vec`. The bytes are
+    /// borrowed from the source if they are all in memory.
+    fn read_exact_cow(&mut self, n: usize) -> io::Result<Option<Cow<'_, [u8]>>>;
+}
+
+#[cfg(feature = "std")]
+impl CowReadExactExt for &[u8] {
+    fn read_exact_cow(&mut self, n: usize) -> io::Result<Option<Cow<'_, [u8]>>> {
+        match self.split_at_checked(n) {
+            Some((record, rest)) => {
+                *self = rest;
+                Ok(Some(Cow::Borrowed(record)))
+            },
+            None if self.is_empty() => Ok(None),
+            None => {
+                *self = &[];
+                Err(unexpected_eof())
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: AsRef<[u8]>> CowReadExactExt for io::Cursor<T> {
+    fn read_exact_cow(&mut self, n: usize) -> io::Result<Option<Cow<'_, [u8]>>> {
+        let len = self.get_ref().as_ref().len();
+        let pos = usize::try_from(self.position()).unwrap_or(usize::MAX).min(len);
+        match len - pos {
+            remaining if remaining >= n => {
+                self.set_position((pos + n) as u64);
+                Ok(Some(Cow::Borrowed(&self.get_ref().as_ref()[pos..pos + n])))
+            },
+            0 => Ok(None),
+            _ => {
+                self.set_position(len as u64);
+                Err(unexpected_eof())
+            },
+        }
+    }
+}
+
+/// A reader whose `read_exact_cow` always reads into an owned buffer.
+///
+/// This lets code that is generic over `CowReadExactExt` accept any reader,
+/// such as a file or socket, that has no memory of its own to borrow from.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct CowOwned<R> {
+    reader: R,
+}
+
+#[cfg(feature = "std")]
+impl<R> CowOwned<R> {
+    /// Wraps a reader.
+    pub fn new(reader: R) -> Self {
+        CowOwned {
+            reader,
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> io::Read for CowOwned<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> CowReadExactExt for CowOwned<R> {
+    fn read_exact_cow(&mut self, n: usize) -> io::Result<Option<Cow<'_, [u8]>>> {
+        self.reader.read_exact_to_vec(n).map(|buf| buf.map(Cow::Owned))
+    }
+}
+
+/// Handles a slice too short to fill `buf`.
+#[cold]
+fn take_partial(data: &mut &[u8], buf: &mut [u8]) -> io::Result<bool> {
+    match data.len() {
+        0 => Ok(false),
+        n => {
+            buf[..n].copy_from_slice(data);
+            *data = &[];
+            Err(unexpected_eof())
+        },
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::borrow::Cow;
+    use std::io::Cursor;
+    use crate::ReadExactExt;
+    use crate::testing::ScriptedReader;
+    use super::{CowOwned, CowReadExactExt, MemReadExactExt};
+
+    #[test]
+    fn slice() {
+        let mut read = &[1, 2, 3, 4, 5][..];
+        let mut buf = [0; 2];
+
+        assert!(read.copy_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(read.copy_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert!(read.copy_exact_or_eof(&mut buf).is_err());
+        assert!(!read.copy_exact_or_eof(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn cursor() {
+        let mut read = Cursor::new(vec![1, 2, 3]);
+        let mut buf = [0; 2];
+
+        assert!(read.copy_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(read.copy_exact_or_eof(&mut buf).is_err());
+        assert_eq!(read.position(), 3);
+        assert!(!read.copy_exact_or_eof(&mut buf).unwrap());
+        read.set_position(10);
+        assert!(!read.copy_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(read.position(), 10);
+    }
+
+    #[test]
+    fn cow() {
+        let mut read = &[1, 2, 3][..];
+
+        assert!(matches!(read.read_exact_cow(2).unwrap(), Some(Cow::Borrowed([1, 2]))));
+        assert!(read.read_exact_cow(2).is_err());
+        assert!(read.read_exact_cow(2).unwrap().is_none());
+
+        let mut read = Cursor::new(vec![1, 2, 3]);
+        assert!(matches!(read.read_exact_cow(2).unwrap(), Some(Cow::Borrowed([1, 2]))));
+        assert!(read.read_exact_cow(2).is_err());
+        assert_eq!(read.position(), 3);
+        assert!(read.read_exact_cow(2).unwrap().is_none());
+
+        let mut read = CowOwned::new(ScriptedReader::new().data(&[1]).data(&[2, 3]));
+        assert!(matches!(read.read_exact_cow(2).unwrap(), Some(Cow::Owned(ref buf)) if buf == &[1, 2]));
+        assert!(read.read_exact_cow(2).is_err());
+        assert!(read.read_exact_cow(2).unwrap().is_none());
+    }
+
+    #[test]
+    fn matches_generic() {
+        for len in 0..6 {
+            let (mut fast, mut generic) = (&[1, 2, 3, 4, 5][..], &[1, 2, 3, 4, 5][..]);
+            let (mut a, mut b) = (vec![0; len], vec![0; len]);
+            for _ in 0..4 {
+                let ret = (fast.copy_exact_or_eof(&mut a), generic.read_exact_or_eof(&mut b));
+                assert_eq!(ret.0.as_ref().ok(), ret.1.as_ref().ok());
+                assert_eq!((a.as_slice(), fast), (b.as_slice(), generic));
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::fs::File;
+use std::io;
+use ::memmap2::Mmap;
+use crate::{unexpected_eof, Records};
+
+/// A reader of fixed-size records that maps its file into memory.
+///
+/// Records are borrowed directly from the mapping without being copied.
+/// Files that cannot be mapped, such as pipes, are read through `Records`
+/// instead. Either way, iteration ends at EOF on a record boundary, and a
+/// truncated final record is an `UnexpectedEof` error.
+#[derive(Debug)]
+pub struct MmapRecords {
+    source: Source,
+    record_len: usize,
+}
+
+#[derive(Debug)]
+enum Source {
+    Mapped {
+        map: Mmap,
+        offset: usize,
+    },
+    Read(Records<File>),
+}
+
+impl MmapRecords {
+    /// Maps `file` and reads `record_len` byte records from its start.
+    ///
+    /// Unmappable files are instead read from their current position.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this or any other
+    /// process, while it is mapped, as with `memmap2::Mmap::map`.
+    pub unsafe fn map(file: File, record_len: usize) -> Self {
+        // SAFETY: upheld by the caller
+        let source = match unsafe { Mmap::map(&file) } {
+            Ok(map) => Source::Mapped {
+                map,
+                offset: 0,
+            },
+            Err(..) => Source::Read(Records::new(file, record_len)),
+        };
+
+        MmapRecords {
+            source,
+            record_len,
+        }
+    }
+
+    /// Returns the length of each record.
+    pub fn record_len(&self) -> usize {
+        self.record_len
+    }
+
+    /// Returns whether the file was mapped, rather than falling back to
+    /// reads.
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.source, Source::Mapped { .. })
+    }
+
+    /// Reads the next record, or returns `None` at EOF on a record boundary.
+    pub fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        match self.source {
+            Source::Mapped { ref map, ref mut offset } => {
+                let rest = &map[*offset..];
+                if rest.is_empty() {
+                    return Ok(None)
+                }
+                match rest.get(..self.record_len) {
+                    Some(record) => {
+                        *offset += self.record_len;
+                        Ok(Some(record))
+                    },
+                    None => {
+                        *offset = map.len();
+                        Err(unexpected_eof())
+                    },
+                }
+            },
+            Source::Read(ref mut records) => records.next_record(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::path::PathBuf;
+    use super::MmapRecords;
+
+    fn temp(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("read_exact-mmap-{}-{}", name, std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn mapped() {
+        let path = temp("mapped", &[1, 2, 3, 4, 5]);
+        // SAFETY: the file is private to this test
+        let mut records = unsafe { MmapRecords::map(File::open(&path).unwrap(), 2) };
+
+        assert!(records.is_mapped());
+        assert_eq!(records.next_record().unwrap(), Some(&[1, 2][..]));
+        assert_eq!(records.next_record().unwrap(), Some(&[3, 4][..]));
+        assert!(records.next_record().is_err());
+        assert_eq!(records.next_record().unwrap(), None);
+        drop(records);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn empty() {
+        let path = temp("empty", &[]);
+        // SAFETY: the file is private to this test
+        let mut records = unsafe { MmapRecords::map(File::open(&path).unwrap(), 2) };
+
+        assert_eq!(records.next_record().unwrap(), None);
+        drop(records);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn fallback() {
+        use std::io::Write;
+        use std::os::fd::OwnedFd;
+
+        let (read, mut write) = std::io::pipe().unwrap();
+        write.write_all(&[1, 2, 3]).unwrap();
+        drop(write);
+        // SAFETY: pipes cannot be mapped
+        let mut records = unsafe { MmapRecords::map(File::from(OwnedFd::from(read)), 2) };
+
+        assert!(!records.is_mapped());
+        assert_eq!(records.next_record().unwrap(), Some(&[1, 2][..]));
+        assert!(records.next_record().is_err());
+    }
+}
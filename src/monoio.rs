@@ -0,0 +1,88 @@
+//! Support for `monoio::io::AsyncReadRent` types.
+//!
+//! Like `tokio_uring`, these methods consume an owned buffer and return it
+//! alongside the result, filling the whole of its capacity and advancing its
+//! initialized length over everything that was read.
+
+use std::io;
+use ::monoio::buf::{IoBuf, IoBufMut};
+use ::monoio::io::AsyncReadRent;
+use ::monoio::BufResult;
+use crate::ReadExactStatus;
+
+/// An extension trait that applies to all `monoio::io::AsyncReadRent` types.
+#[allow(async_fn_in_trait)]
+pub trait AsyncReadExactExt {
+    /// Reads exactly the number of bytes to fill the capacity of `buf`, or
+    /// zero.
+    ///
+    /// Resolves to `true` upon successful read, or `false` if no data was
+    /// read, in the same manner as `ReadExactExt::read_exact_or_eof`. The
+    /// buffer is returned in either case.
+    async fn read_exact_or_eof<B: IoBuf + IoBufMut>(&mut self, buf: B) -> BufResult<bool, B>;
+}
+
+impl<T: AsyncReadRent + ?Sized> AsyncReadExactExt for T {
+    async fn read_exact_or_eof<B: IoBuf + IoBufMut>(&mut self, mut buf: B) -> BufResult<bool, B> {
+        let len = IoBufMut::bytes_total(&mut buf);
+        let mut filled = 0;
+
+        while filled < len {
+            let (res, slice) = self.read(buf.slice_mut(filled..)).await;
+            buf = slice.into_inner();
+            match res {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return (Err(e), buf),
+            }
+        }
+
+        (ReadExactStatus::new(filled, len).into_result(), buf)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::io::Write;
+    use std::os::unix::net;
+    use ::monoio::net::UnixStream;
+    use ::monoio::{LegacyDriver, RuntimeBuilder};
+    use super::AsyncReadExactExt;
+
+    /// Returns a stream that reads `data` and then EOF.
+    fn stream(data: &[u8]) -> net::UnixStream {
+        let (read, mut write) = net::UnixStream::pair().unwrap();
+        write.write_all(data).unwrap();
+        read
+    }
+
+    fn block_on<F: std::future::Future>(f: F) -> F::Output {
+        RuntimeBuilder::<LegacyDriver>::new().build().unwrap().block_on(f)
+    }
+
+    #[test]
+    fn eof() {
+        let read = stream(&[]);
+        block_on(async {
+            let mut read = UnixStream::from_std(read).unwrap();
+            let (res, buf) = read.read_exact_or_eof(Vec::with_capacity(2)).await;
+            assert!(!res.unwrap());
+            assert!(buf.is_empty());
+        });
+    }
+
+    #[test]
+    fn full() {
+        let read = stream(&[1, 2, 3]);
+        block_on(async {
+            let mut read = UnixStream::from_std(read).unwrap();
+            let (res, buf) = read.read_exact_or_eof(Vec::with_capacity(2)).await;
+            assert!(res.unwrap());
+            assert_eq!(buf, [1, 2]);
+            let (res, buf) = read.read_exact_or_eof(Vec::with_capacity(2)).await;
+            assert!(res.is_err());
+            assert_eq!(buf, [3]);
+        });
+    }
+}
@@ -0,0 +1,143 @@
+//! Support for driving `nom` streaming parsers over readers.
+
+use std::io;
+use ::nom::{Err, IResult, Needed};
+use crate::{unexpected_eof, ReadExactExt};
+
+/// A reader that feeds a `nom` streaming parser exactly the bytes it asks
+/// for.
+///
+/// Each time the parser returns `Incomplete(Needed::Size(n))`, exactly `n`
+/// more bytes are read and appended to the buffered input before the parser
+/// is retried. Bytes are read one at a time when the parser does not know how
+/// many it needs, so unbuffered readers should be wrapped in a `BufReader`.
+#[derive(Debug)]
+pub struct StreamParser<R> {
+    reader: R,
+    buf: Vec<u8>,
+}
+
+impl<R> StreamParser<R> {
+    /// Creates a parser over `reader`.
+    pub fn new(reader: R) -> Self {
+        StreamParser {
+            reader,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Returns the input that has been read but not yet parsed.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this parser, returning the underlying reader.
+    ///
+    /// Any buffered input is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::Read> StreamParser<R> {
+    /// Parses the next item, or returns `None` at EOF between items.
+    ///
+    /// EOF partway through an item is an error, and parse errors fail with
+    /// `InvalidData`. The input consumed by the parser is removed from the
+    /// buffer once it succeeds.
+    pub fn parse_or_eof<O, P>(&mut self, mut parser: P) -> io::Result<Option<O>> where
+        P: for<'a> FnMut(&'a [u8]) -> IResult<&'a [u8], O>,
+    {
+        loop {
+            let needed = match parser(&self.buf) {
+                Ok((rest, item)) => {
+                    let consumed = self.buf.len() - rest.len();
+                    self.buf.drain(..consumed);
+                    return Ok(Some(item))
+                },
+                Err(Err::Incomplete(Needed::Size(n))) => n.get(),
+                Err(Err::Incomplete(Needed::Unknown)) => 1,
+                Err(Err::Error(e) | Err::Failure(e)) =>
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("parser failed: {}", e.code.description()))),
+            };
+
+            let start = self.buf.len();
+            self.buf.resize(start + needed, 0);
+            match self.reader.read_exact_or_eof(&mut self.buf[start..]) {
+                Ok(true) => {},
+                Ok(false) => {
+                    self.buf.truncate(start);
+                    return match start {
+                        0 => Ok(None),
+                        _ => Err(unexpected_eof()),
+                    }
+                },
+                Err(e) => {
+                    self.buf.truncate(start);
+                    return Err(e)
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use ::nom::IResult;
+    use ::nom::bytes::streaming::{tag, take};
+    use ::nom::number::streaming::be_u16;
+    use ::nom::Parser;
+    use super::StreamParser;
+
+    /// Parses a `u16` length followed by that many bytes.
+    fn frame(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
+        let (input, len) = be_u16(input)?;
+        let (input, payload) = take(len)(input)?;
+        Ok((input, payload.to_vec()))
+    }
+
+    #[test]
+    fn items() {
+        let mut parser = StreamParser::new(&[0, 2, 1, 2, 0, 0][..]);
+
+        assert_eq!(parser.parse_or_eof(frame).unwrap(), Some(vec![1, 2]));
+        assert_eq!(parser.parse_or_eof(frame).unwrap(), Some(vec![]));
+        assert_eq!(parser.parse_or_eof(frame).unwrap(), None);
+    }
+
+    #[test]
+    fn exact_reads() {
+        let mut reader = &[0, 2, 1, 2, 3][..];
+        let mut parser = StreamParser::new(&mut reader);
+
+        assert_eq!(parser.parse_or_eof(frame).unwrap(), Some(vec![1, 2]));
+        assert!(parser.buffered().is_empty());
+        assert_eq!(parser.into_inner(), &[3]);
+    }
+
+    #[test]
+    fn truncated() {
+        let mut parser = StreamParser::new(&[0, 2, 1][..]);
+
+        assert_eq!(parser.parse_or_eof(frame).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut parser = StreamParser::new(&b"nope"[..]);
+
+        let ret = parser.parse_or_eof(|input| tag(&b"yes"[..]).parse(input).map(|(rest, _)| (rest, ())));
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
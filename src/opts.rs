@@ -0,0 +1,209 @@
+use core::time::Duration;
+use crate::io;
+use crate::source::FillControl;
+
+/// Options that control how an exact read retries and reports errors.
+///
+/// The default options match the behaviour of
+/// `ReadExactExt::read_exact_or_eof`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ReadExactOpts {
+    max_interrupts: Option<u32>,
+    max_reads: Option<u32>,
+    zero_read_retries: u32,
+    zero_read_backoff: Duration,
+    broken_pipe_eof: bool,
+    connection_reset_eof: bool,
+}
+
+impl ReadExactOpts {
+    /// Creates the default options.
+    pub const fn new() -> Self {
+        ReadExactOpts {
+            max_interrupts: None,
+            max_reads: None,
+            zero_read_retries: 0,
+            zero_read_backoff: Duration::ZERO,
+            broken_pipe_eof: false,
+            connection_reset_eof: false,
+        }
+    }
+
+    /// Limits how many `Interrupted` errors are retried during one read.
+    ///
+    /// Once the limit is exceeded, the `Interrupted` error is returned to the
+    /// caller. `None`, the default, retries indefinitely.
+    pub const fn max_interrupts(mut self, max: Option<u32>) -> Self {
+        self.max_interrupts = max;
+        self
+    }
+
+    /// Limits how many `read` calls one read may issue, including those that
+    /// fail with `Interrupted`.
+    ///
+    /// This protects against readers that make almost no progress, such as
+    /// one returning a byte at a time forever. Once the limit is reached
+    /// before the buffer is filled, the read fails with a `StalledFill`
+    /// error. The first read is always issued. `None`, the default, issues
+    /// reads indefinitely.
+    pub const fn max_reads(mut self, max: Option<u32>) -> Self {
+        self.max_reads = max;
+        self
+    }
+
+    /// Retries a read that returns `Ok(0)` up to `retries` times in a row,
+    /// waiting `backoff` before each retry, before treating it as EOF.
+    ///
+    /// Some sources, such as certain PTYs, device files and FUSE filesystems,
+    /// occasionally return zero bytes even though more data will follow. Each
+    /// retry counts towards `max_reads`, and any progress resets the count.
+    /// A genuine EOF is only reported once the retries are exhausted, and
+    /// without the `std` feature there is no way to wait, so `backoff` is
+    /// ignored. Asynchronous reads do not retry. The default is no retries.
+    pub const fn zero_read_retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.zero_read_retries = retries;
+        self.zero_read_backoff = backoff;
+        self
+    }
+
+    /// Treats a `BrokenPipe` error before any data is read as EOF.
+    ///
+    /// Windows reports a closed pipe with `ERROR_BROKEN_PIPE` rather than by
+    /// returning EOF, so enabling this makes pipes behave as they do on Unix.
+    /// A broken pipe partway through the buffer is still an error. This also
+    /// covers `ERROR_NO_DATA`, for a pipe that is being closed, since an
+    /// anonymous pipe often reports the end of a child process's output with
+    /// it.
+    pub const fn broken_pipe_eof(mut self, eof: bool) -> Self {
+        self.broken_pipe_eof = eof;
+        self
+    }
+
+    /// Treats a `ConnectionReset` error before any data is read as EOF.
+    ///
+    /// Many peers reset connections instead of shutting them down cleanly,
+    /// so this allows a reset between records to end a stream. A reset
+    /// partway through the buffer is still an error.
+    pub const fn connection_reset_eof(mut self, eof: bool) -> Self {
+        self.connection_reset_eof = eof;
+        self
+    }
+
+    /// Returns whether an error before any data is read counts as EOF.
+    pub(crate) fn is_eof(&self, e: &io::Error) -> bool {
+        #[cfg(all(feature = "std", windows))]
+        if crate::adapters::raw_os_error(e) == Some(ERROR_NO_DATA) {
+            return self.broken_pipe_eof
+        }
+
+        match e.kind() {
+            io::ErrorKind::BrokenPipe => self.broken_pipe_eof,
+            io::ErrorKind::ConnectionReset => self.connection_reset_eof,
+            _ => false,
+        }
+    }
+
+    /// Checks whether another read may be issued after `reads` reads.
+    pub(crate) fn check_reads(&self, reads: u32, filled: usize, len: usize) -> io::Result<()> {
+        match self.max_reads {
+            Some(max) if reads >= max => Err(stalled_fill(StalledFill { reads, filled, len })),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a read that returned `Ok(0)`, waiting and returning `true` if it
+    /// is to be retried.
+    pub(crate) fn retry_zero_read(&self, zero_reads: &mut u32) -> bool {
+        if *zero_reads >= self.zero_read_retries {
+            return false
+        }
+
+        *zero_reads += 1;
+        #[cfg(feature = "std")]
+        if !self.zero_read_backoff.is_zero() {
+            std::thread::sleep(self.zero_read_backoff);
+        }
+        true
+    }
+
+    /// Counts an `Interrupted` error, returning whether to retry.
+    pub(crate) fn retry_interrupt(&self, interrupts: &mut u32) -> bool {
+        *interrupts += 1;
+        self.max_interrupts.is_none_or(|max| *interrupts <= max)
+    }
+}
+
+/// The fill policy of `ReadExactOpts`, additionally treating `eof_kinds`
+/// before any data as EOF.
+pub(crate) struct OptsControl<'a> {
+    opts: &'a ReadExactOpts,
+    eof_kinds: &'a [io::ErrorKind],
+}
+
+impl<'a> OptsControl<'a> {
+    pub(crate) fn new(opts: &'a ReadExactOpts, eof_kinds: &'a [io::ErrorKind]) -> Self {
+        OptsControl {
+            opts,
+            eof_kinds,
+        }
+    }
+}
+
+impl<B: ?Sized> FillControl<B, io::Error> for OptsControl<'_> {
+    #[inline]
+    fn before_read(&mut self, reads: u32, filled: usize, len: usize) -> io::Result<bool> {
+        if reads > 0 {
+            self.opts.check_reads(reads, filled, len)?;
+        }
+        Ok(true)
+    }
+
+    fn retry_interrupt(&mut self, interrupts: &mut u32) -> bool {
+        self.opts.retry_interrupt(interrupts)
+    }
+
+    fn retry_zero_read(&mut self, zero_reads: &mut u32) -> bool {
+        self.opts.retry_zero_read(zero_reads)
+    }
+
+    fn end_on_error(&mut self, e: &io::Error, filled: usize) -> bool {
+        filled == 0 && (self.opts.is_eof(e) || self.eof_kinds.contains(&e.kind()))
+    }
+}
+
+/// The Windows error for a pipe that is being closed.
+#[cfg(all(feature = "std", windows))]
+const ERROR_NO_DATA: i32 = 232;
+
+/// Diagnostics for a read that reached `ReadExactOpts::max_reads`.
+///
+/// With the `std` feature, this is the inner error of the `Other` error
+/// returned by the read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StalledFill {
+    /// The number of reads issued.
+    pub reads: u32,
+    /// The number of bytes read into the start of the buffer.
+    pub filled: usize,
+    /// The length of the buffer.
+    pub len: usize,
+}
+
+impl core::fmt::Display for StalledFill {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "fill stalled after {} reads with {} of {} bytes", self.reads, self.filled, self.len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StalledFill {}
+
+#[cfg(feature = "std")]
+fn stalled_fill(stalled: StalledFill) -> io::Error {
+    io::Error::other(stalled)
+}
+
+#[cfg(not(feature = "std"))]
+fn stalled_fill(_: StalledFill) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "fill stalled")
+}
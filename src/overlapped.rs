@@ -0,0 +1,163 @@
+use std::io;
+use std::os::windows::io::AsRawHandle;
+use std::time::{Duration, Instant};
+use std::{mem, ptr};
+use ::windows_sys::Win32::Foundation::{
+    CloseHandle, GetLastError, ERROR_BROKEN_PIPE, ERROR_HANDLE_EOF, ERROR_IO_PENDING, ERROR_OPERATION_ABORTED, HANDLE,
+    WAIT_FAILED, WAIT_OBJECT_0,
+};
+use ::windows_sys::Win32::Storage::FileSystem::ReadFile;
+use ::windows_sys::Win32::System::IO::{CancelIoEx, GetOverlappedResult, OVERLAPPED};
+use ::windows_sys::Win32::System::Threading::{CreateEventW, WaitForSingleObject, INFINITE};
+use crate::{ReadExactStatus, TimeoutStatus};
+
+/// An extension trait for Windows handles opened for overlapped I/O.
+pub trait ReadExactOverlappedExt {
+    /// Reads exactly the number of bytes to fill `buf` before `deadline`, or
+    /// zero.
+    ///
+    /// The handle must have been opened with `FILE_FLAG_OVERLAPPED`, such as
+    /// a named pipe or serial port. Each read is issued as an overlapped
+    /// `ReadFile` and cancelled if the deadline passes first, in which case
+    /// this returns `TimeoutStatus::TimedOut` with the number of bytes read
+    /// so far. Overlapped handles have no file position, so every read
+    /// starts at offset zero of seekable files.
+    fn read_exact_or_eof_deadline(&mut self, buf: &mut [u8], deadline: Instant) -> io::Result<TimeoutStatus>;
+}
+
+impl<T: AsRawHandle + ?Sized> ReadExactOverlappedExt for T {
+    fn read_exact_or_eof_deadline(&mut self, buf: &mut [u8], deadline: Instant) -> io::Result<TimeoutStatus> {
+        let handle = self.as_raw_handle() as HANDLE;
+        let event = Event::new()?;
+        let len = buf.len();
+        let mut filled = 0;
+
+        while filled < len {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(TimeoutStatus::TimedOut { filled })
+            }
+
+            match read_overlapped(handle, &event, &mut buf[filled..], remaining)? {
+                (n, true) => return Ok(TimeoutStatus::TimedOut { filled: filled + n }),
+                (0, false) => break,
+                (n, false) => filled += n,
+            }
+        }
+
+        ReadExactStatus::new(filled, len).into_result().map(TimeoutStatus::Done)
+    }
+}
+
+/// A manual-reset event that is closed on drop.
+struct Event(HANDLE);
+
+impl Event {
+    fn new() -> io::Result<Self> {
+        // SAFETY: all of the pointer arguments are optional
+        match unsafe { CreateEventW(ptr::null(), 1, 0, ptr::null()) } {
+            event if event.is_null() => Err(io::Error::last_os_error()),
+            event => Ok(Event(event)),
+        }
+    }
+}
+
+impl Drop for Event {
+    fn drop(&mut self) {
+        // SAFETY: the event is owned by this value
+        unsafe { CloseHandle(self.0) };
+    }
+}
+
+/// Issues a single overlapped read, waiting up to `timeout` for it to
+/// complete, and returns the number of bytes read and whether it timed out.
+fn read_overlapped(handle: HANDLE, event: &Event, buf: &mut [u8], timeout: Duration) -> io::Result<(usize, bool)> {
+    let len = buf.len().min(u32::MAX as usize) as u32;
+    // SAFETY: `OVERLAPPED` is plain data, for which zero is a valid value
+    let mut overlapped: OVERLAPPED = unsafe { mem::zeroed() };
+    overlapped.hEvent = event.0;
+
+    // SAFETY: `buf` and `overlapped` outlive the read, which is always waited
+    // on below before returning once it is pending
+    if unsafe { ReadFile(handle, buf.as_mut_ptr(), len, ptr::null_mut(), &mut overlapped) } == 0 {
+        // SAFETY: always safe to call
+        match unsafe { GetLastError() } {
+            ERROR_IO_PENDING => {},
+            ERROR_BROKEN_PIPE | ERROR_HANDLE_EOF => return Ok((0, false)),
+            code => return Err(io::Error::from_raw_os_error(code as i32)),
+        }
+    }
+
+    // round up, so that the final wait does not return just before the
+    // deadline and spin
+    let millis = timeout.as_nanos().div_ceil(1_000_000).min(u128::from(INFINITE - 1)) as u32;
+    // SAFETY: the event is valid for the duration of the call
+    let wait = unsafe { WaitForSingleObject(event.0, millis) };
+    let wait_error = (wait == WAIT_FAILED).then(io::Error::last_os_error);
+    if wait != WAIT_OBJECT_0 {
+        // SAFETY: `overlapped` identifies the read issued above
+        unsafe { CancelIoEx(handle, &overlapped) };
+    }
+
+    let mut n = 0;
+    // SAFETY: this waits for the read to finish, or for its cancellation to
+    // take effect, after which the buffer is no longer in use
+    if unsafe { GetOverlappedResult(handle, &overlapped, &mut n, 1) } == 0 {
+        // SAFETY: always safe to call
+        match unsafe { GetLastError() } {
+            ERROR_OPERATION_ABORTED => {},
+            ERROR_BROKEN_PIPE | ERROR_HANDLE_EOF => return Ok((0, false)),
+            code => return Err(io::Error::from_raw_os_error(code as i32)),
+        }
+    }
+
+    match wait_error {
+        Some(e) => Err(e),
+        None => Ok((n as usize, wait != WAIT_OBJECT_0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+    use std::fs::{File, OpenOptions};
+    use std::io::Write;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::FromRawHandle;
+    use std::ptr;
+    use std::time::{Duration, Instant};
+    use ::windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use ::windows_sys::Win32::Storage::FileSystem::{FILE_FLAG_OVERLAPPED, PIPE_ACCESS_INBOUND};
+    use ::windows_sys::Win32::System::Pipes::{CreateNamedPipeW, PIPE_TYPE_BYTE};
+    use crate::TimeoutStatus;
+    use super::ReadExactOverlappedExt;
+
+    /// Returns the overlapped read end and the write end of a named pipe.
+    fn pipe() -> (File, File) {
+        let name = format!(r"\\.\pipe\read_exact-{}", std::process::id());
+        let wide: Vec<u16> = OsStr::new(&name).encode_wide().chain(Some(0)).collect();
+        // SAFETY: `wide` is a NUL-terminated string, and the other pointer
+        // argument is optional
+        let read = unsafe { CreateNamedPipeW(wide.as_ptr(), PIPE_ACCESS_INBOUND | FILE_FLAG_OVERLAPPED, PIPE_TYPE_BYTE, 1, 0, 0, 0, ptr::null()) };
+        assert_ne!(read, INVALID_HANDLE_VALUE);
+        let write = OpenOptions::new().write(true).open(&name).unwrap();
+        // SAFETY: the pipe handle is newly created and owned by nothing else
+        (unsafe { File::from_raw_handle(read) }, write)
+    }
+
+    #[test]
+    fn deadline() {
+        let (mut read, mut write) = pipe();
+        let mut buf = [0, 0];
+
+        write.write_all(&[1]).unwrap();
+        let ret = read.read_exact_or_eof_deadline(&mut buf, Instant::now() + Duration::from_millis(50));
+        assert_eq!(ret.unwrap(), TimeoutStatus::TimedOut { filled: 1 });
+
+        write.write_all(&[2, 3]).unwrap();
+        drop(write);
+        let ret = read.read_exact_or_eof_deadline(&mut buf, Instant::now() + Duration::from_secs(5));
+        assert_eq!(ret.unwrap(), TimeoutStatus::Done(true));
+        assert_eq!(buf, [2, 3]);
+    }
+}
@@ -0,0 +1,174 @@
+use std::io::{self, Read};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use ::rayon::Yield;
+use crate::ReadExactExt;
+
+/// Creates a pipeline that reads `record_len` byte records from `reader`
+/// and processes them on the rayon thread pool.
+///
+/// Records are read sequentially on the calling thread, and each is handed
+/// to the pool as soon as it has been read.
+pub fn par_records<R: Read>(reader: R, record_len: usize) -> ParRecords<R> {
+    ParRecords {
+        reader,
+        record_len,
+        max_in_flight: ::rayon::current_num_threads() * 2,
+        ordered: true,
+    }
+}
+
+/// A pipeline of fixed-size records processed in parallel.
+///
+/// This is created by `par_records`.
+#[derive(Debug)]
+pub struct ParRecords<R> {
+    reader: R,
+    record_len: usize,
+    max_in_flight: usize,
+    ordered: bool,
+}
+
+impl<R: Read> ParRecords<R> {
+    /// Sets the maximum number of records that have been read but not yet
+    /// processed, which bounds memory use when processing is slower than
+    /// reading.
+    ///
+    /// The default is twice the number of threads in the pool.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_in_flight` is zero.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        assert!(max_in_flight > 0, "max_in_flight must be non-zero");
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// Sets whether results are returned in the order their records were
+    /// read, rather than the order they finished in.
+    ///
+    /// The default is `true`.
+    pub fn ordered(mut self, ordered: bool) -> Self {
+        self.ordered = ordered;
+        self
+    }
+
+    /// Processes every record with `f`, and collects the results.
+    ///
+    /// Reading stops at EOF on a record boundary, or at the first error,
+    /// which is returned once the records already read have been processed.
+    /// A panic in `f` is resumed on the calling thread.
+    pub fn map<T: Send, F: Fn(&[u8]) -> T + Sync>(mut self, f: F) -> io::Result<Vec<T>> {
+        let (tx, rx) = mpsc::channel();
+        let mut results = Vec::new();
+        let mut spare = Vec::new();
+        let f = &f;
+
+        let ret = ::rayon::in_place_scope(|scope| {
+            let mut in_flight = 0;
+            let mut index = 0;
+
+            let ret = loop {
+                if in_flight == self.max_in_flight {
+                    store(recv(&rx), &mut results, &mut spare);
+                    in_flight -= 1;
+                }
+
+                let mut buf = spare.pop().unwrap_or_else(|| vec![0; self.record_len]);
+                match self.reader.read_exact_or_eof(&mut buf) {
+                    Ok(true) => {},
+                    Ok(false) => break Ok(()),
+                    Err(e) => break Err(e),
+                }
+
+                let tx = tx.clone();
+                scope.spawn(move |_| {
+                    let out = panic::catch_unwind(AssertUnwindSafe(|| f(&buf)));
+                    let _ = tx.send((index, out, buf));
+                });
+                in_flight += 1;
+                index += 1;
+            };
+
+            for _ in 0..in_flight {
+                store(recv(&rx), &mut results, &mut spare);
+            }
+            ret
+        });
+
+        let mut results = results.into_iter()
+            .map(|(index, out)| out.map(|out| (index, out)).unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect::<Vec<_>>();
+        ret?;
+        if self.ordered {
+            results.sort_unstable_by_key(|&(index, _)| index);
+        }
+        Ok(results.into_iter().map(|(_, out)| out).collect())
+    }
+}
+
+/// Stores a finished result, keeping its buffer for another record.
+fn store<T>((index, out, buf): (usize, T, Vec<u8>), results: &mut Vec<(usize, T)>, spare: &mut Vec<Vec<u8>>) {
+    results.push((index, out));
+    spare.push(buf);
+}
+
+/// Waits for a result, running other tasks of the pool while waiting if the
+/// calling thread belongs to it.
+fn recv<T>(rx: &Receiver<T>) -> T {
+    loop {
+        match rx.try_recv() {
+            Ok(value) => return value,
+            Err(TryRecvError::Empty) => match ::rayon::yield_now() {
+                Some(Yield::Executed) => {},
+                Some(Yield::Idle) => thread::yield_now(),
+                None => return rx.recv().expect("the pipeline holds a sender"),
+            },
+            Err(TryRecvError::Disconnected) => unreachable!("the pipeline holds a sender"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::thread;
+    use std::time::Duration;
+    use super::par_records;
+
+    #[test]
+    fn ordered() {
+        let data: Vec<u8> = (0..64).collect();
+        let sums = par_records(&data[..], 4).max_in_flight(3).map(|record| {
+            // finish out of order
+            thread::sleep(Duration::from_millis(u64::from(record[0] % 3)));
+            record.iter().map(|&b| u32::from(b)).sum::<u32>()
+        }).unwrap();
+
+        assert_eq!(sums, (0..16).map(|i| (0..4).map(|j| i * 4 + j).sum()).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn unordered() {
+        let data: Vec<u8> = (0..16).collect();
+        let mut firsts = par_records(&data[..], 2).ordered(false).map(|record| record[0]).unwrap();
+
+        firsts.sort_unstable();
+        assert_eq!(firsts, [0, 2, 4, 6, 8, 10, 12, 14]);
+    }
+
+    #[test]
+    fn truncated() {
+        let ret = par_records(&[1, 2, 3][..], 2).map(|record| record.to_vec());
+
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[should_panic = "boom"]
+    fn panics() {
+        let _ = par_records(&[1, 2][..], 1).map(|record| if record[0] == 2 { panic!("boom") });
+    }
+}
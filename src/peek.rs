@@ -0,0 +1,178 @@
+use std::{io, mem};
+use std::net::TcpStream;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use crate::unexpected_eof;
+
+/// An extension trait for stream sockets that can be peeked.
+///
+/// This requires the `libc` feature, and is only available on Unix.
+pub trait PeekExactExt {
+    /// Waits until enough data is available to fill `buf` without consuming
+    /// it, or returns `false` at EOF.
+    ///
+    /// This uses `MSG_PEEK` with `MSG_WAITALL`, so the data remains to be
+    /// read afterwards. EOF after only part of `buf` is available is an
+    /// error. A nonblocking socket fails with `WouldBlock`, and a socket with
+    /// a read timeout with `TimedOut`, if only part of `buf` is available
+    /// without waiting.
+    fn peek_exact_or_eof(&self, buf: &mut [u8]) -> io::Result<bool>;
+}
+
+impl PeekExactExt for TcpStream {
+    fn peek_exact_or_eof(&self, buf: &mut [u8]) -> io::Result<bool> {
+        peek_exact(self.as_raw_fd(), buf)
+    }
+}
+
+impl PeekExactExt for UnixStream {
+    fn peek_exact_or_eof(&self, buf: &mut [u8]) -> io::Result<bool> {
+        peek_exact(self.as_raw_fd(), buf)
+    }
+}
+
+fn peek_exact(fd: RawFd, buf: &mut [u8]) -> io::Result<bool> {
+    let mut hung_up = false;
+
+    while !buf.is_empty() {
+        // SAFETY: `buf` is valid for writes of `buf.len()` bytes
+        let n = match unsafe { libc::recv(fd, buf.as_mut_ptr().cast(), buf.len(), libc::MSG_PEEK | libc::MSG_WAITALL) } {
+            -1 => match io::Error::last_os_error() {
+                e if e.kind() == io::ErrorKind::Interrupted => continue,
+                e => return Err(e),
+            },
+            n => n as usize,
+        };
+
+        match n {
+            0 => return Ok(false),
+            n if n == buf.len() => break,
+            // the peek after a hangup sees all of the data that will arrive
+            _ if hung_up => return Err(unexpected_eof()),
+            _ if is_hung_up(fd)? => hung_up = true,
+            // `MSG_WAITALL` waits for neither a nonblocking socket nor past a
+            // receive timeout, and otherwise a short peek came from a signal
+            _ if is_nonblocking(fd)? => return Err(io::Error::new(io::ErrorKind::WouldBlock, "peek would block before filling whole buffer")),
+            _ if has_timeout(fd)? => return Err(io::Error::new(io::ErrorKind::TimedOut, "timed out peeking whole buffer")),
+            _ => {},
+        }
+    }
+
+    Ok(true)
+}
+
+/// Returns whether the peer of `fd` has shut down its side of the stream.
+fn is_hung_up(fd: RawFd) -> io::Result<bool> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const POLLRDHUP: libc::c_short = libc::POLLRDHUP;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const POLLRDHUP: libc::c_short = 0;
+
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN | POLLRDHUP,
+        revents: 0,
+    };
+    // SAFETY: `pollfd` is a valid array of one entry for the duration of the call
+    match unsafe { libc::poll(&mut pollfd, 1, 0) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(pollfd.revents & (libc::POLLHUP | POLLRDHUP) != 0),
+    }
+}
+
+fn is_nonblocking(fd: RawFd) -> io::Result<bool> {
+    // SAFETY: `F_GETFL` takes no argument
+    match unsafe { libc::fcntl(fd, libc::F_GETFL) } {
+        -1 => Err(io::Error::last_os_error()),
+        flags => Ok(flags & libc::O_NONBLOCK != 0),
+    }
+}
+
+fn has_timeout(fd: RawFd) -> io::Result<bool> {
+    let mut timeout = libc::timeval {
+        tv_sec: 0,
+        tv_usec: 0,
+    };
+    let mut len = mem::size_of::<libc::timeval>() as libc::socklen_t;
+    // SAFETY: `timeout` is valid for writes of `len` bytes
+    match unsafe { libc::getsockopt(fd, libc::SOL_SOCKET, libc::SO_RCVTIMEO, (&mut timeout as *mut libc::timeval).cast(), &mut len) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(timeout.tv_sec != 0 || timeout.tv_usec != 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+    use super::PeekExactExt;
+
+    fn pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let write = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        (listener.accept().unwrap().0, write)
+    }
+
+    #[test]
+    fn peek() {
+        let (mut read, mut write) = pair();
+        let mut buf = [0; 2];
+
+        write.write_all(&[1]).unwrap();
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            write.write_all(&[2]).unwrap();
+        });
+        assert!(read.peek_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        writer.join().unwrap();
+
+        read.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2]);
+        assert!(!read.peek_exact_or_eof(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn unix_truncated() {
+        let (read, mut write) = std::os::unix::net::UnixStream::pair().unwrap();
+
+        write.write_all(&[1]).unwrap();
+        drop(write);
+        assert_eq!(read.peek_exact_or_eof(&mut [0; 2]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn tcp_truncated() {
+        let (read, mut write) = pair();
+
+        write.write_all(&[1]).unwrap();
+        drop(write);
+        assert_eq!(read.peek_exact_or_eof(&mut [0; 2]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn nonblocking() {
+        let (read, mut write) = std::os::unix::net::UnixStream::pair().unwrap();
+        let mut buf = [0; 2];
+        read.set_nonblocking(true).unwrap();
+
+        assert_eq!(read.peek_exact_or_eof(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        write.write_all(&[1]).unwrap();
+        assert_eq!(read.peek_exact_or_eof(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(read.peek_exact_or_eof(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        write.write_all(&[2]).unwrap();
+        assert!(read.peek_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+    }
+
+    #[test]
+    fn timeout() {
+        let (read, mut write) = pair();
+        read.set_read_timeout(Some(Duration::from_millis(20))).unwrap();
+
+        write.write_all(&[1]).unwrap();
+        assert_eq!(read.peek_exact_or_eof(&mut [0; 2]).unwrap_err().kind(), io::ErrorKind::TimedOut);
+    }
+}
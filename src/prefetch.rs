@@ -0,0 +1,262 @@
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use crate::source::{fill_source, AtLeast, IoSource, SliceBuf};
+use crate::ReadExactExt;
+
+/// A reader that reads ahead of its caller on a background thread.
+///
+/// The background thread reads chunks into a fixed ring of buffers, which
+/// are recycled once their contents have been consumed, so reading overlaps
+/// with whatever the caller does with the data. An error from the underlying
+/// reader is returned once the data read before it is consumed, after which
+/// the reader is at EOF.
+#[derive(Debug)]
+pub struct Prefetching<R> {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    recycle: SyncSender<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+    worker: JoinHandle<R>,
+}
+
+impl<R: Read + Send + 'static> Prefetching<R> {
+    /// Starts reading ahead from `reader` in `chunk_len` byte chunks, holding
+    /// at most `depth` chunks that the caller has not consumed yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_len` or `depth` is zero.
+    pub fn new(reader: R, chunk_len: usize, depth: usize) -> Self {
+        assert!(chunk_len > 0 && depth > 0, "chunk length and depth must be non-zero");
+        let (chunks_tx, chunks) = mpsc::sync_channel(depth);
+        let (recycle, recycle_rx) = mpsc::sync_channel(depth + 1);
+        let worker = thread::spawn(move || prefetch(reader, chunk_len, chunks_tx, recycle_rx));
+
+        Prefetching {
+            chunks,
+            recycle,
+            chunk: Vec::new(),
+            pos: 0,
+            worker,
+        }
+    }
+}
+
+impl<R> Prefetching<R> {
+    /// Stops reading ahead, returning the underlying reader.
+    ///
+    /// Any data read ahead that was not consumed is lost. This waits for the
+    /// background thread to finish its current read, and fails if it
+    /// panicked.
+    pub fn into_inner(self) -> thread::Result<R> {
+        drop(self.chunks);
+        self.worker.join()
+    }
+}
+
+fn prefetch<R: Read>(mut reader: R, chunk_len: usize, chunks: SyncSender<io::Result<Vec<u8>>>, recycle: Receiver<Vec<u8>>) -> R {
+    loop {
+        let mut chunk = recycle.try_recv().unwrap_or_default();
+        chunk.resize(chunk_len, 0);
+        let mut buf = SliceBuf::new(&mut chunk);
+        let ret = fill_source(&mut IoSource(&mut reader), &mut buf, &mut AtLeast(1)).map(|()| buf.filled());
+
+        let ret = match ret {
+            Ok(0) => break,
+            Ok(n) => {
+                chunk.truncate(n);
+                Ok(chunk)
+            },
+            Err(e) => Err(e),
+        };
+        let failed = ret.is_err();
+        if chunks.send(ret).is_err() || failed {
+            break
+        }
+    }
+
+    reader
+}
+
+impl<R> Read for Prefetching<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+
+        if self.pos == self.chunk.len() {
+            let chunk = match self.chunks.recv() {
+                Ok(chunk) => chunk?,
+                Err(..) => return Ok(0),
+            };
+            let _ = self.recycle.try_send(std::mem::replace(&mut self.chunk, chunk));
+            self.pos = 0;
+        }
+
+        let available = &self.chunk[self.pos..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// A reader of fixed-size records that reads each record while the previous
+/// one is being processed.
+///
+/// Unlike `Prefetching`, this holds exactly two record buffers and borrows
+/// the reader on a scoped thread for the duration of `for_each`, so the
+/// reader need not be `'static` and nothing is read ahead once processing
+/// stops.
+#[derive(Debug)]
+pub struct DoubleBuffered<R> {
+    reader: R,
+    record_len: usize,
+}
+
+impl<R> DoubleBuffered<R> {
+    /// Creates a reader of `record_len` byte records.
+    pub fn new(reader: R, record_len: usize) -> Self {
+        DoubleBuffered {
+            reader,
+            record_len,
+        }
+    }
+
+    /// Returns the length of each record.
+    pub fn record_len(&self) -> usize {
+        self.record_len
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Send> DoubleBuffered<R> {
+    /// Calls `f` with each record until EOF on a record boundary, or until
+    /// `f` returns `false`, returning the number of records processed.
+    ///
+    /// The next record is read into the second buffer while `f` runs. If `f`
+    /// stops early, the record read ahead of it is lost. EOF partway through
+    /// a record is an error, which is returned once the records before it
+    /// have been processed. A panic while reading is resumed on the calling
+    /// thread.
+    pub fn for_each<F: FnMut(&[u8]) -> bool>(&mut self, mut f: F) -> io::Result<u64> {
+        let record_len = self.record_len;
+        let reader = &mut self.reader;
+
+        thread::scope(|scope| {
+            let (full_tx, full) = mpsc::sync_channel::<io::Result<Option<Vec<u8>>>>(1);
+            let (empty, empty_rx) = mpsc::sync_channel::<Vec<u8>>(2);
+            for _ in 0..2 {
+                let _ = empty.send(vec![0; record_len]);
+            }
+
+            scope.spawn(move || {
+                for mut buf in empty_rx {
+                    let ret = reader.read_exact_or_eof(&mut buf).map(|full| full.then_some(buf));
+                    let done = !matches!(ret, Ok(Some(..)));
+                    if full_tx.send(ret).is_err() || done {
+                        break
+                    }
+                }
+            });
+
+            let mut processed = 0;
+            // a closed channel means the reader panicked, which the scope
+            // resumes once it ends
+            while let Ok(ret) = full.recv() {
+                let buf = match ret? {
+                    Some(buf) => buf,
+                    None => break,
+                };
+                processed += 1;
+                if !f(&buf) {
+                    break
+                }
+                let _ = empty.send(buf);
+            }
+            Ok(processed)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use crate::ReadExactExt;
+    use super::{DoubleBuffered, Prefetching};
+
+    #[test]
+    fn prefetch() {
+        let data: Vec<u8> = (0..100).collect();
+        let mut read = Prefetching::new(io::Cursor::new(data.clone()), 7, 2);
+        let mut buf = [0; 10];
+
+        for chunk in data.chunks(10) {
+            assert!(read.read_exact_or_eof(&mut buf).unwrap());
+            assert_eq!(buf, chunk);
+        }
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(read.into_inner().unwrap().position(), 100);
+    }
+
+    #[test]
+    fn error() {
+        let read = io::Read::chain(&[1, 2, 3][..], crate::testing::ScriptedReader::new().error(io::ErrorKind::Other));
+        let mut read = Prefetching::new(read, 2, 1);
+        let mut buf = [0; 2];
+
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert!(read.read_exact_or_eof(&mut buf).is_err());
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn double_buffered() {
+        let data: Vec<u8> = (0..10).collect();
+        let mut read = DoubleBuffered::new(&data[..], 2);
+        let mut records = Vec::new();
+
+        assert_eq!(read.for_each(|record| {
+            records.push(record.to_vec());
+            true
+        }).unwrap(), 5);
+        assert_eq!(records, data.chunks(2).collect::<Vec<_>>());
+        assert!(read.get_ref().is_empty());
+    }
+
+    #[test]
+    fn double_buffered_stop() {
+        let mut read = DoubleBuffered::new(&[1, 2, 3, 4, 5, 6, 7, 8][..], 2);
+
+        assert_eq!(read.for_each(|record| record[0] != 3).unwrap(), 2);
+        // only the record after the last one processed has been read ahead
+        assert_eq!(read.into_inner(), &[7, 8]);
+    }
+
+    #[test]
+    fn double_buffered_truncated() {
+        let mut read = DoubleBuffered::new(&[1, 2, 3][..], 2);
+        let mut processed = 0;
+
+        assert!(read.for_each(|_| {
+            processed += 1;
+            true
+        }).is_err());
+        assert_eq!(processed, 1);
+    }
+}
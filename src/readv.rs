@@ -0,0 +1,87 @@
+use std::io::{self, IoSliceMut, Read};
+use std::os::unix::io::{AsRawFd, RawFd};
+use crate::source::{fill_source, IoSource, Plain, VectoredBuf};
+
+/// The most slices passed to a single `readv`, which is the `IOV_MAX` of
+/// Linux and the BSDs.
+const MAX_IOVECS: usize = 1024;
+
+/// An extension trait that reads vectored records with `readv` directly.
+///
+/// This requires the `libc` feature, and is only available on Unix.
+pub trait ReadvExactExt {
+    /// Reads exactly the number of bytes to fill all of `bufs`, or zero.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_vectored_or_eof`, but
+    /// passes `bufs` to `readv` as they are, since an `IoSliceMut` has the
+    /// layout of an `iovec` on Unix. This avoids the overhead of going
+    /// through `Read::read_vectored` for records made of a few segments.
+    fn readv_exact_or_eof(&self, bufs: &mut [IoSliceMut]) -> io::Result<bool>;
+}
+
+impl<T: AsRawFd + ?Sized> ReadvExactExt for T {
+    fn readv_exact_or_eof(&self, bufs: &mut [IoSliceMut]) -> io::Result<bool> {
+        readv_exact(self.as_raw_fd(), bufs)
+    }
+}
+
+fn readv_exact(fd: RawFd, bufs: &mut [IoSliceMut]) -> io::Result<bool> {
+    let mut bufs = VectoredBuf::new(bufs);
+    fill_source(&mut IoSource(&mut Fd(fd)), &mut bufs, &mut Plain)?;
+
+    bufs.status().into_result()
+}
+
+/// A descriptor read with `read` and `readv` directly.
+struct Fd(RawFd);
+
+impl Read for Fd {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // SAFETY: `buf` is valid for writes of its length
+        match unsafe { libc::read(self.0, buf.as_mut_ptr().cast(), buf.len()) } {
+            -1 => Err(io::Error::last_os_error()),
+            n => Ok(n as usize),
+        }
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        let count = bufs.len().min(MAX_IOVECS);
+        // SAFETY: `IoSliceMut` is guaranteed to be ABI compatible with
+        // `iovec`, and each slice is valid for writes of its length
+        match unsafe { libc::readv(self.0, bufs.as_ptr().cast(), count as libc::c_int) } {
+            -1 => Err(io::Error::last_os_error()),
+            n => Ok(n as usize),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{IoSliceMut, Write};
+    use std::os::unix::net::UnixStream;
+    use super::ReadvExactExt;
+
+    #[test]
+    fn readv() {
+        let (mut tx, rx) = UnixStream::pair().unwrap();
+        tx.write_all(&[1, 2, 3, 4, 5, 6]).unwrap();
+        let (mut a, mut b) = ([0; 1], [0; 3]);
+
+        assert!(rx.readv_exact_or_eof(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut []), IoSliceMut::new(&mut b)]).unwrap());
+        assert_eq!((a, b), ([1], [2, 3, 4]));
+        drop(tx);
+        assert!(rx.readv_exact_or_eof(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)]).is_err());
+        assert!(!rx.readv_exact_or_eof(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)]).unwrap());
+    }
+
+    #[test]
+    fn many_slices() {
+        let (mut tx, rx) = UnixStream::pair().unwrap();
+        tx.write_all(&[7; 2000]).unwrap();
+        let mut bytes = [0; 2000];
+        let mut bufs: Vec<_> = bytes.chunks_mut(1).map(IoSliceMut::new).collect();
+
+        assert!(rx.readv_exact_or_eof(&mut bufs).unwrap());
+        assert_eq!(bytes, [7; 2000]);
+    }
+}
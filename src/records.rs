@@ -0,0 +1,620 @@
+use core::iter::FusedIterator;
+use crate::{io, ReadExactExt, ReadExactOpts};
+#[cfg(feature = "std")]
+use std::fmt;
+#[cfg(feature = "std")]
+use crate::ReadExactStatus;
+
+/// An iterator over fixed-size chunks of a reader.
+///
+/// This is created by `ReadExactExt::exact_chunks`. It ends at EOF on a chunk
+/// boundary, or after yielding the first error.
+#[derive(Debug)]
+pub struct ExactChunks<R, const N: usize> {
+    reader: R,
+    opts: ReadExactOpts,
+    done: bool,
+}
+
+impl<R, const N: usize> ExactChunks<R, N> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self::with_opts(reader, ReadExactOpts::new())
+    }
+
+    pub(crate) fn with_opts(reader: R, opts: ReadExactOpts) -> Self {
+        ExactChunks {
+            reader,
+            opts,
+            done: false,
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this iterator, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: io::Read, const N: usize> Iterator for ExactChunks<R, N> {
+    type Item = io::Result<[u8; N]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None
+        }
+
+        let mut buf = [0; N];
+        let ret = self.reader.read_exact_or_eof_with(&self.opts, &mut buf)
+            .map(|full| full.then_some(buf))
+            .transpose();
+        self.done = !matches!(ret, Some(Ok(..)));
+        ret
+    }
+}
+
+impl<R: io::Read, const N: usize> FusedIterator for ExactChunks<R, N> {}
+
+/// A reader of fixed-size records that reuses a single buffer.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Records<R> {
+    reader: R,
+    buf: Vec<u8>,
+    readahead: Option<Readahead<R>>,
+}
+
+/// The readahead window of a `Records<File>`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct Readahead<R> {
+    len: u64,
+    remaining: u64,
+    advise: fn(&R, u64),
+}
+
+#[cfg(feature = "std")]
+impl<R> Records<R> {
+    /// Creates a reader of `record_len` byte records.
+    pub fn new(reader: R, record_len: usize) -> Self {
+        Records {
+            reader,
+            buf: vec![0; record_len],
+            readahead: None,
+        }
+    }
+
+    /// Returns the length of each record.
+    pub fn record_len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> Records<R> {
+    /// Reads the next record, or returns `None` at EOF on a record boundary.
+    ///
+    /// The returned slice borrows the internal buffer, which is overwritten
+    /// by the next call.
+    pub fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        if let Some(readahead) = &mut self.readahead {
+            match readahead.remaining.checked_sub(self.buf.len() as u64) {
+                Some(remaining) => readahead.remaining = remaining,
+                None => {
+                    (readahead.advise)(&self.reader, readahead.len);
+                    readahead.remaining = readahead.len;
+                },
+            }
+        }
+
+        Ok(match self.reader.read_exact_or_eof(&mut self.buf)? {
+            true => Some(&self.buf),
+            false => None,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Records<std::fs::File> {
+    /// Hints to the OS that the file will be read sequentially, and that the
+    /// next `len` bytes after the cursor will be needed soon, refreshing the
+    /// hint each time that many bytes have been read.
+    ///
+    /// This improves the throughput of scans over files that are not already
+    /// cached. Hints are issued with `posix_fadvise` when the `libc` feature
+    /// is enabled on platforms that support it, and are otherwise ignored. On
+    /// Windows, the equivalent is to open the file with
+    /// `FILE_FLAG_SEQUENTIAL_SCAN`.
+    pub fn readahead(mut self, len: u64) -> Self {
+        advise_sequential(&self.reader);
+        self.readahead = Some(Readahead {
+            len,
+            remaining: 0,
+            advise: advise_willneed,
+        });
+        self
+    }
+}
+
+#[cfg(all(feature = "std", feature = "libc", any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+fn advise_sequential(file: &std::fs::File) {
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: the descriptor is owned by `file`
+    unsafe { ::libc::posix_fadvise(file.as_raw_fd(), 0, 0, ::libc::POSIX_FADV_SEQUENTIAL) };
+}
+
+#[cfg(all(feature = "std", feature = "libc", any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+fn advise_willneed(mut file: &std::fs::File, len: u64) {
+    use std::io::Seek;
+    use std::os::unix::io::AsRawFd;
+
+    if let Ok(offset) = file.stream_position() {
+        let len = len.min(::libc::off_t::MAX as u64) as ::libc::off_t;
+        // SAFETY: the descriptor is owned by `file`
+        unsafe { ::libc::posix_fadvise(file.as_raw_fd(), offset as ::libc::off_t, len, ::libc::POSIX_FADV_WILLNEED) };
+    }
+}
+
+#[cfg(all(feature = "std", not(all(feature = "libc", any(target_os = "linux", target_os = "android", target_os = "freebsd")))))]
+fn advise_sequential(_: &std::fs::File) {}
+
+#[cfg(all(feature = "std", not(all(feature = "libc", any(target_os = "linux", target_os = "android", target_os = "freebsd")))))]
+fn advise_willneed(_: &std::fs::File, _: u64) {}
+
+/// A reader of fixed-size records that tracks its position in the stream.
+///
+/// A record that is cut short by EOF fails with an `UnexpectedEof` error
+/// whose inner error is a `TruncatedRecord`, reporting where it happened.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct RecordReader<R> {
+    reader: R,
+    buf: Vec<u8>,
+    records_read: u64,
+    byte_offset: u64,
+}
+
+#[cfg(feature = "std")]
+impl<R> RecordReader<R> {
+    /// Creates a reader of `record_len` byte records.
+    pub fn new(reader: R, record_len: usize) -> Self {
+        RecordReader {
+            reader,
+            buf: vec![0; record_len],
+            records_read: 0,
+            byte_offset: 0,
+        }
+    }
+
+    /// Returns the length of each record.
+    pub fn record_len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns the number of whole records read or skipped so far.
+    pub fn records_read(&self) -> u64 {
+        self.records_read
+    }
+
+    /// Returns the number of bytes consumed from the reader so far.
+    ///
+    /// This includes the bytes of a truncated record.
+    pub fn byte_offset(&self) -> u64 {
+        self.byte_offset
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// Reading from it directly throws off the position of this reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> RecordReader<R> {
+    /// Reads the next record, or returns `None` at EOF on a record boundary.
+    ///
+    /// The returned slice borrows the internal buffer, which is overwritten
+    /// by the next call.
+    pub fn next_record(&mut self) -> io::Result<Option<&[u8]>> {
+        Ok(match self.fill()? {
+            true => Some(&self.buf),
+            false => None,
+        })
+    }
+
+    /// Reads and discards up to `n` records, returning the number skipped,
+    /// which is less than `n` only at EOF on a record boundary.
+    pub fn skip_records(&mut self, n: u64) -> io::Result<u64> {
+        for skipped in 0..n {
+            if !self.fill()? {
+                return Ok(skipped)
+            }
+        }
+        Ok(n)
+    }
+
+    fn fill(&mut self) -> io::Result<bool> {
+        let status = self.reader.read_exact_or_eof_status(&mut self.buf)?;
+        match status {
+            ReadExactStatus::Full => {
+                self.records_read += 1;
+                self.byte_offset += self.buf.len() as u64;
+                Ok(true)
+            },
+            ReadExactStatus::CleanEof => Ok(false),
+            ReadExactStatus::PartialEof { filled } => {
+                self.byte_offset += filled as u64;
+                Err(io::Error::new(io::ErrorKind::UnexpectedEof, TruncatedRecord {
+                    index: self.records_read,
+                    offset: self.byte_offset,
+                    filled,
+                }))
+            },
+        }
+    }
+}
+
+/// The error for a record that was cut short by EOF.
+///
+/// This is the inner error of the `UnexpectedEof` errors returned by
+/// `RecordReader`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TruncatedRecord {
+    /// The index of the truncated record.
+    pub index: u64,
+    /// The byte offset at which EOF was reached.
+    pub offset: u64,
+    /// The number of bytes of the record that were read.
+    pub filled: usize,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for TruncatedRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "record {} truncated at byte offset {}", self.index, self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TruncatedRecord {}
+
+/// A reader of fixed-width text records, such as card images.
+///
+/// Each record is exactly `width` bytes of UTF-8, optionally followed by a
+/// newline.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FixedWidthRecords<R> {
+    records: Records<R>,
+    width: usize,
+    newline: bool,
+    ascii: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R> FixedWidthRecords<R> {
+    /// Creates a reader of `width` byte records.
+    pub fn new(reader: R, width: usize) -> Self {
+        FixedWidthRecords {
+            records: Records::new(reader, width),
+            width,
+            newline: false,
+            ascii: false,
+        }
+    }
+
+    /// Sets whether each record is followed by a `\n`, which is checked and
+    /// stripped from the record.
+    pub fn newline(self, newline: bool) -> Self {
+        FixedWidthRecords {
+            records: Records::new(self.records.into_inner(), self.width + usize::from(newline)),
+            newline,
+            ..self
+        }
+    }
+
+    /// Sets whether records must be ASCII rather than just UTF-8.
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Returns the width of each record, excluding any newline.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.records.get_ref()
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.records.get_mut()
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.records.into_inner()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> FixedWidthRecords<R> {
+    /// Reads the next record, or returns `None` at EOF on a record boundary.
+    ///
+    /// Records that are missing their newline or are not valid text fail with
+    /// `InvalidData`. The returned string borrows the internal buffer, which
+    /// is overwritten by the next call.
+    pub fn next_record(&mut self) -> io::Result<Option<&str>> {
+        let (width, newline, ascii) = (self.width, self.newline, self.ascii);
+        let (record, rest) = match self.records.next_record()? {
+            Some(record) => record.split_at(width),
+            None => return Ok(None),
+        };
+
+        if newline && rest != b"\n" {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "record is missing a trailing newline"))
+        }
+        if ascii && !record.is_ascii() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "record is not ASCII"))
+        }
+        core::str::from_utf8(record)
+            .map(Some)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "record is not valid UTF-8"))
+    }
+}
+
+/// A type that is parsed from a fixed-size record.
+///
+/// Implementing this lets a type be read directly with
+/// `ReadExactExt::read_record_or_eof` and `ReadExactExt::typed_records`.
+#[cfg(feature = "std")]
+pub trait FixedSizeRecord: Sized {
+    /// The length of a record in bytes.
+    const SIZE: usize;
+
+    /// The error returned for a record that cannot be parsed.
+    type Error: Into<Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Parses a record.
+    ///
+    /// `bytes` is always exactly `SIZE` bytes long. It is a slice rather than
+    /// an array because an array length cannot yet depend on `SIZE`.
+    fn parse(bytes: &[u8]) -> Result<Self, Self::Error>;
+}
+
+/// Parses a record, wrapping a parse error in an `InvalidData` error.
+#[cfg(feature = "std")]
+pub(crate) fn parse_record<T: FixedSizeRecord>(bytes: &[u8]) -> io::Result<T> {
+    T::parse(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// An iterator over the records of a reader, parsed as a `FixedSizeRecord`.
+///
+/// This is created by `ReadExactExt::typed_records`. It ends at EOF on a
+/// record boundary, or after yielding the first error.
+#[cfg(feature = "std")]
+pub struct TypedRecords<R, T> {
+    records: Records<R>,
+    done: bool,
+    _record: core::marker::PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "std")]
+impl<R, T: FixedSizeRecord> TypedRecords<R, T> {
+    pub(crate) fn new(reader: R) -> Self {
+        TypedRecords {
+            records: Records::new(reader, T::SIZE),
+            done: false,
+            _record: core::marker::PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R, T> TypedRecords<R, T> {
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.records.get_ref()
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.records.get_mut()
+    }
+
+    /// Unwraps this iterator, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.records.into_inner()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: fmt::Debug, T> fmt::Debug for TypedRecords<R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypedRecords")
+            .field("records", &self.records)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read, T: FixedSizeRecord> Iterator for TypedRecords<R, T> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None
+        }
+
+        let ret = match self.records.next_record() {
+            Ok(Some(record)) => Some(parse_record(record)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        };
+        self.done = !matches!(ret, Some(Ok(..)));
+        ret
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read, T: FixedSizeRecord> FusedIterator for TypedRecords<R, T> {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::ReadExactExt;
+    use super::{FixedSizeRecord, FixedWidthRecords, RecordReader, Records, TruncatedRecord};
+
+    #[derive(Debug, PartialEq)]
+    struct Point(u8, u8);
+
+    impl FixedSizeRecord for Point {
+        const SIZE: usize = 2;
+        type Error = &'static str;
+
+        fn parse(bytes: &[u8]) -> Result<Self, Self::Error> {
+            match *bytes {
+                [x, y] if x != 0xff => Ok(Point(x, y)),
+                _ => Err("bad point"),
+            }
+        }
+    }
+
+    #[test]
+    fn read_record() {
+        let mut read = &[1, 2, 0xff, 0, 3][..];
+
+        assert_eq!(read.read_record_or_eof::<Point>().unwrap(), Some(Point(1, 2)));
+        let err = read.read_record_or_eof::<Point>().unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "bad point");
+        assert!(read.read_record_or_eof::<Point>().is_err());
+        assert_eq!(read.read_record_or_eof::<Point>().unwrap(), None);
+    }
+
+    #[test]
+    fn typed_records() {
+        let records = (&[1, 2, 3, 4][..]).typed_records::<Point>();
+        assert_eq!(records.collect::<std::io::Result<Vec<_>>>().unwrap(), [Point(1, 2), Point(3, 4)]);
+
+        let mut records = (&[1, 2, 0xff, 0, 3, 4][..]).typed_records::<Point>();
+        assert_eq!(records.next().unwrap().unwrap(), Point(1, 2));
+        assert!(records.next().unwrap().is_err());
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn exact_chunks() {
+        let mut chunks = (&[1, 2, 3, 4][..]).exact_chunks::<2>();
+
+        assert_eq!(chunks.next().unwrap().unwrap(), [1, 2]);
+        assert_eq!(chunks.next().unwrap().unwrap(), [3, 4]);
+        assert!(chunks.next().is_none());
+    }
+
+    #[test]
+    fn exact_chunks_truncated() {
+        let mut chunks = (&[1, 2, 3][..]).exact_chunks::<2>();
+
+        assert_eq!(chunks.next().unwrap().unwrap(), [1, 2]);
+        assert!(chunks.next().unwrap().is_err());
+        assert!(chunks.next().is_none());
+    }
+
+    #[test]
+    fn records() {
+        let mut records = Records::new(&[1, 2, 3, 4, 5][..], 2);
+
+        assert_eq!(records.next_record().unwrap(), Some(&[1, 2][..]));
+        assert_eq!(records.next_record().unwrap(), Some(&[3, 4][..]));
+        assert!(records.next_record().is_err());
+        assert_eq!(records.next_record().unwrap(), None);
+    }
+
+    #[test]
+    fn record_reader() {
+        let mut records = RecordReader::new(&[1, 2, 3, 4, 5, 6, 7][..], 2);
+
+        assert_eq!(records.next_record().unwrap(), Some(&[1, 2][..]));
+        assert_eq!(records.skip_records(2).unwrap(), 2);
+        assert_eq!((records.records_read(), records.byte_offset()), (3, 6));
+        let err = records.next_record().unwrap_err();
+        let truncated = err.get_ref().unwrap().downcast_ref::<TruncatedRecord>().unwrap();
+        assert_eq!(*truncated, TruncatedRecord { index: 3, offset: 7, filled: 1 });
+        assert_eq!(err.to_string(), "record 3 truncated at byte offset 7");
+        assert_eq!(records.skip_records(2).unwrap(), 0);
+    }
+
+    #[test]
+    fn readahead() {
+        let path = std::env::temp_dir().join(format!("read_exact-readahead-{}", std::process::id()));
+        std::fs::write(&path, [1, 2, 3, 4, 5, 6]).unwrap();
+        let mut records = Records::new(std::fs::File::open(&path).unwrap(), 2).readahead(4);
+
+        assert_eq!(records.next_record().unwrap(), Some(&[1, 2][..]));
+        assert_eq!(records.next_record().unwrap(), Some(&[3, 4][..]));
+        assert_eq!(records.next_record().unwrap(), Some(&[5, 6][..]));
+        assert_eq!(records.next_record().unwrap(), None);
+        drop(records);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fixed_width() {
+        let mut records = FixedWidthRecords::new(&b"ab\ncd\nef"[..], 2).newline(true);
+
+        assert_eq!(records.next_record().unwrap(), Some("ab"));
+        assert_eq!(records.next_record().unwrap(), Some("cd"));
+        assert!(records.next_record().is_err());
+        assert_eq!(records.next_record().unwrap(), None);
+    }
+
+    #[test]
+    fn fixed_width_invalid() {
+        assert!(FixedWidthRecords::new(&b"abc"[..], 2).newline(true).next_record().is_err());
+        assert!(FixedWidthRecords::new(&[0xff, 0xfe][..], 2).next_record().is_err());
+        assert!(FixedWidthRecords::new("é".as_bytes(), 2).ascii(true).next_record().is_err());
+        assert_eq!(FixedWidthRecords::new("é".as_bytes(), 2).next_record().unwrap(), Some("é"));
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use crate::unexpected_eof;
+use crate::source::{fill_source, AtLeast, IoSource, SliceBuf};
+
+/// An extension trait for draining exact records from a `VecDeque<u8>`.
+pub trait VecDequeExactExt {
+    /// Removes exactly the number of bytes to fill `buf` from the front of
+    /// the queue.
+    ///
+    /// Returns `false` without removing anything if the queue holds fewer
+    /// bytes than that, so that the record can be taken once more bytes have
+    /// been pushed.
+    fn take_exact(&mut self, buf: &mut [u8]) -> bool;
+}
+
+impl VecDequeExactExt for VecDeque<u8> {
+    fn take_exact(&mut self, buf: &mut [u8]) -> bool {
+        if self.len() < buf.len() {
+            return false
+        }
+
+        let len = buf.len();
+        let (front, back) = self.as_slices();
+        let split = front.len().min(len);
+        buf[..split].copy_from_slice(&front[..split]);
+        buf[split..].copy_from_slice(&back[..len - split]);
+        self.drain(..len);
+        true
+    }
+}
+
+/// A reader that reassembles records in a ring buffer.
+///
+/// Unlike `ReadExactExt::read_exact_or_eof`, a record only leaves the ring
+/// once all of it has been read, so errors partway through a record,
+/// including `WouldBlock` from a non-blocking reader, leave the bytes read so
+/// far buffered and the read can simply be retried.
+#[derive(Debug)]
+pub struct RingReader<R> {
+    reader: R,
+    ring: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl<R> RingReader<R> {
+    /// Creates a reader that buffers up to `capacity` bytes of `reader`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(reader: R, capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+        RingReader {
+            reader,
+            ring: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Returns the bytes that have been read but not yet taken.
+    pub fn buffered(&self) -> &VecDeque<u8> {
+        &self.ring
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    ///
+    /// Any buffered bytes are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> RingReader<R> {
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, except that the
+    /// partial record is kept buffered when an error occurs. Records longer
+    /// than the capacity of the ring fail with `InvalidInput`.
+    pub fn read_record_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        if buf.len() > self.capacity {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "record exceeds the ring capacity"))
+        }
+
+        let mut chunk = [0; 0x1000];
+        while !self.ring.take_exact(buf) {
+            let len = (self.capacity - self.ring.len()).min(chunk.len());
+            let needs = (buf.len() - self.ring.len()).min(len);
+            let mut fill = SliceBuf::new(&mut chunk[..len]);
+            let ret = fill_source(&mut IoSource(&mut self.reader), &mut fill, &mut AtLeast(needs));
+            self.ring.extend(fill.filled_bytes());
+            ret?;
+            if fill.filled() < needs {
+                return match self.ring.is_empty() {
+                    true => Ok(false),
+                    false => Err(unexpected_eof()),
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::io;
+    use crate::testing::ScriptedReader;
+    use super::{RingReader, VecDequeExactExt};
+
+    #[test]
+    fn take_exact() {
+        let mut deque = VecDeque::with_capacity(4);
+        deque.extend([0, 0, 1, 2]);
+        deque.drain(..2);
+        deque.extend([3]);
+        let mut buf = [0; 2];
+
+        assert!(deque.take_exact(&mut buf));
+        assert_eq!(buf, [1, 2]);
+        assert!(!deque.take_exact(&mut buf));
+        assert_eq!(deque, [3]);
+    }
+
+    #[test]
+    fn records() {
+        let mut read = RingReader::new(&[1, 2, 3, 4, 5][..], 3);
+        let mut buf = [0; 2];
+
+        assert!(read.read_record_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(read.read_record_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert!(read.read_record_or_eof(&mut buf).is_err());
+        assert_eq!(read.buffered(), &[5]);
+        assert!(read.read_record_or_eof(&mut [0; 4]).is_err());
+    }
+
+    #[test]
+    fn resumes() {
+        let reader = ScriptedReader::new().data(&[1]).error(io::ErrorKind::WouldBlock).data(&[2]);
+        let mut read = RingReader::new(reader, 4);
+        let mut buf = [0; 2];
+
+        assert_eq!(read.read_record_or_eof(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        assert!(read.read_record_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(!read.read_record_or_eof(&mut buf).unwrap());
+    }
+}
@@ -0,0 +1,317 @@
+use std::io::{self, Read};
+use crate::{Endian, ReadExactExt};
+
+/// The type of a field in a `Schema`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    /// An unsigned 8-bit integer.
+    U8,
+    /// A signed 8-bit integer.
+    I8,
+    /// An unsigned 16-bit integer.
+    U16,
+    /// A signed 16-bit integer.
+    I16,
+    /// An unsigned 32-bit integer.
+    U32,
+    /// A signed 32-bit integer.
+    I32,
+    /// An unsigned 64-bit integer.
+    U64,
+    /// A signed 64-bit integer.
+    I64,
+    /// A 32-bit float.
+    F32,
+    /// A 64-bit float.
+    F64,
+    /// Raw bytes of the given width.
+    Bytes(usize),
+}
+
+impl FieldType {
+    /// Returns the width of the field in bytes.
+    pub const fn width(&self) -> usize {
+        match *self {
+            FieldType::U8 | FieldType::I8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+            FieldType::U64 | FieldType::I64 | FieldType::F64 => 8,
+            FieldType::Bytes(width) => width,
+        }
+    }
+}
+
+/// Describes a field of a fixed-size record.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Field {
+    name: String,
+    offset: usize,
+    ty: FieldType,
+    endian: Endian,
+}
+
+impl Field {
+    /// Creates a little-endian field at `offset` bytes into the record.
+    pub fn new<N: Into<String>>(name: N, offset: usize, ty: FieldType) -> Self {
+        Field {
+            name: name.into(),
+            offset,
+            ty,
+            endian: Endian::Little,
+        }
+    }
+
+    /// Sets the byte order of the field.
+    ///
+    /// This is ignored for bytes and single byte integers.
+    pub fn endian(mut self, endian: Endian) -> Self {
+        self.endian = endian;
+        self
+    }
+
+    /// Returns the name of the field.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the offset of the field into the record.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the type of the field.
+    pub fn ty(&self) -> FieldType {
+        self.ty
+    }
+
+    fn value<'a>(&self, record: &'a [u8]) -> FieldValue<'a> {
+        let bytes = &record[self.offset..self.offset + self.ty.width()];
+        if let FieldType::Bytes(..) = self.ty {
+            return FieldValue::Bytes(bytes)
+        }
+
+        let mut array = [0; 8];
+        let bits = match self.endian {
+            Endian::Big => {
+                array[8 - bytes.len()..].copy_from_slice(bytes);
+                u64::from_be_bytes(array)
+            },
+            Endian::Little => {
+                array[..bytes.len()].copy_from_slice(bytes);
+                u64::from_le_bytes(array)
+            },
+        };
+        match self.ty {
+            FieldType::U8 => FieldValue::U8(bits as u8),
+            FieldType::I8 => FieldValue::I8(bits as i8),
+            FieldType::U16 => FieldValue::U16(bits as u16),
+            FieldType::I16 => FieldValue::I16(bits as i16),
+            FieldType::U32 => FieldValue::U32(bits as u32),
+            FieldType::I32 => FieldValue::I32(bits as i32),
+            FieldType::U64 => FieldValue::U64(bits),
+            FieldType::I64 => FieldValue::I64(bits as i64),
+            FieldType::F32 => FieldValue::F32(f32::from_bits(bits as u32)),
+            FieldType::F64 => FieldValue::F64(f64::from_bits(bits)),
+            FieldType::Bytes(..) => FieldValue::Bytes(bytes),
+        }
+    }
+}
+
+/// A typed value of a record field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldValue<'a> {
+    /// An unsigned 8-bit integer.
+    U8(u8),
+    /// A signed 8-bit integer.
+    I8(i8),
+    /// An unsigned 16-bit integer.
+    U16(u16),
+    /// A signed 16-bit integer.
+    I16(i16),
+    /// An unsigned 32-bit integer.
+    U32(u32),
+    /// A signed 32-bit integer.
+    I32(i32),
+    /// An unsigned 64-bit integer.
+    U64(u64),
+    /// A signed 64-bit integer.
+    I64(i64),
+    /// A 32-bit float.
+    F32(f32),
+    /// A 64-bit float.
+    F64(f64),
+    /// Raw bytes.
+    Bytes(&'a [u8]),
+}
+
+/// A declarative description of a fixed-size record.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Schema {
+    record_len: usize,
+    fields: Vec<Field>,
+}
+
+impl Schema {
+    /// Creates a schema for records of `record_len` bytes with no fields.
+    pub fn new(record_len: usize) -> Self {
+        Schema {
+            record_len,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a field to the schema.
+    ///
+    /// Fields may overlap, and bytes not covered by any field are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field extends past the end of the record.
+    pub fn field(mut self, field: Field) -> Self {
+        assert!(field.offset.checked_add(field.ty.width()).is_some_and(|end| end <= self.record_len), "field extends past the end of the record");
+        self.fields.push(field);
+        self
+    }
+
+    /// Returns the length of each record.
+    pub fn record_len(&self) -> usize {
+        self.record_len
+    }
+
+    /// Returns the fields of the schema, in the order they were added.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+}
+
+/// A record read by a `SchemaReader`.
+#[derive(Clone, Copy, Debug)]
+pub struct SchemaRecord<'a> {
+    schema: &'a Schema,
+    buf: &'a [u8],
+}
+
+impl<'a> SchemaRecord<'a> {
+    /// Returns the value of the field named `name`, or `None` if the schema
+    /// has no such field.
+    pub fn get(&self, name: &str) -> Option<FieldValue<'a>> {
+        self.schema.fields.iter()
+            .find(|field| field.name == name)
+            .map(|field| field.value(self.buf))
+    }
+
+    /// Returns an iterator over the fields of the schema and their values.
+    pub fn fields(&self) -> impl Iterator<Item = (&'a Field, FieldValue<'a>)> + 'a {
+        let buf = self.buf;
+        self.schema.fields.iter().map(move |field| (field, field.value(buf)))
+    }
+
+    /// Returns the raw bytes of the record.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.buf
+    }
+}
+
+/// A reader of records described by a `Schema`.
+#[derive(Debug)]
+pub struct SchemaReader<R> {
+    reader: R,
+    schema: Schema,
+    buf: Vec<u8>,
+}
+
+impl<R> SchemaReader<R> {
+    /// Creates a reader of `schema` records from `reader`.
+    pub fn new(reader: R, schema: Schema) -> Self {
+        SchemaReader {
+            buf: vec![0; schema.record_len],
+            reader,
+            schema,
+        }
+    }
+
+    /// Returns the schema of the records.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> SchemaReader<R> {
+    /// Reads the next record, or returns `None` if no data was read.
+    ///
+    /// EOF is handled as in `ReadExactExt::read_exact_or_eof`.
+    pub fn read_record_or_eof(&mut self) -> io::Result<Option<SchemaRecord<'_>>> {
+        match self.reader.read_exact_or_eof(&mut self.buf)? {
+            true => Ok(Some(SchemaRecord {
+                schema: &self.schema,
+                buf: &self.buf,
+            })),
+            false => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Endian;
+    use super::{Field, FieldType, Schema, SchemaReader, FieldValue};
+
+    fn schema() -> Schema {
+        Schema::new(8)
+            .field(Field::new("kind", 0, FieldType::U8))
+            .field(Field::new("len", 1, FieldType::U16).endian(Endian::Big))
+            .field(Field::new("delta", 3, FieldType::I16))
+            .field(Field::new("tag", 5, FieldType::Bytes(3)))
+    }
+
+    #[test]
+    fn records() {
+        let data = [7, 0x01, 0x02, 0xfe, 0xff, b'a', b'b', b'c', 1];
+        let mut read = SchemaReader::new(&data[..], schema());
+
+        let record = read.read_record_or_eof().unwrap().unwrap();
+        assert_eq!(record.get("kind"), Some(FieldValue::U8(7)));
+        assert_eq!(record.get("len"), Some(FieldValue::U16(0x0102)));
+        assert_eq!(record.get("delta"), Some(FieldValue::I16(-2)));
+        assert_eq!(record.get("tag"), Some(FieldValue::Bytes(b"abc")));
+        assert_eq!(record.get("missing"), None);
+        assert_eq!(record.fields().count(), 4);
+        assert!(read.read_record_or_eof().is_err());
+        assert!(read.read_record_or_eof().unwrap().is_none());
+    }
+
+    #[test]
+    fn floats() {
+        let schema = Schema::new(12)
+            .field(Field::new("x", 0, FieldType::F32))
+            .field(Field::new("y", 4, FieldType::F64).endian(Endian::Big));
+        let mut data = 1.5f32.to_le_bytes().to_vec();
+        data.extend(&(-0.25f64).to_be_bytes());
+        let mut read = SchemaReader::new(&data[..], schema);
+
+        let record = read.read_record_or_eof().unwrap().unwrap();
+        assert_eq!(record.get("x"), Some(FieldValue::F32(1.5)));
+        assert_eq!(record.get("y"), Some(FieldValue::F64(-0.25)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_bounds() {
+        let _ = Schema::new(2).field(Field::new("x", 1, FieldType::U16));
+    }
+}
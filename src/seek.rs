@@ -0,0 +1,243 @@
+use crate::{io, unexpected_eof, ReadExactExt};
+
+/// An extension trait for readers that can seek.
+pub trait SeekReadExactExt {
+    /// Reads exactly the number of bytes to fill `buf`, or zero, seeking back
+    /// to the starting position unless `buf` was filled.
+    ///
+    /// This allows a record to be read speculatively, as when detecting a
+    /// file's format. If seeking back fails, that error is returned instead.
+    fn read_exact_or_rewind(&mut self, buf: &mut [u8]) -> io::Result<bool>;
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero, checking the
+    /// length of the stream first.
+    ///
+    /// If fewer than `buf.len()` bytes remain before the end of the stream,
+    /// this fails with `UnexpectedEof`, or returns `false` if none remain,
+    /// without reading anything. The stream length is found by seeking to
+    /// its end and back, so this is only meaningful for sources such as
+    /// files whose length is fixed.
+    fn read_exact_or_eof_checked(&mut self, buf: &mut [u8]) -> io::Result<bool>;
+
+    /// Creates an iterator over the `record_len` byte records between the
+    /// current position and the end of the stream, from last to first.
+    ///
+    /// Records are aligned to the current position. If the stream does not
+    /// end on a record boundary, the iterator first yields an `UnexpectedEof`
+    /// error for the truncated final record, and then goes on to yield the
+    /// complete records before it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `record_len` is zero.
+    #[cfg(feature = "std")]
+    fn rev_records(self, record_len: usize) -> io::Result<RevRecords<Self>> where Self: Sized;
+}
+
+impl<T: io::Read + io::Seek> SeekReadExactExt for T {
+    fn read_exact_or_rewind(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let start = self.stream_position()?;
+        let ret = self.read_exact_or_eof(buf);
+        if !matches!(ret, Ok(true)) {
+            self.seek(io::SeekFrom::Start(start))?;
+        }
+        ret
+    }
+
+    fn read_exact_or_eof_checked(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let start = self.stream_position()?;
+        let end = self.seek(io::SeekFrom::End(0))?;
+        self.seek(io::SeekFrom::Start(start))?;
+
+        match end.saturating_sub(start) {
+            _ if buf.is_empty() => Ok(true),
+            0 => Ok(false),
+            remaining if remaining < buf.len() as u64 => Err(short_record("stream", buf.len() as u64 - remaining)),
+            _ => self.read_exact_or_eof(buf),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn rev_records(mut self, record_len: usize) -> io::Result<RevRecords<Self>> {
+        assert!(record_len > 0, "record length must be non-zero");
+        let start = self.stream_position()?;
+        let len = self.seek(io::SeekFrom::End(0))?.saturating_sub(start);
+
+        Ok(RevRecords {
+            reader: self,
+            start,
+            record_len,
+            remaining: len / record_len as u64,
+            truncated: len % record_len as u64 != 0,
+        })
+    }
+}
+
+/// An extension trait for files that checks their length before reading.
+#[cfg(feature = "std")]
+pub trait FileReadExactExt {
+    /// Reads exactly the number of bytes to fill `buf`, or zero, checking the
+    /// length of the file first.
+    ///
+    /// This behaves like `SeekReadExactExt::read_exact_or_eof_checked`, but
+    /// finds the length from the file's metadata rather than by seeking. A
+    /// file that ends partway through the record fails with an
+    /// `UnexpectedEof` error that says how many bytes it is short by, such
+    /// as "file is 37 bytes short of a full record", without reading
+    /// anything.
+    fn read_exact_or_eof_prechecked(&mut self, buf: &mut [u8]) -> io::Result<bool>;
+}
+
+#[cfg(feature = "std")]
+impl FileReadExactExt for std::fs::File {
+    fn read_exact_or_eof_prechecked(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let len = self.metadata()?.len();
+        let pos = io::Seek::stream_position(self)?;
+
+        match len.saturating_sub(pos) {
+            _ if buf.is_empty() => Ok(true),
+            0 => Ok(false),
+            remaining if remaining < buf.len() as u64 => Err(short_record("file", buf.len() as u64 - remaining)),
+            _ => self.read_exact_or_eof(buf),
+        }
+    }
+}
+
+/// Returns the error for a stream that ends `short` bytes before the end of
+/// a record.
+#[cfg(feature = "std")]
+fn short_record(what: &str, short: u64) -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, format!("{what} is {short} bytes short of a full record"))
+}
+
+#[cfg(not(feature = "std"))]
+fn short_record(_: &str, _: u64) -> io::Error {
+    unexpected_eof()
+}
+
+/// An iterator over the fixed-size records of a stream, from last to first.
+///
+/// This is created by `SeekReadExactExt::rev_records`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct RevRecords<R> {
+    reader: R,
+    start: u64,
+    record_len: usize,
+    remaining: u64,
+    truncated: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R> RevRecords<R> {
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Unwraps this iterator, returning the underlying reader.
+    ///
+    /// The reader is left just past the last record yielded.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read + io::Seek> Iterator for RevRecords<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.truncated {
+            self.truncated = false;
+            return Some(Err(io::Error::new(io::ErrorKind::UnexpectedEof, "final record is truncated")))
+        }
+        if self.remaining == 0 {
+            return None
+        }
+
+        self.remaining -= 1;
+        let offset = self.start + self.remaining * self.record_len as u64;
+        let mut record = vec![0; self.record_len];
+        let ret = self.reader.seek(io::SeekFrom::Start(offset))
+            .and_then(|_| self.reader.read_exact_or_eof(&mut record));
+        let ret = match ret {
+            Ok(true) => Ok(record),
+            // the stream was shortened since its length was found
+            Ok(false) => Err(unexpected_eof()),
+            Err(e) => Err(e),
+        };
+        if ret.is_err() {
+            self.remaining = 0;
+        }
+        Some(ret)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = usize::try_from(self.remaining).unwrap_or(usize::MAX).saturating_add(usize::from(self.truncated));
+        (len, Some(len))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::fs::File;
+    use std::io::{Cursor, Read};
+    use super::{FileReadExactExt, SeekReadExactExt};
+
+    #[test]
+    fn prechecked() {
+        let path = std::env::temp_dir().join(format!("read_exact-prechecked-{}", std::process::id()));
+        std::fs::write(&path, [1, 2, 3]).unwrap();
+        let mut file = File::open(&path).unwrap();
+        let mut buf = [0; 2];
+
+        assert!(file.read_exact_or_eof_prechecked(&mut buf).unwrap());
+        let err = file.read_exact_or_eof_prechecked(&mut buf).unwrap_err();
+        assert_eq!(err.to_string(), "file is 1 bytes short of a full record");
+        assert!(file.read_exact_or_eof_prechecked(&mut [0]).unwrap());
+        assert!(!file.read_exact_or_eof_prechecked(&mut buf).unwrap());
+        drop(file);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rev_records() {
+        let mut read = Cursor::new(vec![0, 1, 2, 3, 4, 5, 6, 7]);
+        read.set_position(1);
+        let mut records = read.rev_records(2).unwrap();
+
+        assert_eq!(records.size_hint(), (4, Some(4)));
+        assert!(records.next().unwrap().is_err());
+        assert_eq!(records.next().unwrap().unwrap(), [5, 6]);
+        assert_eq!(records.next().unwrap().unwrap(), [3, 4]);
+        assert_eq!(records.next().unwrap().unwrap(), [1, 2]);
+        assert!(records.next().is_none());
+        assert_eq!(records.into_inner().position(), 3);
+    }
+
+    #[test]
+    fn rewind() {
+        let mut read = Cursor::new(vec![1, 2, 3]);
+        let mut buf = [0, 0];
+
+        assert!(read.read_exact_or_rewind(&mut buf).unwrap());
+        assert!(read.read_exact_or_rewind(&mut buf).is_err());
+        assert_eq!(read.position(), 2);
+        assert_eq!(read.read(&mut buf).unwrap(), 1);
+        assert!(!read.read_exact_or_rewind(&mut buf).unwrap());
+        assert_eq!(read.position(), 3);
+    }
+
+    #[test]
+    fn checked() {
+        let mut read = Cursor::new(vec![1, 2, 3]);
+        let mut buf = [0, 0];
+
+        assert!(read.read_exact_or_eof_checked(&mut buf).unwrap());
+        assert!(read.read_exact_or_eof_checked(&mut buf).is_err());
+        assert_eq!(read.position(), 2);
+        read.set_position(3);
+        assert!(!read.read_exact_or_eof_checked(&mut buf).unwrap());
+    }
+}
@@ -0,0 +1,179 @@
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+use ::serialport::{ClearBuffer, SerialPort};
+use crate::source::{fill_source, FillControl, FnSource, SliceBuf};
+use crate::{ReadExactStatus, TimeoutStatus};
+
+/// The timeouts of a frame read from a serial port.
+///
+/// Without any timeouts, a read waits indefinitely for each byte.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SerialTiming {
+    frame_timeout: Option<Duration>,
+    inter_byte_timeout: Option<Duration>,
+    flush_partial: bool,
+}
+
+impl SerialTiming {
+    /// Creates timing that waits indefinitely.
+    pub const fn new() -> Self {
+        SerialTiming {
+            frame_timeout: None,
+            inter_byte_timeout: None,
+            flush_partial: false,
+        }
+    }
+
+    /// Sets the longest a whole frame may take to arrive, starting from the
+    /// read.
+    pub const fn frame_timeout(mut self, timeout: Duration) -> Self {
+        self.frame_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the longest gap allowed between the bytes of a frame once its
+    /// first byte has arrived.
+    ///
+    /// A sender that stops partway through a frame, as when a sensor resets,
+    /// is then detected without waiting out the frame timeout.
+    pub const fn inter_byte_timeout(mut self, timeout: Duration) -> Self {
+        self.inter_byte_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether the port's input buffer is cleared after a partial frame
+    /// times out.
+    ///
+    /// This discards the rest of a frame that arrives late, so that the next
+    /// read starts closer to a frame boundary. The default is `false`.
+    pub const fn flush_partial(mut self, flush: bool) -> Self {
+        self.flush_partial = flush;
+        self
+    }
+}
+
+/// An extension trait for `serialport::SerialPort` types.
+pub trait SerialReadExactExt {
+    /// Reads exactly the number of bytes to fill `buf` within the timeouts
+    /// of `timing`, or zero.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, but returns
+    /// `TimeoutStatus::TimedOut` with the number of bytes read so far if a
+    /// timeout passes first. The port's timeout is adjusted across partial
+    /// reads, and restored to its original value before returning.
+    fn read_serial_frame_or_eof(&mut self, buf: &mut [u8], timing: &SerialTiming) -> io::Result<TimeoutStatus>;
+}
+
+impl<T: SerialPort + ?Sized> SerialReadExactExt for T {
+    fn read_serial_frame_or_eof(&mut self, buf: &mut [u8], timing: &SerialTiming) -> io::Result<TimeoutStatus> {
+        let original = self.timeout();
+        let ret = fill_frame(self, buf, timing, |port, timeout| port.set_timeout(timeout).map_err(io::Error::from));
+        let restored = self.set_timeout(original);
+
+        let ret = ret?;
+        restored?;
+        if timing.flush_partial && matches!(ret, TimeoutStatus::TimedOut { filled } if filled > 0) {
+            self.clear(ClearBuffer::Input)?;
+        }
+        Ok(ret)
+    }
+}
+
+/// The timeout used to wait indefinitely, which ports do not support
+/// directly.
+const FOREVER: Duration = Duration::from_secs(u32::MAX as u64);
+
+fn fill_frame<R, F>(reader: &mut R, buf: &mut [u8], timing: &SerialTiming, mut set_timeout: F) -> io::Result<TimeoutStatus>
+where
+    R: Read + ?Sized,
+    F: FnMut(&mut R, Duration) -> io::Result<()>,
+{
+    let len = buf.len();
+    let deadline = timing.frame_timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+    let mut buf = SliceBuf::new(buf);
+    let mut control = Frame { timed_out: false };
+    let mut filled = 0;
+    fill_source(&mut FnSource(|buf: &mut [u8]| {
+        let remaining = match deadline {
+            Some(deadline) => match deadline.saturating_duration_since(Instant::now()) {
+                Duration::ZERO => return Err(io::ErrorKind::TimedOut.into()),
+                remaining => remaining,
+            },
+            None => FOREVER,
+        };
+        let timeout = match timing.inter_byte_timeout {
+            Some(gap) if filled > 0 => gap.min(remaining),
+            _ => remaining,
+        };
+        set_timeout(reader, timeout)?;
+
+        let n = reader.read(buf)?;
+        filled += n;
+        Ok(n)
+    }), &mut buf, &mut control)?;
+
+    match control.timed_out {
+        true => Ok(TimeoutStatus::TimedOut { filled: buf.filled() }),
+        false => ReadExactStatus::new(buf.filled(), len).into_result().map(TimeoutStatus::Done),
+    }
+}
+
+/// The fill policy of `fill_frame`, which ends the fill when a timeout
+/// passes, setting `timed_out`.
+struct Frame {
+    timed_out: bool,
+}
+
+impl<B: ?Sized> FillControl<B, io::Error> for Frame {
+    fn end_on_error(&mut self, e: &io::Error, _: usize) -> bool {
+        self.timed_out = e.kind() == io::ErrorKind::TimedOut;
+        self.timed_out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::time::Duration;
+    use crate::testing::ScriptedReader;
+    use crate::TimeoutStatus;
+    use super::{fill_frame, SerialTiming, FOREVER};
+
+    #[test]
+    fn frame() {
+        let timing = SerialTiming::new()
+            .frame_timeout(Duration::from_secs(5))
+            .inter_byte_timeout(Duration::from_millis(10));
+        let mut read = ScriptedReader::new()
+            .data(&[1])
+            .data(&[2])
+            .data(&[3])
+            .error(io::ErrorKind::TimedOut)
+            .eof();
+        let mut buf = [0; 2];
+        let mut timeouts = Vec::new();
+        let mut fill = |read: &mut ScriptedReader, buf: &mut [u8]| fill_frame(read, buf, &timing, |_, timeout| {
+            timeouts.push(timeout);
+            Ok(())
+        });
+
+        assert_eq!(fill(&mut read, &mut buf).unwrap(), TimeoutStatus::Done(true));
+        assert_eq!(fill(&mut read, &mut buf).unwrap(), TimeoutStatus::TimedOut { filled: 1 });
+        assert_eq!(fill(&mut read, &mut buf).unwrap(), TimeoutStatus::Done(false));
+        assert!(timeouts[0] > Duration::from_secs(4));
+        assert_eq!(timeouts[1], Duration::from_millis(10));
+    }
+
+    #[test]
+    fn forever() {
+        let mut read = ScriptedReader::new().data(&[1, 2]);
+        let mut timeouts = Vec::new();
+
+        let ret = fill_frame(&mut read, &mut [0; 2], &SerialTiming::new(), |_, timeout| {
+            timeouts.push(timeout);
+            Ok(())
+        });
+        assert_eq!(ret.unwrap(), TimeoutStatus::Done(true));
+        assert_eq!(timeouts, [FOREVER]);
+    }
+}
@@ -0,0 +1,102 @@
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+use crate::{ReadExactExt, ReadExactStatus};
+
+/// A reader shared between threads that reads whole records at a time.
+///
+/// Each read holds the lock until its buffer is filled, so records read by
+/// different threads are never interleaved.
+#[derive(Debug)]
+pub struct SharedExactReader<R> {
+    reader: Arc<Mutex<R>>,
+}
+
+impl<R> SharedExactReader<R> {
+    /// Creates a new shared reader.
+    pub fn new(reader: R) -> Self {
+        SharedExactReader {
+            reader: Arc::new(Mutex::new(reader)),
+        }
+    }
+
+    /// Gets a reference to the shared underlying reader.
+    pub fn get_ref(&self) -> &Arc<Mutex<R>> {
+        &self.reader
+    }
+
+    fn lock(&self) -> io::Result<MutexGuard<'_, R>> {
+        self.reader.lock()
+            .map_err(|_| io::Error::other("shared reader lock poisoned"))
+    }
+}
+
+impl<R: io::Read> SharedExactReader<R> {
+    /// Reads exactly the number of bytes to fill `buf`, or zero, as with
+    /// `ReadExactExt::read_exact_or_eof`.
+    ///
+    /// Fails if another thread panicked while holding the lock.
+    pub fn read_exact_or_eof(&self, buf: &mut [u8]) -> io::Result<bool> {
+        self.lock()?.read_exact_or_eof(buf)
+    }
+
+    /// Reads exactly the number of bytes to fill `buf`, as with
+    /// `ReadExactExt::read_exact_or_eof_status`.
+    pub fn read_exact_or_eof_status(&self, buf: &mut [u8]) -> io::Result<ReadExactStatus> {
+        self.lock()?.read_exact_or_eof_status(buf)
+    }
+}
+
+impl<R> Clone for SharedExactReader<R> {
+    fn clone(&self) -> Self {
+        SharedExactReader {
+            reader: self.reader.clone(),
+        }
+    }
+}
+
+impl<R> From<Arc<Mutex<R>>> for SharedExactReader<R> {
+    fn from(reader: Arc<Mutex<R>>) -> Self {
+        SharedExactReader {
+            reader,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, thread};
+    use super::SharedExactReader;
+
+    /// Reads one byte at a time.
+    struct Slow<R>(R);
+
+    impl<R: io::Read> io::Read for Slow<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(1);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn records() {
+        let data: Vec<u8> = (0..200).collect();
+        let shared = SharedExactReader::new(Slow(io::Cursor::new(data)));
+
+        let workers: Vec<_> = (0..4).map(|_| {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                let mut buf = [0, 0];
+                let mut count = 0;
+                while shared.read_exact_or_eof(&mut buf).unwrap() {
+                    assert_eq!(buf[0] % 2, 0);
+                    assert_eq!(buf[1], buf[0] + 1);
+                    count += 1;
+                }
+                count
+            })
+        }).collect();
+
+        let total: usize = workers.into_iter().map(|worker| worker.join().unwrap()).sum();
+        assert_eq!(total, 100);
+    }
+}
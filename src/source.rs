@@ -0,0 +1,556 @@
+use crate::ReadExactStatus;
+
+/// A minimal source of bytes that exact reads can be driven over.
+///
+/// The fill loops of this crate are written against this trait, with an
+/// implementation for each I/O ecosystem it supports. Implementing it for
+/// another source, such as a handle of a foreign library, allows
+/// `read_exact_or_eof_source` to be used with it, and `FnSource` does so for
+/// a closure.
+pub trait Source {
+    /// The error returned by the source.
+    type Error;
+
+    /// Reads some bytes into `buf`, returning how many were read.
+    ///
+    /// Returning zero for a non-empty `buf` means EOF.
+    fn read_some(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+
+    /// Returns whether `error` only interrupted the read, which is then
+    /// retried.
+    ///
+    /// The default treats every error as fatal.
+    fn is_interrupted(error: &Self::Error) -> bool {
+        let _ = error;
+        false
+    }
+}
+
+impl<S: Source + ?Sized> Source for &mut S {
+    type Error = S::Error;
+
+    fn read_some(&mut self, buf: &mut [u8]) -> Result<usize, S::Error> {
+        (**self).read_some(buf)
+    }
+
+    fn is_interrupted(error: &S::Error) -> bool {
+        S::is_interrupted(error)
+    }
+}
+
+/// A source that reads by calling a closure.
+///
+/// This adapts a foreign read function, which typically returns a length or
+/// an error code, without a type of its own. If the closure returns
+/// `io::Error`s, those of kind `Interrupted` are retried as they are for a
+/// `Read`.
+#[derive(Clone, Copy, Debug)]
+pub struct FnSource<F>(pub F);
+
+impl<F: FnMut(&mut [u8]) -> Result<usize, E>, E: 'static> Source for FnSource<F> {
+    type Error = E;
+
+    fn read_some(&mut self, buf: &mut [u8]) -> Result<usize, E> {
+        (self.0)(buf)
+    }
+
+    fn is_interrupted(error: &E) -> bool {
+        is_io_interrupted(error)
+    }
+}
+
+/// Returns whether `error` is an `io::Error` of kind `Interrupted`.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+fn is_io_interrupted<E: 'static>(error: &E) -> bool {
+    (error as &dyn core::any::Any).downcast_ref::<crate::io::Error>()
+        .is_some_and(|e| e.kind() == crate::io::ErrorKind::Interrupted)
+}
+
+#[cfg(not(any(feature = "std", feature = "no-std-io")))]
+fn is_io_interrupted<E: 'static>(_: &E) -> bool {
+    false
+}
+
+/// Reads exactly the number of bytes to fill `buf` from `source`, reporting
+/// how much was read if EOF is encountered first.
+///
+/// This behaves like `ReadExactExt::read_exact_or_eof_status` for any
+/// `Source`, retrying the errors that `Source::is_interrupted` accepts.
+pub fn read_exact_or_eof_source<S: Source + ?Sized>(source: &mut S, buf: &mut [u8]) -> Result<ReadExactStatus, S::Error> {
+    let len = buf.len();
+    fill_slice(source, buf).map(|filled| ReadExactStatus::new(filled, len))
+}
+
+/// Reads exactly the number of bytes to fill `buf` by calling `read_some`,
+/// or zero.
+///
+/// This behaves like `ReadExactExt::read_exact_or_eof`, retrying
+/// `Interrupted` errors, for a transport that has no `Read` impl but reports
+/// `io::Error`s, such as a completion queue or an RPC that returns chunks.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub fn fill_exact_with<F: FnMut(&mut [u8]) -> crate::io::Result<usize>>(buf: &mut [u8], read_some: F) -> crate::io::Result<bool> {
+    let len = buf.len();
+    crate::fill_with(&mut FnSource(read_some), buf, &crate::ReadExactOpts::new(), &[])
+        .and_then(|filled| ReadExactStatus::new(filled, len).into_result())
+}
+
+/// Reads into `buf` until it is full or `source` reaches EOF, returning the
+/// number of bytes read.
+pub(crate) fn fill_slice<S: Source + ?Sized>(source: &mut S, buf: &mut [u8]) -> Result<usize, S::Error> {
+    let mut buf = SliceBuf::new(buf);
+    fill_source(source, &mut buf, &mut Plain).map(|()| buf.filled())
+}
+
+/// A buffer that a fill loop reads into from `S`.
+pub(crate) trait FillBuf<S: Source + ?Sized> {
+    /// Returns the total length of the buffer.
+    fn len(&self) -> usize;
+
+    /// Returns the number of bytes filled so far.
+    fn filled(&self) -> usize;
+
+    /// Issues a single read into the unfilled part of the buffer, advancing
+    /// past the bytes read.
+    fn read_from(&mut self, source: &mut S) -> Result<usize, S::Error>;
+}
+
+/// A slice being filled from its start.
+pub(crate) struct SliceBuf<'a> {
+    buf: &'a mut [u8],
+    filled: usize,
+}
+
+impl<'a> SliceBuf<'a> {
+    pub(crate) fn new(buf: &'a mut [u8]) -> Self {
+        Self::with_filled(buf, 0)
+    }
+
+    /// Wraps `buf`, of which the first `filled` bytes are already filled.
+    pub(crate) fn with_filled(buf: &'a mut [u8], filled: usize) -> Self {
+        assert!(filled <= buf.len(), "filled exceeds the buffer length");
+        SliceBuf {
+            buf,
+            filled,
+        }
+    }
+
+    pub(crate) fn filled(&self) -> usize {
+        self.filled
+    }
+
+    #[cfg(any(feature = "std", feature = "no-std-io"))]
+    pub(crate) fn status(&self) -> ReadExactStatus {
+        ReadExactStatus::new(self.filled, self.buf.len())
+    }
+
+    #[cfg(any(feature = "std", feature = "no-std-io"))]
+    pub(crate) fn is_full(&self) -> bool {
+        self.filled == self.buf.len()
+    }
+
+    /// Returns the bytes filled so far.
+    #[cfg(any(feature = "std", feature = "no-std-io"))]
+    pub(crate) fn filled_bytes(&self) -> &[u8] {
+        &self.buf[..self.filled]
+    }
+}
+
+impl<S: Source + ?Sized> FillBuf<S> for SliceBuf<'_> {
+    fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    fn filled(&self) -> usize {
+        self.filled
+    }
+
+    #[inline]
+    fn read_from(&mut self, source: &mut S) -> Result<usize, S::Error> {
+        let n = source.read_some(limit_read(&mut self.buf[self.filled..]))?;
+        self.filled += n;
+        Ok(n)
+    }
+}
+
+/// The policy of a fill loop, deciding when it ends and what it retries.
+///
+/// The defaults fill the whole buffer, retry every interrupted read, and
+/// treat any other error as fatal.
+pub(crate) trait FillControl<B: ?Sized, E> {
+    /// Returns whether `filled` bytes of a `len` byte buffer end the fill.
+    fn is_done(&self, filled: usize, len: usize) -> bool {
+        filled == len
+    }
+
+    /// Checks whether to issue another read after `reads` reads, returning
+    /// `Ok(false)` to end the fill.
+    fn before_read(&mut self, reads: u32, filled: usize, len: usize) -> Result<bool, E> {
+        let _ = (reads, filled, len);
+        Ok(true)
+    }
+
+    /// Inspects the buffer after a read that returned data, returning `true`
+    /// to end the fill.
+    fn after_read(&mut self, buf: &B) -> bool {
+        let _ = buf;
+        false
+    }
+
+    /// Counts an interrupted read, returning whether to retry it.
+    fn retry_interrupt(&mut self, interrupts: &mut u32) -> bool {
+        *interrupts += 1;
+        true
+    }
+
+    /// Counts a read that returned no data, returning whether to retry it
+    /// rather than end the fill at EOF.
+    fn retry_zero_read(&mut self, zero_reads: &mut u32) -> bool {
+        let _ = zero_reads;
+        false
+    }
+
+    /// Handles an error that is not retried as an interruption, returning
+    /// `Ok(true)` to retry the read, `Ok(false)` to go on to `end_on_error`,
+    /// or another error to fail the fill with in its place.
+    fn retry_error(&mut self, error: &E) -> Result<bool, E> {
+        let _ = error;
+        Ok(false)
+    }
+
+    /// Returns whether `error`, after `filled` bytes, ends the fill as EOF
+    /// does rather than failing it.
+    fn end_on_error(&mut self, error: &E, filled: usize) -> bool {
+        let _ = (error, filled);
+        false
+    }
+}
+
+/// The default fill policy.
+pub(crate) struct Plain;
+
+impl<B: ?Sized, E> FillControl<B, E> for Plain {}
+
+/// A fill policy that ends the fill once this many bytes are read.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub(crate) struct AtLeast(pub usize);
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+impl<B: ?Sized, E> FillControl<B, E> for AtLeast {
+    fn is_done(&self, filled: usize, _: usize) -> bool {
+        filled >= self.0
+    }
+}
+
+/// A fill policy that ends the fill at `WouldBlock`, setting `blocked`.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub(crate) struct NonBlocking {
+    pub blocked: bool,
+}
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+impl<B: ?Sized> FillControl<B, crate::io::Error> for NonBlocking {
+    fn end_on_error(&mut self, e: &crate::io::Error, _: usize) -> bool {
+        self.blocked = e.kind() == crate::io::ErrorKind::WouldBlock;
+        self.blocked
+    }
+}
+
+/// Reads into `buf` from `source` until `control` ends the fill or `source`
+/// reaches EOF.
+///
+/// This is the read loop behind every synchronous fill of this crate. The
+/// async reads have loops of their own, as they return to the executor
+/// between reads, and so do the `BufRead` helpers, which retry refills of
+/// the reader's own buffer rather than filling one, and the helpers built on
+/// calls that do not read, such as `recv`, `MSG_PEEK`, `splice` or writes.
+/// The number of bytes filled is left in `buf`, including when an error is
+/// returned.
+#[inline]
+pub(crate) fn fill_source<S, B, C>(source: &mut S, buf: &mut B, control: &mut C) -> Result<(), S::Error> where
+    S: Source + ?Sized,
+    B: FillBuf<S> + ?Sized,
+    C: FillControl<B, S::Error> + ?Sized,
+{
+    let len = buf.len();
+    let mut interrupts = 0;
+    let mut zero_reads = 0;
+    let mut reads = 0;
+    #[cfg(feature = "tracing")]
+    let span = ::tracing::trace_span!("fill",
+        len,
+        filled = ::tracing::field::Empty,
+        retries = ::tracing::field::Empty,
+        outcome = ::tracing::field::Empty,
+    ).entered();
+    #[cfg(feature = "metrics")]
+    let start = std::time::Instant::now();
+
+    let ret = 'fill: {
+        let filled = buf.filled();
+        if control.is_done(filled, len) {
+            break 'fill Ok(())
+        }
+        match control.before_read(reads, filled, len) {
+            Ok(true) => {},
+            Ok(false) => break 'fill Ok(()),
+            Err(e) => break 'fill Err(e),
+        }
+        reads += 1;
+
+        // a single read usually fills the whole buffer, so it skips the
+        // accounting of the loop
+        let mut ret = buf.read_from(source);
+        if ret.is_ok() && buf.filled() == len {
+            break 'fill Ok(())
+        }
+
+        loop {
+            match ret {
+                Ok(0) if control.retry_zero_read(&mut zero_reads) => {}
+                Ok(0) => break Ok(()),
+                Ok(_) => {
+                    zero_reads = 0;
+                    if control.after_read(buf) {
+                        break Ok(())
+                    }
+                },
+                Err(ref e) if S::is_interrupted(e) && control.retry_interrupt(&mut interrupts) => {}
+                Err(e) => match control.retry_error(&e) {
+                    Ok(true) => {},
+                    Ok(false) if control.end_on_error(&e, buf.filled()) => break Ok(()),
+                    Ok(false) => break Err(e),
+                    Err(e) => break Err(e),
+                },
+            }
+
+            let filled = buf.filled();
+            if control.is_done(filled, len) {
+                break Ok(())
+            }
+            match control.before_read(reads, filled, len) {
+                Ok(true) => {},
+                Ok(false) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+            reads += 1;
+            ret = buf.read_from(source);
+        }
+    };
+
+    let filled = ret.as_ref().ok().map(|()| buf.filled());
+    #[cfg(feature = "tracing")]
+    record_fill(&span, len, interrupts, filled);
+    #[cfg(feature = "metrics")]
+    record_metrics(len, start, filled);
+    let _ = filled;
+    ret
+}
+
+/// The largest read issued at once.
+///
+/// Some platforms fail reads larger than `INT_MAX`, and Linux shortens any
+/// read to just under 2 GiB, so larger fills are split into reads of at most
+/// the Linux limit.
+pub(crate) const MAX_READ: usize = 0x7fff_f000;
+
+/// Shortens `buf` to at most `MAX_READ` bytes.
+#[inline]
+pub(crate) fn limit_read(buf: &mut [u8]) -> &mut [u8] {
+    let len = buf.len().min(MAX_READ);
+    &mut buf[..len]
+}
+
+/// Records the result of a fill on its span, given the number of bytes
+/// filled unless it failed.
+#[cfg(feature = "tracing")]
+fn record_fill(span: &::tracing::Span, len: usize, retries: u32, filled: Option<usize>) {
+    span.record("retries", retries);
+    match filled {
+        Some(filled) => {
+            span.record("filled", filled);
+            span.record("outcome", match ReadExactStatus::new(filled, len) {
+                ReadExactStatus::Full => "full",
+                ReadExactStatus::CleanEof => "eof",
+                ReadExactStatus::PartialEof { .. } => "partial_eof",
+            });
+        },
+        None => {
+            span.record("outcome", "error");
+        },
+    }
+}
+
+/// Emits the metrics of a fill that began at `start`, given the number of
+/// bytes filled unless it failed.
+#[cfg(feature = "metrics")]
+fn record_metrics(len: usize, start: std::time::Instant, filled: Option<usize>) {
+    ::metrics::histogram!("read_exact.fill_duration").record(start.elapsed());
+    if let Some(filled) = filled {
+        ::metrics::counter!("read_exact.bytes").increment(filled as u64);
+        match ReadExactStatus::new(filled, len) {
+            ReadExactStatus::Full => {},
+            ReadExactStatus::CleanEof => ::metrics::counter!("read_exact.clean_eof").increment(1),
+            ReadExactStatus::PartialEof { .. } => ::metrics::counter!("read_exact.truncations").increment(1),
+        }
+    }
+}
+
+/// The `Source` of a `std::io::Read`, or of a `no_std_io2::io::Read` without
+/// the `std` feature.
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+pub(crate) struct IoSource<'a, R: ?Sized>(pub &'a mut R);
+
+#[cfg(any(feature = "std", feature = "no-std-io"))]
+impl<R: crate::io::Read + ?Sized> Source for IoSource<'_, R> {
+    type Error = crate::io::Error;
+
+    #[inline]
+    fn read_some(&mut self, buf: &mut [u8]) -> crate::io::Result<usize> {
+        self.0.read(buf)
+    }
+
+    fn is_interrupted(error: &crate::io::Error) -> bool {
+        error.kind() == crate::io::ErrorKind::Interrupted
+    }
+}
+
+/// A set of buffers being filled in order with vectored reads.
+#[cfg(feature = "std")]
+pub(crate) struct VectoredBuf<'a, 'b> {
+    bufs: &'a mut [std::io::IoSliceMut<'b>],
+    len: usize,
+    filled: usize,
+}
+
+#[cfg(feature = "std")]
+impl<'a, 'b> VectoredBuf<'a, 'b> {
+    pub(crate) fn new(mut bufs: &'a mut [std::io::IoSliceMut<'b>]) -> Self {
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        std::io::IoSliceMut::advance_slices(&mut bufs, 0);
+        VectoredBuf {
+            bufs,
+            len,
+            filled: 0,
+        }
+    }
+
+    pub(crate) fn filled(&self) -> usize {
+        self.filled
+    }
+
+    pub(crate) fn status(&self) -> ReadExactStatus {
+        ReadExactStatus::new(self.filled, self.len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + ?Sized> FillBuf<IoSource<'_, R>> for VectoredBuf<'_, '_> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn filled(&self) -> usize {
+        self.filled
+    }
+
+    fn read_from(&mut self, source: &mut IoSource<'_, R>) -> std::io::Result<usize> {
+        let n = source.0.read_vectored(self.bufs)?;
+        std::io::IoSliceMut::advance_slices(&mut self.bufs, n);
+        self.filled += n;
+        Ok(n)
+    }
+}
+
+/// The unfilled part of a `BorrowedCursor`, being filled with `read_buf`.
+#[cfg(all(feature = "std", feature = "nightly"))]
+pub(crate) struct CursorBuf<'a> {
+    cursor: std::io::BorrowedCursor<'a>,
+    len: usize,
+}
+
+#[cfg(all(feature = "std", feature = "nightly"))]
+impl<'a> CursorBuf<'a> {
+    pub(crate) fn new(cursor: std::io::BorrowedCursor<'a>) -> Self {
+        let len = cursor.capacity();
+        CursorBuf {
+            cursor,
+            len,
+        }
+    }
+
+    pub(crate) fn status(&self) -> ReadExactStatus {
+        ReadExactStatus::new(self.len - self.cursor.capacity(), self.len)
+    }
+}
+
+#[cfg(all(feature = "std", feature = "nightly"))]
+impl<R: std::io::Read + ?Sized> FillBuf<IoSource<'_, R>> for CursorBuf<'_> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn filled(&self) -> usize {
+        self.len - self.cursor.capacity()
+    }
+
+    fn read_from(&mut self, source: &mut IoSource<'_, R>) -> std::io::Result<usize> {
+        let written = self.cursor.written();
+        source.0.read_buf(self.cursor.reborrow())?;
+        Ok(self.cursor.written() - written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ReadExactStatus;
+    use super::{read_exact_or_eof_source, FnSource};
+
+    #[test]
+    fn closure() {
+        let mut data = &[1, 2, 3][..];
+        let mut source = FnSource(|buf: &mut [u8]| -> Result<usize, i32> {
+            let n = data.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&data[..n]);
+            data = &data[n..];
+            Ok(n)
+        });
+        let mut buf = [0; 2];
+
+        assert_eq!(read_exact_or_eof_source(&mut source, &mut buf), Ok(ReadExactStatus::Full));
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(read_exact_or_eof_source(&mut source, &mut buf), Ok(ReadExactStatus::PartialEof { filled: 1 }));
+        assert_eq!(read_exact_or_eof_source(&mut source, &mut buf), Ok(ReadExactStatus::CleanEof));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn fill_exact() {
+        use std::io;
+        use super::fill_exact_with;
+
+        let mut calls = 0;
+        let mut read_some = |buf: &mut [u8]| {
+            calls += 1;
+            match calls {
+                1 => Err(io::ErrorKind::Interrupted.into()),
+                2 | 3 => {
+                    buf[0] = calls;
+                    Ok(1)
+                },
+                _ => Ok(0),
+            }
+        };
+        let mut buf = [0; 2];
+
+        assert!(fill_exact_with(&mut buf, &mut read_some).unwrap());
+        assert_eq!(buf, [2, 3]);
+        assert!(!fill_exact_with(&mut buf, &mut read_some).unwrap());
+    }
+
+    #[test]
+    fn error() {
+        let mut source = FnSource(|_: &mut [u8]| Err(-5));
+
+        assert_eq!(read_exact_or_eof_source(&mut source, &mut [0]), Err(-5));
+    }
+}
@@ -0,0 +1,90 @@
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::ptr;
+use crate::{copy_fill, ReadExactStatus};
+
+/// Copies exactly `n` bytes between file descriptors, or zero, without
+/// copying through userspace where possible.
+///
+/// This behaves like `copy_exact`, but moves the data with `splice` if
+/// either end is a pipe, or `sendfile` if `reader` is a regular file. If the
+/// kernel supports neither for this pair of descriptors, the remaining bytes
+/// are copied through a buffer as usual.
+///
+/// This requires the `libc` feature, and is only available on Linux.
+pub fn splice_exact<R, W>(reader: &mut R, writer: &mut W, n: usize) -> io::Result<bool>
+where
+    R: io::Read + AsRawFd,
+    W: io::Write + AsRawFd,
+{
+    let (input, output) = (reader.as_raw_fd(), writer.as_raw_fd());
+    let mut sendfile = false;
+    let mut copied = 0;
+
+    while copied < n {
+        // large requests are capped by the kernel anyway
+        let len = (n - copied).min(1 << 30);
+        // SAFETY: both descriptors are borrowed for the duration of the call,
+        // and null offsets use and update their file positions
+        let ret = unsafe {
+            match sendfile {
+                false => libc::splice(input, ptr::null_mut(), output, ptr::null_mut(), len, libc::SPLICE_F_MOVE),
+                true => libc::sendfile(output, input, ptr::null_mut(), len),
+            }
+        };
+
+        match ret {
+            -1 => match io::Error::last_os_error() {
+                e if e.kind() == io::ErrorKind::Interrupted => {},
+                e if matches!(e.raw_os_error(), Some(libc::EINVAL | libc::ENOSYS)) => match sendfile {
+                    false => sendfile = true,
+                    true => {
+                        copied += copy_fill(reader, writer, n - copied)?;
+                        break
+                    },
+                },
+                e => return Err(e),
+            },
+            0 => break,
+            ret => copied += ret as usize,
+        }
+    }
+
+    ReadExactStatus::new(copied, n).into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use std::io::Read;
+    use std::os::unix::net::UnixStream;
+    use super::splice_exact;
+
+    #[test]
+    fn file() {
+        let path = std::env::temp_dir().join(format!("read_exact-splice-{}", std::process::id()));
+        fs::write(&path, [1, 2, 3]).unwrap();
+        let mut read = File::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let (mut write, mut peer) = UnixStream::pair().unwrap();
+        let mut buf = [0, 0];
+
+        assert!(splice_exact(&mut read, &mut write, 2).unwrap());
+        peer.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2]);
+        assert!(splice_exact(&mut read, &mut write, 2).is_err());
+        assert!(!splice_exact(&mut read, &mut write, 2).unwrap());
+    }
+
+    #[test]
+    fn fallback() {
+        let (mut read, mut source) = UnixStream::pair().unwrap();
+        let (mut write, mut peer) = UnixStream::pair().unwrap();
+        let mut buf = [0, 0];
+
+        std::io::Write::write_all(&mut source, &[1, 2]).unwrap();
+        assert!(splice_exact(&mut read, &mut write, 2).unwrap());
+        peer.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2]);
+    }
+}
@@ -0,0 +1,25 @@
+use std::io::{self, StdinLock};
+use crate::{ExactChunks, ReadExactExt, ReadExactOpts};
+
+/// Windows reports a closed stdin pipe as `BrokenPipe` on some paths, so it
+/// counts as EOF here as it would on Unix.
+const STDIN_OPTS: ReadExactOpts = ReadExactOpts::new().broken_pipe_eof(true);
+
+/// Reads exactly the number of bytes to fill `buf` from stdin, or zero.
+///
+/// This locks stdin for the duration of the read, and behaves like
+/// `ReadExactExt::read_exact_or_eof`. A closed pipe between records is EOF on
+/// every platform, as is Ctrl-Z at the start of a line of Windows console
+/// input.
+pub fn read_exact_or_eof_stdin(buf: &mut [u8]) -> io::Result<bool> {
+    io::stdin().lock().read_exact_or_eof_with(&STDIN_OPTS, buf)
+}
+
+/// Creates an iterator over `N` byte records of stdin.
+///
+/// Stdin is locked until the iterator is dropped. EOF is handled as in
+/// `read_exact_or_eof_stdin`, and the iterator otherwise behaves like
+/// `ReadExactExt::exact_chunks`.
+pub fn stdin_records<const N: usize>() -> ExactChunks<StdinLock<'static>, N> {
+    ExactChunks::with_opts(io::stdin().lock(), STDIN_OPTS)
+}
@@ -0,0 +1,293 @@
+use crate::{io, unexpected_eof, ReadExactExt};
+
+/// An extension trait for `Take` readers that consults the remaining limit.
+pub trait ReadExactTakeExt {
+    /// Reads exactly the number of bytes to fill `buf`, or zero, without
+    /// issuing reads that cannot complete.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, except that no
+    /// reads are issued if the remaining limit is too small to fill `buf`.
+    /// An exhausted limit is reported as EOF, and a limit that is nonzero but
+    /// less than `buf.len()` fails with `UnexpectedEof`, even if the inner
+    /// reader would have reached EOF first.
+    fn read_exact_or_eof_limited(&mut self, buf: &mut [u8]) -> io::Result<bool>;
+}
+
+impl<R: io::Read> ReadExactTakeExt for io::Take<R> {
+    fn read_exact_or_eof_limited(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        match self.limit() {
+            _ if buf.is_empty() => Ok(true),
+            0 => Ok(false),
+            limit if limit < buf.len() as u64 => Err(unexpected_eof()),
+            _ => self.read_exact_or_eof(buf),
+        }
+    }
+}
+
+/// A reader that enforces a byte budget across all of its reads.
+///
+/// Unlike `Take`, which reports an exhausted limit as EOF, a read that would
+/// go over the budget fails with a `QuotaExceeded` error, so that a client
+/// sending an unbounded number of valid frames can be told apart from one
+/// that closed the stream.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Quota<R> {
+    reader: R,
+    limit: u64,
+    consumed: u64,
+}
+
+#[cfg(feature = "std")]
+impl<R> Quota<R> {
+    /// Limits `reader` to `limit` bytes in total.
+    pub fn new(reader: R, limit: u64) -> Self {
+        Quota {
+            reader,
+            limit,
+            consumed: 0,
+        }
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
+
+    /// Returns the number of bytes left in the budget.
+    pub fn remaining(&self) -> u64 {
+        self.limit - self.consumed
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn exceeded(&self, requested: usize) -> io::Error {
+        io::Error::new(io::ErrorKind::QuotaExceeded, QuotaExceeded {
+            limit: self.limit,
+            requested,
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> Quota<R> {
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, except that it
+    /// fails with `QuotaExceeded` without reading anything if `buf` does not
+    /// fit in the remaining budget, so that no partial record is consumed.
+    pub fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        if buf.len() as u64 > self.remaining() {
+            return Err(self.exceeded(buf.len()))
+        }
+        ReadExactExt::read_exact_or_eof(self, buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> io::Read for Quota<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+        let len = match self.remaining() {
+            0 => return Err(self.exceeded(buf.len())),
+            remaining => buf.len().min(usize::try_from(remaining).unwrap_or(usize::MAX)),
+        };
+
+        let n = self.reader.read(&mut buf[..len])?;
+        self.consumed += n as u64;
+        Ok(n)
+    }
+}
+
+/// An error indicating that a read would go over the budget of a `Quota`.
+///
+/// This is the inner error of the `QuotaExceeded` errors returned by
+/// `Quota`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct QuotaExceeded {
+    /// The total budget of the reader.
+    pub limit: u64,
+    /// The number of bytes the read asked for.
+    pub requested: usize,
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "read of {} bytes exceeds the quota of {} bytes", self.requested, self.limit)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QuotaExceeded {}
+
+/// A reader of exactly `n` bytes of another reader.
+///
+/// This is created by `ReadExactExt::take_exact`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct TakeExact<R> {
+    reader: R,
+    len: u64,
+    remaining: u64,
+}
+
+#[cfg(feature = "std")]
+impl<R> TakeExact<R> {
+    pub(crate) fn new(reader: R, len: u64) -> Self {
+        TakeExact {
+            reader,
+            len,
+            remaining: len,
+        }
+    }
+
+    /// Returns the number of bytes left to read.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::Read> io::Read for TakeExact<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.remaining == 0 {
+            return Ok(0)
+        }
+        let len = buf.len().min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+
+        match self.reader.read(&mut buf[..len])? {
+            0 => Err(io::Error::new(io::ErrorKind::UnexpectedEof, TruncatedRegion {
+                expected: self.len,
+                available: self.len - self.remaining,
+            })),
+            n => {
+                self.remaining -= n as u64;
+                Ok(n)
+            },
+        }
+    }
+}
+
+/// An error indicating that a region ended before its expected length.
+///
+/// This is the inner error of the `UnexpectedEof` errors returned by
+/// `TakeExact`.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TruncatedRegion {
+    /// The length of the region.
+    pub expected: u64,
+    /// The number of bytes of the region that were available.
+    pub available: u64,
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for TruncatedRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "expected a region of {} bytes, but only {} were available", self.expected, self.available)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TruncatedRegion {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::io::{self, Read};
+    use crate::ReadExactExt;
+    use super::{Quota, QuotaExceeded, ReadExactTakeExt, TruncatedRegion};
+
+    /// Counts the reads issued against it.
+    struct Counted<R>(usize, R);
+
+    impl<R: Read> Read for Counted<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0 += 1;
+            self.1.read(buf)
+        }
+    }
+
+    #[test]
+    fn limited() {
+        let mut read = Counted(0, &[1, 2, 3, 4][..]).take(3);
+        let mut buf = [0, 0];
+
+        assert!(read.read_exact_or_eof_limited(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(read.read_exact_or_eof_limited(&mut buf).is_err());
+        assert_eq!(read.get_ref().0, 1);
+    }
+
+    #[test]
+    fn exhausted() {
+        let mut read = Counted(0, &[1, 2][..]).take(0);
+        let mut buf = [0, 0];
+
+        assert!(!read.read_exact_or_eof_limited(&mut buf).unwrap());
+        assert_eq!(read.get_ref().0, 0);
+    }
+
+    #[test]
+    fn quota() {
+        let mut read = Quota::new(&[1, 2, 3, 4, 5][..], 3);
+        let mut buf = [0, 0];
+
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        let err = read.read_exact_or_eof(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::QuotaExceeded);
+        assert_eq!(err.get_ref().unwrap().downcast_ref::<QuotaExceeded>(), Some(&QuotaExceeded { limit: 3, requested: 2 }));
+        assert_eq!(read.consumed(), 2);
+
+        assert!(ReadExactExt::read_exact_or_eof(&mut read, &mut buf).is_err());
+        assert_eq!(read.remaining(), 0);
+        assert_eq!(read.read(&mut [0]).unwrap_err().kind(), io::ErrorKind::QuotaExceeded);
+    }
+
+    #[test]
+    fn take_exact() {
+        let mut read = (&[1, 2, 3][..]).take_exact(2);
+        let mut buf = Vec::new();
+
+        assert_eq!(read.read_to_end(&mut buf).unwrap(), 2);
+        assert_eq!(read.remaining(), 0);
+        assert_eq!(read.into_inner(), [3]);
+
+        let mut read = (&[1, 2, 3][..]).take_exact(5);
+        let err = read.read_to_end(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(err.get_ref().unwrap().downcast_ref::<TruncatedRegion>(), Some(&TruncatedRegion { expected: 5, available: 3 }));
+        assert_eq!(err.to_string(), "expected a region of 5 bytes, but only 3 were available");
+    }
+}
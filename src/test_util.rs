@@ -0,0 +1,26 @@
+#[cfg(any(feature = "futures", feature = "tokio", feature = "embedded-io-async", feature = "http-body"))]
+use std::future::Future;
+#[cfg(any(feature = "futures", feature = "tokio", feature = "embedded-io-async", feature = "http-body"))]
+use std::sync::Arc;
+#[cfg(any(feature = "futures", feature = "tokio", feature = "embedded-io-async", feature = "http-body"))]
+use std::task::{Context, Poll, Wake, Waker};
+
+/// Polls `f` to completion on the current thread.
+#[cfg(any(feature = "futures", feature = "tokio", feature = "embedded-io-async", feature = "http-body"))]
+pub fn block_on<F: Future>(f: F) -> F::Output {
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let waker = Waker::from(Arc::new(Noop));
+    let mut cx = Context::from_waker(&waker);
+    let mut f = std::pin::pin!(f);
+
+    loop {
+        if let Poll::Ready(ret) = f.as_mut().poll(&mut cx) {
+            return ret
+        }
+    }
+}
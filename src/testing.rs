@@ -0,0 +1,676 @@
+//! Readers for testing code built on this crate.
+//!
+//! These produce the short reads, interruptions and truncations that real
+//! sources produce only occasionally, so that framing logic can be tested
+//! against them deterministically.
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::thread;
+use std::time::Duration;
+use crate::ReadExactExt;
+
+/// A single step of a `ScriptedReader`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Step {
+    /// Returns these bytes, across as many reads as it takes to consume them.
+    Data(Vec<u8>),
+    /// Returns `len` copies of `byte`, across as many reads as it takes to
+    /// consume them.
+    Pattern {
+        /// The byte to repeat.
+        byte: u8,
+        /// The number of bytes to return.
+        len: usize,
+    },
+    /// Returns EOF once.
+    Eof,
+    /// Fails once with an error of this kind.
+    Error(io::ErrorKind),
+}
+
+/// A reader that follows a script of chunks, EOFs and errors.
+///
+/// Each read returns at most one step's data, so each step models one short
+/// read. The reader returns EOF after the script ends.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedReader {
+    steps: VecDeque<Step>,
+}
+
+impl ScriptedReader {
+    /// Creates a reader with an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a step to the script.
+    pub fn step(mut self, step: Step) -> Self {
+        self.steps.push_back(step);
+        self
+    }
+
+    /// Appends a chunk of data to the script.
+    pub fn data(self, data: &[u8]) -> Self {
+        self.step(Step::Data(data.to_vec()))
+    }
+
+    /// Appends a chunk of `len` copies of `byte` to the script.
+    pub fn pattern(self, byte: u8, len: usize) -> Self {
+        self.step(Step::Pattern { byte, len })
+    }
+
+    /// Appends an EOF to the script.
+    pub fn eof(self) -> Self {
+        self.step(Step::Eof)
+    }
+
+    /// Appends an error to the script.
+    pub fn error(self, kind: io::ErrorKind) -> Self {
+        self.step(Step::Error(kind))
+    }
+
+    /// Returns whether every step of the script has been consumed.
+    pub fn is_done(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl Read for ScriptedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+
+        let (n, done) = match self.steps.front_mut() {
+            None => return Ok(0),
+            Some(Step::Data(data)) => {
+                let n = data.len().min(buf.len());
+                buf[..n].copy_from_slice(&data[..n]);
+                data.drain(..n);
+                (n, data.is_empty())
+            },
+            Some(Step::Pattern { byte, len }) => {
+                let n = (*len).min(buf.len());
+                buf[..n].fill(*byte);
+                *len -= n;
+                (n, *len == 0)
+            },
+            Some(Step::Eof) => (0, true),
+            Some(Step::Error(kind)) => {
+                let kind = *kind;
+                self.steps.pop_front();
+                return Err(io::Error::new(kind, "scripted error"))
+            },
+        };
+
+        if done {
+            self.steps.pop_front();
+        }
+        Ok(n)
+    }
+}
+
+/// A reader that injects faults into another reader on a seeded schedule.
+///
+/// Each read may fail with `Interrupted`, `WouldBlock` or a transient
+/// `Other` error, or may permanently truncate the stream by returning EOF
+/// early, each with a configured probability. Reads that go through may also
+/// be shortened. The same seed always produces the same schedule.
+#[derive(Clone, Debug)]
+pub struct FaultInjector<R> {
+    reader: R,
+    rng: Rng,
+    interrupted: f64,
+    would_block: f64,
+    other: f64,
+    truncate: f64,
+    short_reads: bool,
+    truncated: bool,
+}
+
+impl<R> FaultInjector<R> {
+    /// Creates an injector that injects no faults until configured.
+    pub fn new(reader: R, seed: u64) -> Self {
+        FaultInjector {
+            reader,
+            rng: Rng::new(seed),
+            interrupted: 0.0,
+            would_block: 0.0,
+            other: 0.0,
+            truncate: 0.0,
+            short_reads: false,
+            truncated: false,
+        }
+    }
+
+    /// Sets the probability that a read fails with `Interrupted`.
+    pub fn interrupted(mut self, probability: f64) -> Self {
+        self.interrupted = probability;
+        self
+    }
+
+    /// Sets the probability that a read fails with `WouldBlock`.
+    pub fn would_block(mut self, probability: f64) -> Self {
+        self.would_block = probability;
+        self
+    }
+
+    /// Sets the probability that a read fails with a transient `Other` error.
+    pub fn other(mut self, probability: f64) -> Self {
+        self.other = probability;
+        self
+    }
+
+    /// Sets the probability that a read ends the stream early.
+    pub fn truncate(mut self, probability: f64) -> Self {
+        self.truncate = probability;
+        self
+    }
+
+    /// Sets whether reads are shortened to a random length.
+    pub fn short_reads(mut self, short_reads: bool) -> Self {
+        self.short_reads = short_reads;
+        self
+    }
+
+    /// Returns whether the stream has been truncated.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this injector, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+}
+
+/// The seeded generator behind the schedules of the simulated readers.
+#[derive(Clone, Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // splitmix64 spreads nearby seeds apart without merging any of them,
+        // except that xorshift gets stuck at zero
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        match z ^ (z >> 31) {
+            0 => Rng(0x9e37_79b9_7f4a_7c15),
+            state => Rng(state),
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        probability > 0.0 && ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+}
+
+impl<R: Read> Read for FaultInjector<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.truncated || buf.is_empty() {
+            return Ok(0)
+        }
+
+        if self.rng.chance(self.interrupted) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "injected interrupt"))
+        }
+        if self.rng.chance(self.would_block) {
+            return Err(io::Error::new(io::ErrorKind::WouldBlock, "injected would block"))
+        }
+        if self.rng.chance(self.other) {
+            return Err(io::Error::other("injected error"))
+        }
+        if self.rng.chance(self.truncate) {
+            self.truncated = true;
+            return Ok(0)
+        }
+
+        let len = match self.short_reads {
+            true => 1 + (self.rng.next() % buf.len() as u64) as usize,
+            false => buf.len(),
+        };
+        self.reader.read(&mut buf[..len])
+    }
+}
+
+/// A reader that delivers another reader's data as a slow network would.
+///
+/// Each read waits for a configured latency plus a random jitter, returns at
+/// most a random fragment of the requested length, and then waits as long
+/// as the bytes returned would take to arrive at a configured bandwidth. The
+/// same seed always produces the same fragments and delays.
+#[derive(Clone, Debug)]
+pub struct Simulated<R> {
+    reader: R,
+    rng: Rng,
+    latency: Duration,
+    jitter: Duration,
+    bandwidth: Option<u64>,
+    max_fragment: Option<usize>,
+}
+
+impl<R> Simulated<R> {
+    /// Creates a simulator that delivers data immediately until configured.
+    pub fn new(reader: R, seed: u64) -> Self {
+        Simulated {
+            reader,
+            rng: Rng::new(seed),
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            bandwidth: None,
+            max_fragment: None,
+        }
+    }
+
+    /// Sets the delay before each read.
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Sets the most random delay that is added to the latency of each read.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Limits delivery to `rate` bytes per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is zero.
+    pub fn bandwidth(mut self, rate: u64) -> Self {
+        assert!(rate > 0, "rate must be non-zero");
+        self.bandwidth = Some(rate);
+        self
+    }
+
+    /// Fragments reads to a random length of at most `max` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub fn max_fragment(mut self, max: usize) -> Self {
+        assert!(max > 0, "max fragment must be non-zero");
+        self.max_fragment = Some(max);
+        self
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this simulator, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Read for Simulated<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+
+        let len = match self.max_fragment {
+            Some(max) => 1 + (self.rng.next() % buf.len().min(max) as u64) as usize,
+            None => buf.len(),
+        };
+        let jitter = match self.jitter.as_nanos() as u64 {
+            0 => Duration::ZERO,
+            jitter => Duration::from_nanos(self.rng.next() % (jitter + 1)),
+        };
+        thread::sleep(self.latency + jitter);
+
+        let n = self.reader.read(&mut buf[..len])?;
+        if let Some(rate) = self.bandwidth {
+            thread::sleep(Duration::from_secs_f64(n as f64 / rate as f64));
+        }
+        Ok(n)
+    }
+}
+
+/// A reader that records the outcome of every read of another reader.
+///
+/// The resulting `Trace` can be saved with `Trace::write_to` and replayed
+/// later with `Trace::replay`, so that a framing bug seen on a production
+/// stream can be reproduced offline with the same short reads, errors and
+/// EOFs. The messages of errors are not recorded, only their kinds.
+#[derive(Debug)]
+pub struct Recording<R> {
+    reader: R,
+    trace: Trace,
+}
+
+impl<R> Recording<R> {
+    /// Wraps a reader with an empty trace.
+    pub fn new(reader: R) -> Self {
+        Recording {
+            reader,
+            trace: Trace::default(),
+        }
+    }
+
+    /// Returns the trace recorded so far.
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+
+    /// Takes the trace recorded so far, leaving an empty one.
+    pub fn take_trace(&mut self) -> Trace {
+        std::mem::take(&mut self.trace)
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Read for Recording<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let ret = self.reader.read(buf);
+        self.trace.steps.push(match ret {
+            Ok(0) => Step::Eof,
+            Ok(n) => Step::Data(buf[..n].to_vec()),
+            Err(ref e) => Step::Error(e.kind()),
+        });
+        ret
+    }
+}
+
+/// The error kinds that a `Trace` can encode, by their index.
+///
+/// Other kinds are encoded as `Other`.
+const TRACE_KINDS: &[io::ErrorKind] = &[
+    io::ErrorKind::Other,
+    io::ErrorKind::NotFound,
+    io::ErrorKind::PermissionDenied,
+    io::ErrorKind::ConnectionRefused,
+    io::ErrorKind::ConnectionReset,
+    io::ErrorKind::ConnectionAborted,
+    io::ErrorKind::NotConnected,
+    io::ErrorKind::AddrInUse,
+    io::ErrorKind::AddrNotAvailable,
+    io::ErrorKind::BrokenPipe,
+    io::ErrorKind::AlreadyExists,
+    io::ErrorKind::WouldBlock,
+    io::ErrorKind::InvalidInput,
+    io::ErrorKind::InvalidData,
+    io::ErrorKind::TimedOut,
+    io::ErrorKind::WriteZero,
+    io::ErrorKind::Interrupted,
+    io::ErrorKind::Unsupported,
+    io::ErrorKind::UnexpectedEof,
+    io::ErrorKind::OutOfMemory,
+];
+
+/// The outcomes of the reads of a `Recording`.
+///
+/// Traces are encoded as a sequence of steps, each a tag byte followed by a
+/// little-endian `u32` length and the data for a read that returned data, or
+/// by the index of the error kind for a read that failed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Trace {
+    steps: Vec<Step>,
+}
+
+impl Trace {
+    /// Returns the recorded steps, one for each read.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Creates a reader that replays the trace.
+    pub fn replay(&self) -> Replay {
+        Replay {
+            script: self.steps.iter().cloned().fold(ScriptedReader::new(), ScriptedReader::step),
+        }
+    }
+
+    /// Encodes the trace to `writer`.
+    pub fn write_to<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        for step in &self.steps {
+            let data = match *step {
+                Step::Data(ref data) => data.clone(),
+                Step::Pattern { byte, len } => vec![byte; len],
+                Step::Eof => {
+                    writer.write_all(&[1])?;
+                    continue
+                },
+                Step::Error(kind) => {
+                    let index = TRACE_KINDS.iter().position(|&k| k == kind).unwrap_or(0);
+                    writer.write_all(&[2, index as u8])?;
+                    continue
+                },
+            };
+
+            let len = u32::try_from(data.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "trace step exceeds u32::MAX bytes"))?;
+            writer.write_all(&[0])?;
+            writer.write_all(&len.to_le_bytes())?;
+            writer.write_all(&data)?;
+        }
+        Ok(())
+    }
+
+    /// Decodes a trace written by `write_to`, reading `reader` to EOF.
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut steps = Vec::new();
+
+        while let Some(tag) = reader.read_u8_or_eof()? {
+            steps.push(match tag {
+                0 => {
+                    let len = reader.read_u32_le_or_eof()?.ok_or_else(crate::unexpected_eof)?;
+                    Step::Data(reader.read_exact_to_vec(len as usize)?.ok_or_else(crate::unexpected_eof)?)
+                },
+                1 => Step::Eof,
+                2 => {
+                    let index = reader.read_u8_or_eof()?.ok_or_else(crate::unexpected_eof)?;
+                    Step::Error(*TRACE_KINDS.get(usize::from(index)).ok_or_else(|| invalid("unknown error kind in trace"))?)
+                },
+                _ => return Err(invalid("unknown trace step")),
+            });
+        }
+        Ok(Trace { steps })
+    }
+}
+
+/// A reader that replays a `Trace`.
+///
+/// Each read returns the outcome of the recorded read it corresponds to, as
+/// long as it is given a buffer at least as large as the original. The
+/// reader returns EOF after the trace ends.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    script: ScriptedReader,
+}
+
+impl Replay {
+    /// Returns whether every step of the trace has been replayed.
+    pub fn is_done(&self) -> bool {
+        self.script.is_done()
+    }
+}
+
+impl Read for Replay {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.script.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+    use std::time::{Duration, Instant};
+    use crate::ReadExactExt;
+    use super::{FaultInjector, Recording, Rng, ScriptedReader, Simulated, Step, Trace};
+
+    #[test]
+    fn seeds() {
+        // seeds that differ only in their lowest bit give distinct schedules
+        assert_ne!(Rng::new(0).next(), Rng::new(1).next());
+        assert_ne!(Rng::new(2).next(), Rng::new(3).next());
+    }
+
+    #[test]
+    fn script() {
+        let mut read = ScriptedReader::new()
+            .data(&[1, 2, 3])
+            .pattern(0xff, 2)
+            .eof()
+            .error(io::ErrorKind::Other);
+        let mut buf = [0; 2];
+
+        assert_eq!(read.read(&mut buf).unwrap(), 2);
+        assert_eq!(read.read(&mut buf).unwrap(), 1);
+        assert_eq!(read.read(&mut buf).unwrap(), 2);
+        assert_eq!(buf, [0xff, 0xff]);
+        assert_eq!(read.read(&mut buf).unwrap(), 0);
+        assert!(read.read(&mut buf).is_err());
+        assert!(read.is_done());
+        assert_eq!(read.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn short_reads() {
+        let mut read = ScriptedReader::new()
+            .data(&[1])
+            .error(io::ErrorKind::Interrupted)
+            .data(&[2, 3])
+            .eof()
+            .data(&[4]);
+        let mut buf = [0; 3];
+
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2, 3]);
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+        assert!(read.read_exact_or_eof(&mut buf).is_err());
+    }
+
+    #[test]
+    fn faults() {
+        let data: Vec<u8> = (0..=255).collect();
+        let mut read = FaultInjector::new(&data[..], 1)
+            .interrupted(0.5)
+            .short_reads(true);
+        let mut buf = [0; 16];
+
+        for chunk in data.chunks(16) {
+            assert!(read.read_exact_or_eof(&mut buf).unwrap());
+            assert_eq!(buf, chunk);
+        }
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn truncate() {
+        let mut read = FaultInjector::new(&[0; 64][..], 2).truncate(1.0);
+        let mut buf = [0; 16];
+
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+        assert!(read.is_truncated());
+    }
+
+    #[test]
+    fn deterministic() {
+        let schedule = |seed| {
+            let mut read = FaultInjector::new(&[0; 64][..], seed).other(0.5);
+            (0..16).map(|_| read.read(&mut [0; 1]).is_ok()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(schedule(3), schedule(3));
+    }
+
+    #[test]
+    fn record_replay() {
+        let source = ScriptedReader::new().data(&[1]).error(io::ErrorKind::TimedOut).data(&[2, 3, 4]).eof();
+        let mut read = Recording::new(source);
+        let mut buf = [0; 2];
+
+        assert!(read.read_exact_or_eof(&mut buf).is_err());
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert!(read.read_exact_or_eof(&mut buf).is_err());
+        assert_eq!(read.trace().steps(), [
+            Step::Data(vec![1]),
+            Step::Error(io::ErrorKind::TimedOut),
+            Step::Data(vec![2, 3]),
+            Step::Data(vec![4]),
+            Step::Eof,
+        ]);
+
+        let trace = read.take_trace();
+        let mut encoded = Vec::new();
+        trace.write_to(&mut encoded).unwrap();
+        assert_eq!(Trace::read_from(&encoded[..]).unwrap(), trace);
+        assert!(read.trace().steps().is_empty());
+
+        let mut replay = trace.replay();
+        assert_eq!(replay.read_exact_or_eof(&mut buf).unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert!(replay.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [2, 3]);
+        assert!(replay.read_exact_or_eof(&mut buf).is_err());
+        assert!(replay.is_done());
+    }
+
+    #[test]
+    fn trace_invalid() {
+        assert_eq!(Trace::read_from(&[0, 2, 0, 0, 0, 1][..]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(Trace::read_from(&[3][..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn simulated() {
+        let data: Vec<u8> = (0..64).collect();
+        let mut read = Simulated::new(&data[..], 4)
+            .latency(Duration::from_micros(100))
+            .jitter(Duration::from_micros(100))
+            .bandwidth(4000)
+            .max_fragment(3);
+        let mut buf = [0; 16];
+        let start = Instant::now();
+
+        for chunk in data.chunks(16) {
+            assert!(read.read_exact_or_eof(&mut buf).unwrap());
+            assert_eq!(buf, chunk);
+        }
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(16));
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::{self, Read};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A reader limited to a bandwidth by a token bucket.
+///
+/// Reads sleep until enough bandwidth is available rather than returning
+/// early, so exact reads through `ReadExactExt` still fill whole records,
+/// just more slowly.
+#[derive(Debug)]
+pub struct Throttled<R> {
+    reader: R,
+    rate: u64,
+    burst: u64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl<R> Throttled<R> {
+    /// Limits `reader` to `rate` bytes per second, allowing bursts of up to
+    /// `burst` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` or `burst` is zero.
+    pub fn new(reader: R, rate: u64, burst: u64) -> Self {
+        assert!(rate > 0 && burst > 0, "rate and burst must be non-zero");
+        Throttled {
+            reader,
+            rate,
+            burst,
+            tokens: burst as f64,
+            refilled: Instant::now(),
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate as f64).min(self.burst as f64);
+        self.refilled = now;
+    }
+}
+
+impl<R: Read> Read for Throttled<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+
+        // wait for as much of the read as a single burst allows
+        let wanted = buf.len().min(self.burst as usize) as f64;
+        self.refill();
+        if self.tokens < wanted {
+            thread::sleep(Duration::from_secs_f64((wanted - self.tokens) / self.rate as f64));
+            self.refill();
+        }
+
+        let len = (self.tokens as usize).clamp(1, buf.len());
+        let n = self.reader.read(&mut buf[..len])?;
+        self.tokens -= n as f64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::ReadExactExt;
+    use super::Throttled;
+
+    #[test]
+    fn throttled() {
+        let mut read = Throttled::new(&[0; 40][..], 1000, 10);
+        let mut buf = [0; 20];
+
+        let start = Instant::now();
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(25));
+    }
+}
@@ -0,0 +1,302 @@
+use std::io::{self, Read};
+use std::net::TcpStream;
+#[cfg(all(feature = "libc", unix))]
+use std::os::unix::io::AsRawFd;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::time::{Duration, Instant};
+#[cfg(all(feature = "libc", unix))]
+use crate::source::{FillControl, IoSource};
+use crate::source::{fill_source, FnSource, Plain, SliceBuf};
+use crate::ReadExactStatus;
+#[cfg(all(feature = "libc", unix))]
+use crate::TimeoutStatus;
+
+/// An extension trait for sockets that support read timeouts.
+pub trait ReadExactTimeoutExt {
+    /// Reads exactly the number of bytes to fill `buf` within `timeout`, or
+    /// zero.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, but fails with
+    /// `TimedOut` if the buffer is not filled before the deadline. The
+    /// socket's read timeout is adjusted across partial reads, and restored
+    /// to its original value before returning.
+    fn read_exact_or_eof_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<bool>;
+}
+
+macro_rules! impl_timeout {
+    ($ty:ty) => {
+        impl ReadExactTimeoutExt for $ty {
+            fn read_exact_or_eof_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<bool> {
+                let original = self.read_timeout()?;
+                let len = buf.len();
+                let filled = fill_timeout(self, buf, timeout, |s, timeout| s.set_read_timeout(timeout));
+                let restored = self.set_read_timeout(original);
+
+                let filled = filled?;
+                restored?;
+                ReadExactStatus::new(filled, len).into_result()
+            }
+        }
+    };
+}
+
+impl_timeout!(TcpStream);
+#[cfg(unix)]
+impl_timeout!(UnixStream);
+
+/// An extension trait for file descriptors, including pipes and character
+/// devices that do not support socket timeouts.
+#[cfg(all(feature = "libc", unix))]
+pub trait ReadExactPollExt {
+    /// Reads exactly the number of bytes to fill `buf` before `deadline`, or
+    /// zero.
+    ///
+    /// Each read waits for the descriptor with `poll(2)` first, so this works
+    /// with any descriptor that `poll` supports. If the deadline passes first,
+    /// this returns `TimeoutStatus::TimedOut` with the number of bytes read so
+    /// far.
+    fn read_exact_or_eof_deadline(&mut self, buf: &mut [u8], deadline: Instant) -> io::Result<TimeoutStatus>;
+
+    /// Reads exactly the number of bytes to fill `buf` from a nonblocking
+    /// descriptor, or zero, waiting whenever it would block.
+    ///
+    /// Reads are issued directly, and only a `WouldBlock` error waits for the
+    /// descriptor with `poll(2)`, so that synchronous record logic can be used
+    /// with a nonblocking socket without spinning. With a `deadline`, this
+    /// returns `TimeoutStatus::TimedOut` with the number of bytes read so far
+    /// if it would still block once the deadline passes. Without one, it waits
+    /// indefinitely.
+    fn read_exact_or_eof_wait(&mut self, buf: &mut [u8], deadline: Option<Instant>) -> io::Result<TimeoutStatus>;
+}
+
+#[cfg(all(feature = "libc", unix))]
+impl<T: Read + AsRawFd + ?Sized> ReadExactPollExt for T {
+    fn read_exact_or_eof_deadline(&mut self, buf: &mut [u8], deadline: Instant) -> io::Result<TimeoutStatus> {
+        let mut control = Deadline { fd: self.as_raw_fd(), deadline, timed_out: false };
+        let len = buf.len();
+        let mut buf = SliceBuf::new(buf);
+        fill_source(&mut IoSource(self), &mut buf, &mut control)?;
+
+        timeout_status(buf.filled(), len, control.timed_out)
+    }
+
+    fn read_exact_or_eof_wait(&mut self, buf: &mut [u8], deadline: Option<Instant>) -> io::Result<TimeoutStatus> {
+        let mut control = Wait { fd: self.as_raw_fd(), deadline, timed_out: false };
+        let len = buf.len();
+        let mut buf = SliceBuf::new(buf);
+        fill_source(&mut IoSource(self), &mut buf, &mut control)?;
+
+        timeout_status(buf.filled(), len, control.timed_out)
+    }
+}
+
+#[cfg(all(feature = "libc", unix))]
+fn timeout_status(filled: usize, len: usize, timed_out: bool) -> io::Result<TimeoutStatus> {
+    match timed_out {
+        true => Ok(TimeoutStatus::TimedOut { filled }),
+        false => ReadExactStatus::new(filled, len).into_result().map(TimeoutStatus::Done),
+    }
+}
+
+/// The fill policy of `read_exact_or_eof_deadline`, which polls before each
+/// read, setting `timed_out` if the deadline passes first.
+#[cfg(all(feature = "libc", unix))]
+struct Deadline {
+    fd: std::os::unix::io::RawFd,
+    deadline: Instant,
+    timed_out: bool,
+}
+
+#[cfg(all(feature = "libc", unix))]
+impl<B: ?Sized> FillControl<B, io::Error> for Deadline {
+    fn before_read(&mut self, _: u32, _: usize, _: usize) -> io::Result<bool> {
+        loop {
+            let remaining = self.deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                self.timed_out = true;
+                return Ok(false)
+            }
+            if poll_readable(self.fd, Some(remaining))? {
+                return Ok(true)
+            }
+        }
+    }
+
+    fn retry_error(&mut self, e: &io::Error) -> io::Result<bool> {
+        Ok(e.kind() == io::ErrorKind::WouldBlock)
+    }
+}
+
+/// The fill policy of `read_exact_or_eof_wait`, which polls only once a read
+/// would block, setting `timed_out` if it still would at the deadline.
+#[cfg(all(feature = "libc", unix))]
+struct Wait {
+    fd: std::os::unix::io::RawFd,
+    deadline: Option<Instant>,
+    timed_out: bool,
+}
+
+#[cfg(all(feature = "libc", unix))]
+impl<B: ?Sized> FillControl<B, io::Error> for Wait {
+    fn retry_error(&mut self, e: &io::Error) -> io::Result<bool> {
+        if e.kind() != io::ErrorKind::WouldBlock {
+            return Ok(false)
+        }
+        let remaining = match self.deadline {
+            Some(deadline) => match deadline.saturating_duration_since(Instant::now()) {
+                Duration::ZERO => {
+                    self.timed_out = true;
+                    return Ok(false)
+                },
+                remaining => Some(remaining),
+            },
+            None => None,
+        };
+        poll_readable(self.fd, remaining)?;
+        Ok(true)
+    }
+
+    fn end_on_error(&mut self, _: &io::Error, _: usize) -> bool {
+        self.timed_out
+    }
+}
+
+/// Waits up to `timeout` for `fd` to become readable, or to hang up, or
+/// indefinitely without a timeout.
+#[cfg(all(feature = "libc", unix))]
+fn poll_readable(fd: std::os::unix::io::RawFd, timeout: Option<Duration>) -> io::Result<bool> {
+    let mut pollfd = ::libc::pollfd {
+        fd,
+        events: ::libc::POLLIN,
+        revents: 0,
+    };
+    // round up, so that the final poll does not return just before the
+    // deadline and spin
+    let millis = timeout.map_or(-1, |timeout| timeout.as_nanos().div_ceil(1_000_000).min(::libc::c_int::MAX as u128) as ::libc::c_int);
+
+    // SAFETY: `pollfd` is a valid array of one entry for the duration of the call
+    match unsafe { ::libc::poll(&mut pollfd, 1, millis) } {
+        -1 => match io::Error::last_os_error() {
+            e if e.kind() == io::ErrorKind::Interrupted => Ok(false),
+            e => Err(e),
+        },
+        ready => Ok(ready > 0),
+    }
+}
+
+fn fill_timeout<R, F>(reader: &mut R, buf: &mut [u8], timeout: Duration, set_timeout: F) -> io::Result<usize>
+where
+    R: Read,
+    F: Fn(&R, Option<Duration>) -> io::Result<()>,
+{
+    let deadline = Instant::now().checked_add(timeout);
+    let mut buf = SliceBuf::new(buf);
+    fill_source(&mut FnSource(|buf: &mut [u8]| {
+        let remaining = match deadline {
+            Some(deadline) => match deadline.saturating_duration_since(Instant::now()) {
+                Duration::ZERO => return Err(timed_out()),
+                remaining => Some(remaining),
+            },
+            None => None,
+        };
+        set_timeout(reader, remaining)?;
+
+        reader.read(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => timed_out(),
+            _ => e,
+        })
+    }), &mut buf, &mut Plain)?;
+
+    Ok(buf.filled())
+}
+
+fn timed_out() -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, "timed out filling whole buffer")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::time::Duration;
+    #[cfg(all(feature = "libc", unix))]
+    use std::time::Instant;
+    #[cfg(all(feature = "libc", unix))]
+    use crate::TimeoutStatus;
+    #[cfg(all(feature = "libc", unix))]
+    use super::ReadExactPollExt;
+    use super::ReadExactTimeoutExt;
+
+    fn pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        (client, listener.accept().unwrap().0)
+    }
+
+    #[test]
+    fn timeout() {
+        let (mut read, mut write) = pair();
+        let mut buf = [0, 0];
+
+        write.write_all(&[1]).unwrap();
+        let ret = read.read_exact_or_eof_timeout(&mut buf, Duration::from_millis(50));
+        assert_eq!(ret.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(read.read_timeout().unwrap(), None);
+
+        write.write_all(&[2, 3]).unwrap();
+        drop(write);
+        let ret = read.read_exact_or_eof_timeout(&mut buf, Duration::from_secs(5));
+        assert!(ret.unwrap());
+        assert_eq!(buf, [2, 3]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn unix_eof() {
+        let (mut read, write) = std::os::unix::net::UnixStream::pair().unwrap();
+        drop(write);
+
+        assert!(!read.read_exact_or_eof_timeout(&mut [0], Duration::from_secs(5)).unwrap());
+    }
+
+    #[test]
+    #[cfg(all(feature = "libc", unix))]
+    fn deadline() {
+        let (mut read, mut write) = io::pipe().unwrap();
+        let mut buf = [0, 0];
+
+        write.write_all(&[1]).unwrap();
+        let ret = read.read_exact_or_eof_deadline(&mut buf, Instant::now() + Duration::from_millis(50));
+        assert_eq!(ret.unwrap(), TimeoutStatus::TimedOut { filled: 1 });
+
+        write.write_all(&[2, 3]).unwrap();
+        drop(write);
+        let ret = read.read_exact_or_eof_deadline(&mut buf, Instant::now() + Duration::from_secs(5));
+        assert_eq!(ret.unwrap(), TimeoutStatus::Done(true));
+        assert_eq!(buf, [2, 3]);
+        let ret = read.read_exact_or_eof_deadline(&mut buf, Instant::now() + Duration::from_secs(5));
+        assert_eq!(ret.unwrap(), TimeoutStatus::Done(false));
+    }
+
+    #[test]
+    #[cfg(all(feature = "libc", unix))]
+    fn wait() {
+        let (mut read, mut write) = std::os::unix::net::UnixStream::pair().unwrap();
+        read.set_nonblocking(true).unwrap();
+        let mut buf = [0, 0];
+
+        write.write_all(&[1]).unwrap();
+        let ret = read.read_exact_or_eof_wait(&mut buf, Some(Instant::now() + Duration::from_millis(50)));
+        assert_eq!(ret.unwrap(), TimeoutStatus::TimedOut { filled: 1 });
+
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            write.write_all(&[2, 3]).unwrap();
+        });
+        assert_eq!(read.read_exact_or_eof_wait(&mut buf, None).unwrap(), TimeoutStatus::Done(true));
+        assert_eq!(buf, [2, 3]);
+        writer.join().unwrap();
+        assert_eq!(read.read_exact_or_eof_wait(&mut buf, None).unwrap(), TimeoutStatus::Done(false));
+    }
+}
@@ -0,0 +1,89 @@
+//! Classification of unclean TLS closes.
+
+use std::io::{self, Read};
+use crate::source::{fill_source, FillControl, IoSource, SliceBuf};
+use crate::TlsStatus;
+
+/// Returns whether `e` reports that the TLS peer closed the connection
+/// without sending a `close_notify` alert.
+///
+/// Neither rustls nor native-tls give these errors a type of their own, so
+/// they are recognized by the `UnexpectedEof` that rustls returns, and by the
+/// `unexpected eof while reading` reason of OpenSSL 3.
+pub fn is_unclean_close(e: &io::Error) -> bool {
+    let message = e.to_string();
+    match e.kind() {
+        io::ErrorKind::UnexpectedEof => message.contains("close_notify"),
+        _ => message.to_ascii_lowercase().contains("unexpected eof while reading"),
+    }
+}
+
+pub(crate) fn read_exact_or_eof_tls<R: Read + ?Sized>(reader: &mut R, buf: &mut [u8]) -> io::Result<TlsStatus> {
+    let len = buf.len();
+    let mut buf = SliceBuf::new(buf);
+    let mut control = Tls { unclean: false };
+    fill_source(&mut IoSource(reader), &mut buf, &mut control)?;
+
+    match (control.unclean, buf.filled()) {
+        (true, 0) => Ok(TlsStatus::Done(false)),
+        (true, filled) => Ok(TlsStatus::TruncatedTls { filled }),
+        (false, filled) => crate::ReadExactStatus::new(filled, len).into_result().map(TlsStatus::Done),
+    }
+}
+
+/// The fill policy of `read_exact_or_eof_tls`, which ends the fill at an
+/// unclean close, setting `unclean`.
+struct Tls {
+    unclean: bool,
+}
+
+impl<B: ?Sized> FillControl<B, io::Error> for Tls {
+    fn end_on_error(&mut self, e: &io::Error, _: usize) -> bool {
+        self.unclean = is_unclean_close(e);
+        self.unclean
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+    use crate::{ReadExactExt, TlsStatus};
+    use super::is_unclean_close;
+
+    /// Reads its data, then fails as rustls does on an unclean close.
+    struct Unclean(&'static [u8]);
+
+    impl Read for Unclean {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.read(buf)? {
+                0 => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "peer closed connection without sending TLS close_notify")),
+                n => Ok(n),
+            }
+        }
+    }
+
+    #[test]
+    fn classify() {
+        assert!(is_unclean_close(&io::Error::other("error:0A000126:SSL routines::unexpected eof while reading")));
+        assert!(!is_unclean_close(&io::ErrorKind::UnexpectedEof.into()));
+        assert!(!is_unclean_close(&io::ErrorKind::ConnectionReset.into()));
+    }
+
+    #[test]
+    fn boundary() {
+        let mut read = Unclean(&[1, 2, 3]);
+        let mut buf = [0; 2];
+
+        assert_eq!(read.read_exact_or_eof_tls(&mut buf).unwrap(), TlsStatus::Done(true));
+        assert_eq!(read.read_exact_or_eof_tls(&mut buf).unwrap(), TlsStatus::TruncatedTls { filled: 1 });
+
+        let mut read = Unclean(&[1, 2]);
+        assert_eq!(read.read_exact_or_eof_tls(&mut buf).unwrap(), TlsStatus::Done(true));
+        assert_eq!(read.read_exact_or_eof_tls(&mut buf).unwrap(), TlsStatus::Done(false));
+    }
+
+    #[test]
+    fn clean_truncation() {
+        assert!((&[1][..]).read_exact_or_eof_tls(&mut [0; 2]).is_err());
+    }
+}
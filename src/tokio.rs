@@ -0,0 +1,638 @@
+//! Support for `tokio::io::AsyncRead` types.
+
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use ::tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use futures_core::{FusedStream, Stream};
+use crate::async_ext::async_read_exact_ext;
+use crate::{FrameFormat, ReadExactExt, ReadExactOpts, ReadExactState, ReadExactStatus, TimeoutStatus};
+use crate::unexpected_eof;
+
+/// Issues a single read into `buf`.
+fn poll_read_some<R: AsyncRead + ?Sized>(reader: Pin<&mut R>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+    let mut buf = ReadBuf::new(buf);
+    reader.poll_read(cx, &mut buf).map_ok(|()| buf.filled().len())
+}
+
+/// Issues a single write of `buf`.
+fn poll_write_some<W: AsyncWrite + ?Sized>(writer: Pin<&mut W>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+    writer.poll_write(cx, buf)
+}
+
+/// Flushes the writer.
+fn poll_flush<W: AsyncWrite + ?Sized>(writer: Pin<&mut W>, cx: &mut Context) -> Poll<io::Result<()>> {
+    writer.poll_flush(cx)
+}
+
+async_read_exact_ext!("An extension trait that applies to all `tokio::io::AsyncRead` types.");
+
+/// An extension trait for exact reads of `tokio::io::AsyncRead` types with a
+/// timeout.
+#[allow(async_fn_in_trait)]
+pub trait AsyncReadExactTimeoutExt {
+    /// Reads exactly the number of bytes to fill `buf`, or zero, unless
+    /// `timeout` elapses first.
+    ///
+    /// Unlike wrapping `read_exact_or_eof` in `tokio::time::timeout`, which
+    /// drops the bytes already read, the progress is kept in `state`: once
+    /// the timeout elapses this returns `TimeoutStatus::TimedOut` with the
+    /// number of bytes filled so far, and calling this again with the same
+    /// `state` and `buf` continues the read. The same holds if the future is
+    /// dropped before it completes, so this is cancel-safe.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `state` has filled more than `buf.len()` bytes.
+    async fn read_exact_or_eof_timeout(&mut self, state: &mut ReadExactState, buf: &mut [u8], timeout: Duration) -> io::Result<TimeoutStatus>;
+}
+
+impl<T: AsyncRead + Unpin + ?Sized> AsyncReadExactTimeoutExt for T {
+    async fn read_exact_or_eof_timeout(&mut self, state: &mut ReadExactState, buf: &mut [u8], timeout: Duration) -> io::Result<TimeoutStatus> {
+        let mut sleep = pin!(::tokio::time::sleep(timeout));
+
+        poll_fn(|cx| {
+            if let Poll::Ready(ret) = poll_read_exact_or_eof(Pin::new(&mut *self), cx, state, buf) {
+                return Poll::Ready(ret.map(TimeoutStatus::Done))
+            }
+            sleep.as_mut().poll(cx).map(|()| Ok(TimeoutStatus::TimedOut { filled: state.filled() }))
+        }).await
+    }
+}
+
+/// An adapter that reads from a blocking `Read` on tokio's blocking pool.
+///
+/// This allows existing synchronous readers, such as files or the stdout of a
+/// child process, to be used from async code without blocking the runtime.
+/// Each read moves the reader and buffer into a blocking task and back.
+#[derive(Debug)]
+pub struct BlockingReader<R> {
+    reader: Option<R>,
+}
+
+impl<R> BlockingReader<R> {
+    /// Creates a new adapter around `reader`.
+    pub fn new(reader: R) -> Self {
+        BlockingReader {
+            reader: Some(reader),
+        }
+    }
+
+    /// Gets a reference to the underlying reader.
+    ///
+    /// Returns `None` if a read was cancelled before it completed, which
+    /// loses the reader along with the blocking task.
+    pub fn get_ref(&self) -> Option<&R> {
+        self.reader.as_ref()
+    }
+
+    /// Unwraps this adapter, returning the underlying reader.
+    ///
+    /// Returns `None` if a read was cancelled before it completed.
+    pub fn into_inner(self) -> Option<R> {
+        self.reader
+    }
+}
+
+impl<R: io::Read + Send + 'static> BlockingReader<R> {
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// Resolves to `true` upon successful read, or `false` if no data was
+    /// read, in the same manner as `ReadExactExt::read_exact_or_eof`. The
+    /// buffer is returned in either case.
+    ///
+    /// Dropping the returned future before it completes loses the reader, and
+    /// any further reads fail. A panic in the underlying reader is resumed on
+    /// the calling task.
+    pub async fn read_exact_or_eof(&mut self, mut buf: Vec<u8>) -> (io::Result<bool>, Vec<u8>) {
+        let mut reader = match self.reader.take() {
+            Some(reader) => reader,
+            None => return (Err(io::Error::other("blocking reader was lost to a cancelled read")), buf),
+        };
+
+        let task = ::tokio::task::spawn_blocking(move || {
+            let res = reader.read_exact_or_eof(&mut buf);
+            (res, buf, reader)
+        });
+        match task.await {
+            Ok((res, buf, reader)) => {
+                self.reader = Some(reader);
+                (res, buf)
+            },
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
+
+/// An adapter that reads from a tokio `AsyncRead` synchronously, by blocking
+/// on a runtime.
+///
+/// This is the reverse of `BlockingReader`, for synchronous code such as a
+/// plugin or an FFI callback that consumes records from an async transport.
+/// It implements `std::io::Read`, so every method of `ReadExactExt` applies
+/// to it. Each read blocks the calling thread with `Handle::block_on`, which
+/// panics if called from an async context; a worker of a multi-threaded
+/// runtime can wrap it in `tokio::task::block_in_place`.
+#[derive(Debug)]
+pub struct BlockingExact<R> {
+    reader: R,
+    handle: ::tokio::runtime::Handle,
+}
+
+impl<R> BlockingExact<R> {
+    /// Creates a new adapter that drives `reader` on the runtime of `handle`.
+    pub fn new(reader: R, handle: ::tokio::runtime::Handle) -> Self {
+        BlockingExact {
+            reader,
+            handle,
+        }
+    }
+
+    /// Gets a reference to the handle of the runtime that drives the reader.
+    pub fn handle(&self) -> &::tokio::runtime::Handle {
+        &self.handle
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this adapter, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> BlockingExact<R> {
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof`, but blocks on
+    /// the runtime once for the whole read rather than once for each read
+    /// of the underlying reader.
+    pub fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        self.handle.block_on(AsyncReadExactExt::read_exact_or_eof(&mut self.reader, buf))
+    }
+}
+
+impl<R: AsyncRead + Unpin> io::Read for BlockingExact<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let reader = &mut self.reader;
+        self.handle.block_on(poll_fn(|cx| poll_read_some(Pin::new(&mut *reader), cx, buf)))
+    }
+}
+
+/// A reader over byte chunks received from a `tokio::sync::mpsc` channel.
+///
+/// This is the async equivalent of `ChannelReader`. Once every sender has
+/// been dropped and the remaining chunks have been read, the reader is at
+/// EOF.
+#[derive(Debug)]
+pub struct ChannelReader {
+    receiver: ::tokio::sync::mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl ChannelReader {
+    /// Creates a reader over the chunks received by `receiver`.
+    pub fn new(receiver: ::tokio::sync::mpsc::Receiver<Vec<u8>>) -> Self {
+        ChannelReader {
+            receiver,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Gets a reference to the underlying receiver.
+    pub fn get_ref(&self) -> &::tokio::sync::mpsc::Receiver<Vec<u8>> {
+        &self.receiver
+    }
+
+    /// Unwraps this reader, returning the underlying receiver.
+    ///
+    /// Any partially read chunk is lost.
+    pub fn into_inner(self) -> ::tokio::sync::mpsc::Receiver<Vec<u8>> {
+        self.receiver
+    }
+}
+
+impl AsyncRead for ChannelReader {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut ReadBuf) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        while this.pos == this.chunk.len() {
+            match this.receiver.poll_recv(cx) {
+                Poll::Ready(Some(chunk)) => {
+                    this.chunk = chunk;
+                    this.pos = 0;
+                },
+                // every sender is gone
+                Poll::Ready(None) => return Poll::Ready(Ok(())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let n = (this.chunk.len() - this.pos).min(buf.remaining());
+        buf.put_slice(&this.chunk[this.pos..this.pos + n]);
+        this.pos += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// The default maximum payload length of a `FrameStream`, 8 MiB, which
+/// matches tokio-util's `LengthDelimitedCodec`.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 8 * 1024 * 1024;
+
+/// A stream of length-prefixed frames read from a tokio reader.
+///
+/// Each frame is a big-endian `u32` length followed by that many bytes of
+/// payload, as with the default `FrameFormat` and tokio-util's
+/// `LengthDelimitedCodec`. The stream ends at EOF before a length prefix, and
+/// yields an `UnexpectedEof` error if the reader ends partway through a
+/// frame, after which it is terminated.
+///
+/// The reader is only read from while the stream is polled, so a slow
+/// consumer applies backpressure to it.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct FrameStream<R> {
+    reader: R,
+    prefix: [u8; 4],
+    payload: Option<Vec<u8>>,
+    state: ReadExactState,
+    max_len: usize,
+    done: bool,
+}
+
+impl<R> FrameStream<R> {
+    /// Creates a stream of the frames of `reader`, accepting payloads of up
+    /// to `DEFAULT_MAX_FRAME_LEN` bytes.
+    pub fn new(reader: R) -> Self {
+        FrameStream {
+            reader,
+            prefix: [0; 4],
+            payload: None,
+            state: ReadExactState::new(),
+            max_len: DEFAULT_MAX_FRAME_LEN,
+            done: false,
+        }
+    }
+
+    /// Sets the maximum accepted payload length.
+    ///
+    /// Longer frames fail with `InvalidData` before their payload is
+    /// allocated. A payload that cannot be allocated fails with `OutOfMemory`
+    /// rather than aborting.
+    pub fn max_frame_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Unwraps this stream, returning the underlying reader.
+    ///
+    /// Any partially read frame is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> FrameStream<R> {
+    fn poll_frame(&mut self, cx: &mut Context) -> Poll<io::Result<Option<::bytes::Bytes>>> {
+        loop {
+            let payload = match self.payload {
+                Some(ref mut payload) => payload,
+                None => {
+                    if !std::task::ready!(poll_read_exact_or_eof(Pin::new(&mut self.reader), cx, &mut self.state, &mut self.prefix))? {
+                        return Poll::Ready(Ok(None))
+                    }
+                    let len = u32::from_be_bytes(self.prefix) as usize;
+                    if len > self.max_len {
+                        return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, "frame length exceeds maximum")))
+                    }
+                    let mut payload = Vec::new();
+                    if payload.try_reserve_exact(len).is_err() {
+                        return Poll::Ready(Err(io::Error::new(io::ErrorKind::OutOfMemory, "failed to allocate frame")))
+                    }
+                    payload.resize(len, 0);
+                    self.payload = Some(payload);
+                    continue
+                },
+            };
+
+            if !std::task::ready!(poll_read_exact_or_eof(Pin::new(&mut self.reader), cx, &mut self.state, payload))? {
+                return Poll::Ready(Err(unexpected_eof()))
+            }
+            return Poll::Ready(Ok(self.payload.take().map(Into::into)))
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for FrameStream<R> {
+    type Item = io::Result<::bytes::Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None)
+        }
+
+        let ret = std::task::ready!(this.poll_frame(cx)).transpose();
+        this.done = !matches!(ret, Some(Ok(..)));
+        Poll::Ready(ret)
+    }
+}
+
+impl<R: AsyncRead + Unpin> FusedStream for FrameStream<R> {
+    fn is_terminated(&self) -> bool {
+        self.done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll, Waker};
+    use ::tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+    use futures_lite::StreamExt;
+    use std::time::Duration;
+    use crate::{FrameFormat, ReadExactOpts, ReadExactState, ReadExactStatus, TimeoutStatus};
+    use crate::test_util::block_on;
+    use super::{exchange_exact, poll_read_exact_or_eof, AsyncReadExactExt, AsyncReadExactTimeoutExt, BlockingExact, BlockingReader, ChannelReader};
+
+    /// Returns `Pending` before every read, and reads one byte at a time.
+    struct Trickle(bool, &'static [u8]);
+
+    impl AsyncRead for Trickle {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context, buf: &mut ReadBuf) -> Poll<io::Result<()>> {
+            self.0 = !self.0;
+            if self.0 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending
+            }
+
+            if let Some((&byte, rest)) = self.1.split_first() {
+                buf.put_slice(&[byte]);
+                self.1 = rest;
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Fails every read with `ConnectionReset`.
+    struct Reset;
+
+    impl AsyncRead for Reset {
+        fn poll_read(self: Pin<&mut Self>, _: &mut Context, _: &mut ReadBuf) -> Poll<io::Result<()>> {
+            Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()))
+        }
+    }
+
+    /// Reads from the first slice, and writes to the vector.
+    struct Duplex(&'static [u8], Vec<u8>);
+
+    impl AsyncRead for Duplex {
+        fn poll_read(mut self: Pin<&mut Self>, _: &mut Context, buf: &mut ReadBuf) -> Poll<io::Result<()>> {
+            let n = self.0.len().min(buf.remaining());
+            buf.put_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for Duplex {
+        fn poll_write(mut self: Pin<&mut Self>, _: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+            self.1.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn exchange() {
+        let mut stream = Duplex(&[3, 4, 5], Vec::new());
+        let mut buf = [0; 2];
+
+        assert!(block_on(exchange_exact(&mut stream, &[1], &mut buf)).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert!(block_on(exchange_exact(&mut stream, &[2], &mut buf)).is_err());
+        assert!(!block_on(exchange_exact(&mut stream, &[], &mut buf)).unwrap());
+        assert_eq!(stream.1, [1, 2]);
+    }
+
+    #[test]
+    fn eof() {
+        let mut read = &[][..];
+        let mut buf = [0, 0];
+
+        let ret = block_on(read.read_exact_or_eof(&mut buf));
+
+        assert!(!ret.unwrap());
+    }
+
+    #[test]
+    fn ok() {
+        let mut read = &[1, 1, 2][..];
+        let mut buf = [0, 0];
+
+        let ret = block_on(read.read_exact_or_eof(&mut buf));
+
+        assert!(ret.unwrap());
+        assert_eq!(buf, [1, 1]);
+        assert_eq!(read, [2]);
+    }
+
+    #[test]
+    fn unexpected_eof() {
+        let mut read = &[1][..];
+        let mut buf = [0, 0];
+
+        let ret = block_on(read.read_exact_or_eof(&mut buf));
+
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn poll_resume() {
+        let mut read = Trickle(false, &[1, 2, 3]);
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut state = ReadExactState::new();
+        let mut buf = [0, 0];
+
+        assert!(poll_read_exact_or_eof(Pin::new(&mut read), &mut cx, &mut state, &mut buf).is_pending());
+        assert!(poll_read_exact_or_eof(Pin::new(&mut read), &mut cx, &mut state, &mut buf).is_pending());
+        assert_eq!(state.filled(), 1);
+
+        let ret = poll_read_exact_or_eof(Pin::new(&mut read), &mut cx, &mut state, &mut buf);
+        assert!(matches!(ret, Poll::Ready(Ok(true))));
+        assert_eq!(buf, [1, 2]);
+        assert_eq!(state.filled(), 0);
+    }
+
+    #[test]
+    fn exact_chunks() {
+        let mut chunks = Trickle(false, &[1, 2, 3, 4, 5]).exact_chunks::<2>();
+
+        assert_eq!(block_on(chunks.next()).unwrap().unwrap(), [1, 2]);
+        assert_eq!(block_on(chunks.next()).unwrap().unwrap(), [3, 4]);
+        assert!(block_on(chunks.next()).unwrap().is_err());
+        assert!(block_on(chunks.next()).is_none());
+    }
+
+    #[test]
+    fn blocking() {
+        let rt = ::tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let mut read = BlockingReader::new(&[1, 2, 3][..]);
+
+        rt.block_on(async {
+            let (res, buf) = read.read_exact_or_eof(vec![0; 2]).await;
+            assert!(res.unwrap());
+            assert_eq!(buf, [1, 2]);
+            let (res, _) = read.read_exact_or_eof(buf).await;
+            assert!(res.is_err());
+            let (res, _) = read.read_exact_or_eof(vec![0; 2]).await;
+            assert!(!res.unwrap());
+        });
+        assert!(read.into_inner().is_some());
+    }
+
+    #[test]
+    fn blocking_exact() {
+        let rt = ::tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let mut read = BlockingExact::new(Trickle(false, &[1, 2, 3, 4, 5]), rt.handle().clone());
+        let mut buf = [0; 2];
+
+        assert!(read.read_exact_or_eof(&mut buf).unwrap());
+        assert_eq!(buf, [1, 2]);
+        assert!(crate::ReadExactExt::read_exact_or_eof(&mut read, &mut buf).unwrap());
+        assert_eq!(buf, [3, 4]);
+        assert!(read.read_exact_or_eof(&mut buf).is_err());
+        assert!(!read.read_exact_or_eof(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn channel() {
+        let (tx, rx) = ::tokio::sync::mpsc::channel(4);
+        let mut read = ChannelReader::new(rx);
+        let mut buf = [0; 2];
+
+        block_on(async {
+            tx.send(vec![1]).await.unwrap();
+            tx.send(vec![2, 3]).await.unwrap();
+            drop(tx);
+            assert!(read.read_exact_or_eof(&mut buf).await.unwrap());
+            assert_eq!(buf, [1, 2]);
+            assert!(read.read_exact_or_eof(&mut buf).await.is_err());
+            assert!(!read.read_exact_or_eof(&mut buf).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn timeout() {
+        let rt = ::tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let (tx, rx) = ::tokio::sync::mpsc::channel(4);
+        let mut read = ChannelReader::new(rx);
+        let mut state = ReadExactState::new();
+        let mut buf = [0; 2];
+
+        rt.block_on(async {
+            tx.send(vec![1]).await.unwrap();
+            let ret = read.read_exact_or_eof_timeout(&mut state, &mut buf, Duration::from_millis(10)).await;
+            assert_eq!(ret.unwrap(), TimeoutStatus::TimedOut { filled: 1 });
+            tx.send(vec![2]).await.unwrap();
+            let ret = read.read_exact_or_eof_timeout(&mut state, &mut buf, Duration::from_millis(10)).await;
+            assert_eq!(ret.unwrap(), TimeoutStatus::Done(true));
+            assert_eq!(buf, [1, 2]);
+            drop(tx);
+            let ret = read.read_exact_or_eof_timeout(&mut state, &mut buf, Duration::from_millis(10)).await;
+            assert_eq!(ret.unwrap(), TimeoutStatus::Done(false));
+        });
+    }
+
+    #[test]
+    fn with_opts() {
+        let mut buf = [0, 0];
+
+        assert!(block_on(Reset.read_exact_or_eof(&mut buf)).is_err());
+        let opts = ReadExactOpts::new().connection_reset_eof(true);
+        assert!(!block_on(Reset.read_exact_or_eof_with(&opts, &mut buf)).unwrap());
+    }
+
+    #[test]
+    fn status() {
+        let mut read = Trickle(false, &[1]);
+        let mut buf = [0, 0];
+
+        let ret = block_on(read.read_exact_or_eof_status(&mut buf));
+
+        assert_eq!(ret.unwrap(), ReadExactStatus::PartialEof { filled: 1 });
+    }
+
+    #[test]
+    fn array() {
+        let mut read = Trickle(false, &[1, 2]);
+
+        assert_eq!(block_on(read.read_array_or_eof::<2>()).unwrap(), Some([1, 2]));
+        assert_eq!(block_on(read.read_array_or_eof::<2>()).unwrap(), None);
+    }
+
+    #[test]
+    fn frames() {
+        let mut frames = super::FrameStream::new(Trickle(false, &[0, 0, 0, 2, 1, 2, 0, 0, 0, 0, 0, 0, 0, 3, 3]));
+
+        assert_eq!(block_on(frames.next()).unwrap().unwrap(), &[1, 2][..]);
+        assert_eq!(block_on(frames.next()).unwrap().unwrap(), &[][..]);
+        assert_eq!(block_on(frames.next()).unwrap().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert!(block_on(frames.next()).is_none());
+
+        let mut frames = super::FrameStream::new(&[0, 0, 0, 1, 1][..]);
+        assert_eq!(block_on(frames.next()).unwrap().unwrap(), &[1][..]);
+        assert!(block_on(frames.next()).is_none());
+    }
+
+    #[test]
+    fn frame_max_len() {
+        let mut frames = super::FrameStream::new(&[0, 0, 1, 0][..]).max_frame_len(0xff);
+
+        assert_eq!(block_on(frames.next()).unwrap().unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(block_on(frames.next()).is_none());
+
+        let mut frames = super::FrameStream::new(&[0x7f, 0xff, 0xff, 0xff][..]);
+        assert_eq!(block_on(frames.next()).unwrap().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn helpers() {
+        let mut read = Trickle(false, &[0, 0, 0, 2, 1, 2, 3, 4, 5]);
+        let mut buf = [0; 2];
+
+        assert_eq!(block_on(read.read_frame_or_eof(&FrameFormat::default())).unwrap(), Some(vec![1, 2]));
+        assert_eq!(block_on(read.read_u16_be_or_eof()).unwrap(), Some(0x0304));
+        assert_eq!(block_on(read.read_full(&mut buf)).unwrap(), 1);
+        assert_eq!(block_on(read.read_exact_to_vec(1)).unwrap(), None);
+    }
+}
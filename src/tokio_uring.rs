@@ -0,0 +1,143 @@
+//! Support for `tokio_uring` files and sockets.
+//!
+//! `io_uring` operations take ownership of their buffer for the duration of
+//! the read, so these methods consume an owned buffer and return it alongside
+//! the result once the final read completes, in the same manner as
+//! `tokio_uring` itself. The whole of the buffer's capacity is filled, and its
+//! initialized length is advanced over everything that was read.
+//!
+//! Each read is submitted once the previous one completes; `tokio_uring` does
+//! not expose linked submissions, and a short read must be observed before the
+//! remainder can be requested anyway.
+
+use std::future::Future;
+use std::io;
+use ::tokio_uring::buf::{BoundedBuf, IoBufMut, Slice};
+use ::tokio_uring::fs::File;
+use ::tokio_uring::net::{TcpStream, UnixStream};
+use ::tokio_uring::BufResult;
+use crate::ReadExactStatus;
+
+/// An extension trait for `tokio_uring` sockets.
+#[allow(async_fn_in_trait)]
+pub trait AsyncReadExactExt {
+    /// Reads exactly the number of bytes to fill the capacity of `buf`, or
+    /// zero.
+    ///
+    /// Resolves to `true` upon successful read, or `false` if no data was
+    /// read, in the same manner as `ReadExactExt::read_exact_or_eof`. The
+    /// buffer is returned in either case.
+    async fn read_exact_or_eof<B: IoBufMut>(&self, buf: B) -> BufResult<bool, B>;
+}
+
+/// An extension trait for positioned reads of `tokio_uring` files.
+#[allow(async_fn_in_trait)]
+pub trait AsyncReadExactAtExt {
+    /// Reads exactly the number of bytes to fill the capacity of `buf`
+    /// starting at `offset`, or zero.
+    ///
+    /// This behaves like `AsyncReadExactExt::read_exact_or_eof`, except that
+    /// `offset` past the end of the file is treated as EOF, as with
+    /// `ReadExactAtExt::read_exact_at_or_eof`.
+    async fn read_exact_at_or_eof<B: IoBufMut>(&self, buf: B, offset: u64) -> BufResult<bool, B>;
+}
+
+macro_rules! impl_read {
+    ($($ty:ty),*) => {
+        $(
+            impl AsyncReadExactExt for $ty {
+                async fn read_exact_or_eof<B: IoBufMut>(&self, buf: B) -> BufResult<bool, B> {
+                    fill(buf, |slice, _| self.read(slice)).await
+                }
+            }
+        )*
+    };
+}
+
+impl_read!(TcpStream, UnixStream);
+
+impl AsyncReadExactAtExt for File {
+    async fn read_exact_at_or_eof<B: IoBufMut>(&self, buf: B, offset: u64) -> BufResult<bool, B> {
+        fill(buf, |slice, filled| self.read_at(slice, offset + filled as u64)).await
+    }
+}
+
+/// Fills the capacity of `buf` by repeatedly reading into its unfilled tail.
+async fn fill<B, F, Fut>(mut buf: B, mut read: F) -> BufResult<bool, B> where
+    B: IoBufMut,
+    F: FnMut(Slice<B>, usize) -> Fut,
+    Fut: Future<Output = BufResult<usize, Slice<B>>>,
+{
+    let len = buf.bytes_total();
+    let mut filled = 0;
+
+    while filled < len {
+        let (res, slice) = read(buf.slice(filled..), filled).await;
+        buf = slice.into_inner();
+        match res {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return (Err(e), buf),
+        }
+    }
+
+    (ReadExactStatus::new(filled, len).into_result(), buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::os::unix::net;
+    use ::tokio_uring::fs::File;
+    use ::tokio_uring::net::UnixStream;
+    use super::{AsyncReadExactAtExt, AsyncReadExactExt};
+
+    /// Returns a stream that reads `data` and then EOF.
+    fn stream(data: &[u8]) -> net::UnixStream {
+        let (read, mut write) = net::UnixStream::pair().unwrap();
+        write.write_all(data).unwrap();
+        read
+    }
+
+    #[test]
+    fn eof() {
+        let read = stream(&[]);
+        ::tokio_uring::start(async {
+            let read = UnixStream::from_std(read);
+            let (res, buf) = read.read_exact_or_eof(Vec::with_capacity(2)).await;
+            assert!(!res.unwrap());
+            assert!(buf.is_empty());
+        });
+    }
+
+    #[test]
+    fn full() {
+        let read = stream(&[1, 2, 3]);
+        ::tokio_uring::start(async {
+            let read = UnixStream::from_std(read);
+            let (res, buf) = read.read_exact_or_eof(Vec::with_capacity(2)).await;
+            assert!(res.unwrap());
+            assert_eq!(buf, [1, 2]);
+            let (res, buf) = read.read_exact_or_eof(Vec::with_capacity(2)).await;
+            assert!(res.is_err());
+            assert_eq!(buf, [3]);
+        });
+    }
+
+    #[test]
+    fn at() {
+        let path = std::env::temp_dir().join(format!("read_exact-uring-{}", std::process::id()));
+        std::fs::write(&path, [1, 2, 3]).unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        ::tokio_uring::start(async {
+            let file = File::from_std(file);
+            let (res, buf) = file.read_exact_at_or_eof(Vec::with_capacity(2), 1).await;
+            assert!(res.unwrap());
+            assert_eq!(buf, [2, 3]);
+            let (res, _) = file.read_exact_at_or_eof(Vec::with_capacity(2), 3).await;
+            assert!(!res.unwrap());
+        });
+    }
+}
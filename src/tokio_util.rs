@@ -0,0 +1,153 @@
+//! Support for `tokio_util::codec` framing and cancellation.
+
+use std::future::{poll_fn, Future};
+use std::io;
+use std::pin::{pin, Pin};
+use std::task::Poll;
+use ::bytes::{Bytes, BytesMut};
+use ::tokio::io::AsyncRead;
+use ::tokio_util::codec::{Decoder, Encoder};
+use ::tokio_util::sync::CancellationToken;
+use crate::tokio::poll_read_exact_or_eof;
+use crate::{unexpected_eof, CancelStatus, ReadExactState};
+
+/// An extension trait for cancelling the reads of `tokio::io::AsyncRead`
+/// types.
+#[allow(async_fn_in_trait)]
+pub trait AsyncReadExactCancelExt {
+    /// Reads exactly the number of bytes to fill `buf`, or zero, unless
+    /// `token` is cancelled first.
+    ///
+    /// This behaves like `ReadExactExt::read_exact_or_eof_cancellable`, but
+    /// also notices the cancellation while waiting for the reader.
+    async fn read_exact_or_eof_cancellable(&mut self, buf: &mut [u8], token: &CancellationToken) -> io::Result<CancelStatus>;
+}
+
+impl<T: AsyncRead + Unpin + ?Sized> AsyncReadExactCancelExt for T {
+    async fn read_exact_or_eof_cancellable(&mut self, buf: &mut [u8], token: &CancellationToken) -> io::Result<CancelStatus> {
+        let mut state = ReadExactState::new();
+        let mut cancelled = pin!(token.cancelled());
+
+        poll_fn(|cx| {
+            if cancelled.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Ok(CancelStatus::Cancelled { filled: state.filled() }))
+            }
+            poll_read_exact_or_eof(Pin::new(&mut *self), cx, &mut state, buf).map_ok(CancelStatus::Done)
+        }).await
+    }
+}
+
+/// A codec for fixed-size chunks.
+///
+/// Decoding ends at EOF on a chunk boundary, and fails with `UnexpectedEof`
+/// if the stream ends partway through a chunk, in the same manner as
+/// `ReadExactExt::exact_chunks`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ExactCodec {
+    len: usize,
+}
+
+impl ExactCodec {
+    /// Creates a codec for chunks of `len` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is zero.
+    pub const fn new(len: usize) -> Self {
+        assert!(len > 0, "chunk length must be non-zero");
+        ExactCodec {
+            len,
+        }
+    }
+
+    /// Returns the length of each chunk.
+    pub const fn chunk_len(&self) -> usize {
+        self.len
+    }
+}
+
+impl Decoder for ExactCodec {
+    type Item = BytesMut;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<BytesMut>> {
+        if src.len() < self.len {
+            src.reserve(self.len - src.len());
+            return Ok(None)
+        }
+
+        Ok(Some(src.split_to(self.len)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> io::Result<Option<BytesMut>> {
+        match self.decode(src)? {
+            Some(chunk) => Ok(Some(chunk)),
+            None if src.is_empty() => Ok(None),
+            None => Err(unexpected_eof()),
+        }
+    }
+}
+
+impl Encoder<Bytes> for ExactCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Bytes, dst: &mut BytesMut) -> io::Result<()> {
+        if item.len() != self.len {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "chunk length does not match the codec"))
+        }
+
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::bytes::{Bytes, BytesMut};
+    use ::tokio_util::codec::{Decoder, Encoder};
+    use ::tokio_util::sync::CancellationToken;
+    use crate::test_util::block_on;
+    use crate::CancelStatus;
+    use super::{AsyncReadExactCancelExt, ExactCodec};
+
+    #[test]
+    fn decode() {
+        let mut codec = ExactCodec::new(2);
+        let mut src = BytesMut::from(&[1, 2, 3][..]);
+
+        assert_eq!(codec.decode(&mut src).unwrap().unwrap()[..], [1, 2]);
+        assert_eq!(codec.decode(&mut src).unwrap(), None);
+        assert!(codec.decode_eof(&mut src).is_err());
+    }
+
+    #[test]
+    fn decode_eof() {
+        let mut codec = ExactCodec::new(2);
+        let mut src = BytesMut::from(&[1, 2][..]);
+
+        assert_eq!(codec.decode_eof(&mut src).unwrap().unwrap()[..], [1, 2]);
+        assert_eq!(codec.decode_eof(&mut src).unwrap(), None);
+    }
+
+    #[test]
+    fn encode() {
+        let mut codec = ExactCodec::new(2);
+        let mut dst = BytesMut::new();
+
+        codec.encode(Bytes::from_static(&[1, 2]), &mut dst).unwrap();
+        assert!(codec.encode(Bytes::from_static(&[3]), &mut dst).is_err());
+        assert_eq!(dst[..], [1, 2]);
+    }
+
+    #[test]
+    fn cancellable() {
+        let token = CancellationToken::new();
+        let mut buf = [0, 0];
+
+        let ret = block_on((&[1, 2][..]).read_exact_or_eof_cancellable(&mut buf, &token));
+        assert_eq!(ret.unwrap(), CancelStatus::Done(true));
+        token.cancel();
+        let ret = block_on((&[1, 2][..]).read_exact_or_eof_cancellable(&mut buf, &token));
+        assert_eq!(ret.unwrap(), CancelStatus::Cancelled { filled: 0 });
+    }
+}
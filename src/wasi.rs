@@ -0,0 +1,42 @@
+//! Support for WASI preview 2 `wasi::io::streams::InputStream`.
+//!
+//! Input streams report EOF with `stream-error::closed` rather than a zero
+//! length read, which is treated as clean EOF at a record boundary.
+
+use std::io;
+use ::wasi::io::streams::{InputStream, StreamError};
+use crate::ReadExactStatus;
+
+/// An extension trait for `wasi::io::streams::InputStream`.
+pub trait InputStreamExactExt {
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// This blocks until the stream is ready, and returns `false` if the
+    /// stream was closed before any data was read, in the same manner as
+    /// `ReadExactExt::read_exact_or_eof`. A `last-operation-failed` error
+    /// fails with `Other`.
+    fn read_exact_or_eof(&self, buf: &mut [u8]) -> io::Result<bool>;
+}
+
+impl InputStreamExactExt for InputStream {
+    fn read_exact_or_eof(&self, buf: &mut [u8]) -> io::Result<bool> {
+        let len = buf.len();
+        let mut filled = 0;
+
+        while filled < len {
+            match self.blocking_read((len - filled) as u64) {
+                Ok(chunk) if chunk.len() > len - filled => {
+                    return Err(io::Error::other("stream returned more bytes than requested"))
+                },
+                Ok(chunk) => {
+                    buf[filled..filled + chunk.len()].copy_from_slice(&chunk);
+                    filled += chunk.len();
+                },
+                Err(StreamError::Closed) => break,
+                Err(StreamError::LastOperationFailed(e)) => return Err(io::Error::other(e.to_debug_string())),
+            }
+        }
+
+        ReadExactStatus::new(filled, len).into_result()
+    }
+}
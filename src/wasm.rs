@@ -0,0 +1,87 @@
+//! Support for `web_sys::ReadableStream` in the browser.
+//!
+//! Byte streams, such as the body of a `fetch` response, can be read through
+//! a "bring your own buffer" reader, which fills a caller-provided view
+//! rather than returning chunks of arbitrary size.
+
+use std::io;
+use ::js_sys::Uint8Array;
+use ::wasm_bindgen::{JsCast, JsValue};
+use ::wasm_bindgen_futures::JsFuture;
+use ::web_sys::{ReadableStream, ReadableStreamByobReader, ReadableStreamReadResult};
+use crate::ReadExactStatus;
+
+/// An exact reader of a `ReadableStream` byte stream.
+///
+/// This holds the stream's lock until `release` is called.
+#[derive(Debug)]
+pub struct ByobReader {
+    reader: ReadableStreamByobReader,
+}
+
+impl ByobReader {
+    /// Locks `stream` to a new BYOB reader.
+    ///
+    /// Fails if `stream` is not a byte stream, or is already locked.
+    pub fn new(stream: &ReadableStream) -> io::Result<Self> {
+        ReadableStreamByobReader::new(stream)
+            .map(|reader| ByobReader { reader })
+            .map_err(js_error)
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &ReadableStreamByobReader {
+        &self.reader
+    }
+
+    /// Unwraps this reader, returning the underlying reader, which still
+    /// holds the stream's lock.
+    pub fn into_inner(self) -> ReadableStreamByobReader {
+        self.reader
+    }
+
+    /// Releases the stream's lock, so that it can be read by others.
+    pub fn release(self) {
+        self.reader.release_lock()
+    }
+
+    /// Reads exactly the number of bytes to fill `buf`, or zero.
+    ///
+    /// Resolves to `true` upon successful read, or `false` if the stream was
+    /// closed before any data was read, in the same manner as
+    /// `ReadExactExt::read_exact_or_eof`. A stream that errors fails with
+    /// `Other`.
+    pub async fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let len = buf.len();
+        let mut filled = 0;
+        // each read transfers the view's buffer, so the next read must use
+        // the one that is returned
+        let mut view = Uint8Array::new_with_length(len as u32);
+
+        while filled < len {
+            let unfilled = Uint8Array::new_with_byte_offset_and_length(&view.buffer(), filled as u32, (len - filled) as u32);
+            let result: ReadableStreamReadResult = JsFuture::from(self.reader.read_with_array_buffer_view(&unfilled)).await
+                .map_err(js_error)?
+                .unchecked_into();
+            let value = result.get_value();
+            if value.is_undefined() {
+                break
+            }
+
+            let value: Uint8Array = value.unchecked_into();
+            let n = value.byte_length() as usize;
+            value.copy_to(&mut buf[filled..filled + n]);
+            filled += n;
+            if result.get_done().unwrap_or(false) || n == 0 {
+                break
+            }
+            view = Uint8Array::new(&value.buffer());
+        }
+
+        ReadExactStatus::new(filled, len).into_result()
+    }
+}
+
+fn js_error(e: JsValue) -> io::Error {
+    io::Error::other(format!("{:?}", e))
+}
@@ -0,0 +1,166 @@
+use std::io::{self, Write};
+
+/// A buffered writer of fixed-size records.
+///
+/// This is the counterpart of `RecordReader`. Only whole records are
+/// accepted, and they are buffered and written out in batches, so that the
+/// underlying writer never sees part of a record unless a write fails. The
+/// buffer is flushed when the writer is dropped, but errors can then only be
+/// ignored, so `finish` should be called instead.
+#[derive(Debug)]
+pub struct RecordWriter<W: Write> {
+    writer: Option<W>,
+    buf: Vec<u8>,
+    record_len: usize,
+    capacity: usize,
+    records_written: u64,
+}
+
+impl<W: Write> RecordWriter<W> {
+    /// Creates a writer of `record_len` byte records, with a buffer of 64
+    /// records.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `record_len` is zero.
+    pub fn new(writer: W, record_len: usize) -> Self {
+        Self::with_capacity(writer, record_len, 64)
+    }
+
+    /// Creates a writer of `record_len` byte records, with a buffer of
+    /// `records` records.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `record_len` or `records` is zero.
+    pub fn with_capacity(writer: W, record_len: usize, records: usize) -> Self {
+        assert!(record_len > 0 && records > 0, "record length and capacity must be non-zero");
+        let capacity = record_len.checked_mul(records).expect("capacity overflows usize");
+        RecordWriter {
+            writer: Some(writer),
+            buf: Vec::with_capacity(capacity),
+            record_len,
+            capacity,
+            records_written: 0,
+        }
+    }
+
+    /// Returns the length of each record.
+    pub fn record_len(&self) -> usize {
+        self.record_len
+    }
+
+    /// Returns the number of records accepted so far, including those still
+    /// buffered.
+    pub fn records_written(&self) -> u64 {
+        self.records_written
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.writer.as_ref().expect("writer is present until finished")
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    ///
+    /// Writing to it directly may interleave with buffered records.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.writer.as_mut().expect("writer is present until finished")
+    }
+
+    /// Writes a single record.
+    ///
+    /// Fails with `InvalidInput`, without writing anything, if `record` is
+    /// not exactly `record_len` bytes long.
+    pub fn write_record(&mut self, record: &[u8]) -> io::Result<()> {
+        if record.len() != self.record_len {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "record has the wrong length"))
+        }
+        self.write_records(record)
+    }
+
+    /// Writes any number of consecutive records.
+    ///
+    /// Fails with `InvalidInput`, without writing anything, if `records` is
+    /// not a whole number of records. Records that do not fit in the buffer
+    /// are written directly.
+    pub fn write_records(&mut self, records: &[u8]) -> io::Result<()> {
+        if !records.len().is_multiple_of(self.record_len) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "buffer is not a whole number of records"))
+        }
+
+        if self.buf.len() + records.len() > self.capacity {
+            self.flush_buf()?;
+        }
+        if records.len() >= self.capacity {
+            self.get_mut().write_all(records)?;
+        } else {
+            self.buf.extend_from_slice(records);
+        }
+        self.records_written += (records.len() / self.record_len) as u64;
+        Ok(())
+    }
+
+    /// Writes out any buffered records, and flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.flush_buf()?;
+        self.get_mut().flush()
+    }
+
+    /// Flushes the writer, returning the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.writer.take().expect("writer is present until finished"))
+    }
+
+    fn flush_buf(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            let writer = self.writer.as_mut().expect("writer is present until finished");
+            let ret = writer.write_all(&self.buf);
+            self.buf.clear();
+            ret?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for RecordWriter<W> {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            let _ = self.flush_buf();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use super::RecordWriter;
+
+    #[test]
+    fn records() {
+        let mut writer = RecordWriter::with_capacity(Vec::new(), 2, 2);
+
+        writer.write_record(&[1, 2]).unwrap();
+        assert!(writer.get_ref().is_empty());
+        assert_eq!(writer.write_record(&[1]).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(writer.write_records(&[1, 2, 3]).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        writer.write_record(&[3, 4]).unwrap();
+        writer.write_records(&[5, 6, 7, 8, 9, 10]).unwrap();
+        assert_eq!(writer.get_ref(), &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        writer.write_record(&[11, 12]).unwrap();
+        assert_eq!(writer.records_written(), 6);
+        assert_eq!(writer.finish().unwrap().len(), 12);
+    }
+
+    #[test]
+    fn drop_flushes() {
+        let mut out = Vec::new();
+        {
+            let mut writer = RecordWriter::new(&mut out, 1);
+            writer.write_record(&[1]).unwrap();
+        }
+
+        assert_eq!(out, [1]);
+    }
+}